@@ -45,11 +45,11 @@ impl AES {
             return Err("Invalid IV length");
         }
 
-        if plain.len()%16!=0 {
+        if plain.len() % 16 != 0 {
             return Err("Plain text must be padded in 16-byte units");
         }
 
-        if plain.len()==0 {
+        if plain.is_empty() {
             return Err("Empty plain text");
         }
 
@@ -82,7 +82,7 @@ impl AES {
                 },
                 CipherMode::CFB=>{
                     if i==0 {
-                        block = self.encrypt_block(key,&iv);
+                        block = self.encrypt_block(key,iv);
                     } else {
                         block = self.encrypt_block(key,&cipher_block);
                     }
@@ -93,7 +93,7 @@ impl AES {
                 },
                 CipherMode::OFB=>{
                     if i==0 {
-                        block=*iv;
+                        block = *iv;
                     } else {
                         block = out_block;
                     }
@@ -105,20 +105,20 @@ impl AES {
                 },
                 CipherMode::CTR=>{
                     if i==0 {
-                        nonce=*iv;
+                        nonce = *iv;
                     }
                     block = self.encrypt_block(key,&nonce);
                     for j in 0..16 {
                         cipher_block[j]=block[j]^plain_block[j];
                     }
 
-                    // increment nonce
-                    for i in 0..16 {
-                        if nonce[i]!=0xFF {
-                            nonce[i]+=1;
+                    // increment nonce as a big-endian counter
+                    for v in nonce.iter_mut().rev() {
+                        if *v!=0xFF {
+                            *v+=1;
                             break;
                         } else {
-                            nonce[i]=0;
+                            *v=0;
                         }
                     }
                 },
@@ -139,7 +139,7 @@ impl AES {
             return Err("Invalid IV length");
         }
 
-        if cipher.len()%16!=0 {
+        if cipher.len() % 16 != 0 {
             return Err("Invalid ciphertext length");
         }
         
@@ -149,6 +149,8 @@ impl AES {
         let mut block: [u8;16];
         let mut out_block: [u8;16]=[0;16];
         let mut nonce: [u8;16]=[0;16];
+        // the previous cipher text block, chained in CBC and CFB modes
+        let mut prev_block: [u8;16]=*iv;
         
         for i in 0..cipher.len()/16 {
             cipher_block = cipher[16*i..16*(i+1)].try_into().unwrap();
@@ -160,30 +162,20 @@ impl AES {
                 CipherMode::CBC=>{
                     block = self.decrypt_block(key,&cipher_block);
 
-                    if i==0 {
-                        for j in 0..16 {
-                            plain_block[j]=block[j]^iv[j];
-                        }
-                    } else {
-                        for j in 0..16 {
-                            plain_block[j]=block[j]^cipher_block[j];
-                        }
+                    for j in 0..16 {
+                        plain_block[j]=block[j]^prev_block[j];
                     }
                 },
                 CipherMode::CFB=>{
-                    if i==0 {
-                        block = self.encrypt_block(key,&iv);
-                    } else {
-                        block = self.encrypt_block(key,&cipher_block);
-                    }
-                    
+                    block = self.encrypt_block(key,&prev_block);
+
                     for j in 0..16 {
                         plain_block[j]=block[j]^cipher_block[j];
                     }
                 },
                 CipherMode::OFB=>{
                     if i==0 {
-                        block=*iv;
+                        block = *iv;
                     } else {
                         block = out_block;
                     }
@@ -195,26 +187,27 @@ impl AES {
                 },
                 CipherMode::CTR=>{
                     if i==0 {
-                        nonce=*iv;
+                        nonce = *iv;
                     }
                     block = self.encrypt_block(key,&nonce);
                     for j in 0..16 {
                         plain_block[j]=block[j]^cipher_block[j];
                     }
 
-                    // increment nonce
-                    for i in 0..16 {
-                        if nonce[i]!=0xFF {
-                            nonce[i]+=1;
+                    // increment nonce as a big-endian counter
+                    for v in nonce.iter_mut().rev() {
+                        if *v!=0xFF {
+                            *v+=1;
                             break;
                         } else {
-                            nonce[i]=0;
+                            *v=0;
                         }
                     }
                 },
             }
 
             plain.extend_from_slice(&plain_block);
+            prev_block = cipher_block;
         }
 
         Ok(plain)
@@ -226,10 +219,10 @@ impl AES {
         let mut state: [[u8; 4]; 4] = [[0; 4]; 4];
 
         for (i,v) in input.iter().enumerate() {
-            state[i%4][i/4]=*v;
+            state[i%4][i/4] = *v;
         }
 
-        let rkey = self.expand_key(&key);
+        let rkey = self.expand_key(key);
         self.add_roundkey(&mut state,&rkey,0);
         for i in 1..self.nr {
             for row in state.iter_mut() {
@@ -249,16 +242,11 @@ impl AES {
         state = AES::shift_rows(&state);
         self.add_roundkey(&mut state,&rkey,self.nr);
 
-        let mut i=0;
-        let mut j=0;
         let mut output: [u8; 16] = [0; 16];
-        for v1 in state.iter() {
-            for v2 in v1.iter() {
-                output[4*i+j]=*v2;
-                i+=1;
+        for (j,v1) in state.iter().enumerate() {
+            for (i,v2) in v1.iter().enumerate() {
+                output[4*i+j] = *v2;
             }
-            i=0;
-            j+=1;
         }
 
         output
@@ -270,10 +258,10 @@ impl AES {
         let mut state: [[u8; 4]; 4] = [[0; 4]; 4];
 
         for (i,v) in input.iter().enumerate() {
-            state[i%4][i/4]=*v;
+            state[i%4][i/4] = *v;
         }
 
-        let rkey = self.expand_key(&key);
+        let rkey = self.expand_key(key);
         self.add_roundkey(&mut state,&rkey,self.nr);
         for i in (1..self.nr).rev() {
             state = AES::inv_shift_rows(&state);
@@ -293,16 +281,11 @@ impl AES {
         }
         self.add_roundkey(&mut state,&rkey,0);
 
-        let mut i=0;
-        let mut j=0;
         let mut output: [u8; 16] = [0; 16];
-        for v1 in state.iter() {
-            for v2 in v1.iter() {
-                output[4*i+j]=*v2;
-                i+=1;
+        for (j,v1) in state.iter().enumerate() {
+            for (i,v2) in v1.iter().enumerate() {
+                output[4*i+j] = *v2;
             }
-            i=0;
-            j+=1;
         }
 
         output
@@ -348,13 +331,13 @@ impl AES {
     }
 
     fn rot_word(w: u32) -> u32 {
-        (w<<8)+(w>>24)
+        w.rotate_right(8)
     }
 
     fn sub_word(w: u32) -> u32 {
         let mut r:u32 = 0;
         for i in 0..4 {
-            r |= (AES::sub_bytes((w>>i*8 & 0xFF) as u8) as u32) << i*8;
+            r |= (AES::sub_bytes((w>>(i*8) & 0xFF) as u8) as u32) << (i*8);
         }
         r
     }
@@ -501,4 +484,56 @@ impl AES {
     fn xtime(b:u8) -> u8 {
         (b<<1) ^ (if b&0x80!=0 {0x1b} else {0x00})
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // FIPS-197 appendix C
+    #[test]
+    fn test_encrypt_block() {
+        let key: Vec<u8> = (0..32).collect();
+        let plain: [u8;16] = [0x00,0x11,0x22,0x33,0x44,0x55,0x66,0x77,0x88,0x99,0xaa,0xbb,0xcc,0xdd,0xee,0xff];
+        let expected: [(KeyLength,[u8;16]);3] = [
+            (KeyLength::KL128, [0x69,0xc4,0xe0,0xd8,0x6a,0x7b,0x04,0x30,0xd8,0xcd,0xb7,0x80,0x70,0xb4,0xc5,0x5a]),
+            (KeyLength::KL192, [0xdd,0xa9,0x7c,0xa4,0x86,0x4c,0xdf,0xe0,0x6e,0xaf,0x70,0xa0,0xec,0x0d,0x71,0x91]),
+            (KeyLength::KL256, [0x8e,0xa2,0xb7,0xca,0x51,0x67,0x45,0xbf,0xea,0xfc,0x49,0x90,0x4b,0x49,0x60,0x89])];
+
+        for (keylen, cipher) in expected.iter() {
+            let aes = AES::new(*keylen, CipherMode::EBC);
+            let key = &key[..*keylen as usize/8];
+            assert_eq!(aes.encrypt_block(key, &plain),*cipher);
+            assert_eq!(aes.decrypt_block(key, cipher),plain);
+        }
+    }
+
+    // SP 800-38A F.2.5, F.3.17 and F.5.5: chaining and counters over several blocks
+    #[test]
+    fn test_modes() {
+        use crate::utility::codec::hex;
+        let key = hex::decode("603deb1015ca71be2b73aef0857d77811f352c073b6108d72d9810a30914dff4").unwrap();
+        let iv: [u8;16] = [0x00,0x01,0x02,0x03,0x04,0x05,0x06,0x07,0x08,0x09,0x0a,0x0b,0x0c,0x0d,0x0e,0x0f];
+        let plain = hex::decode("6bc1bee22e409f96e93d7e117393172aae2d8a571e03ac9c9eb76fac45af8e51\
+            30c81c46a35ce411e5fbc1191a0a52eff69f2445df4f9b17ad2b417be66c3710").unwrap();
+        let expected = [
+            (CipherMode::CBC, "f58c4c04d6e5f1ba779eabfb5f7bfbd69cfc4e967edb808d679f777bc6702c7d\
+                39f23369a9d9bacfa530e26304231461b2eb05e2c39be9fcda6c19078c6a9d1b"),
+            (CipherMode::CFB, "dc7e84bfda79164b7ecd8486985d386039ffed143b28b1c832113c6331e5407b\
+                df10132415e54b92a13ed0a8267ae2f975a385741ab9cef82031623d55b1e471")];
+
+        for (mode, cipher) in expected.iter() {
+            let aes = AES::new(KeyLength::KL256, *mode);
+            let cipher = hex::decode(cipher).unwrap();
+            assert_eq!(aes.encrypt(&key, &iv, &plain).unwrap(),cipher);
+            assert_eq!(aes.decrypt(&key, &iv, &cipher).unwrap(),plain);
+        }
+
+        let aes = AES::new(KeyLength::KL256, CipherMode::CTR);
+        let counter: [u8;16] = [0xf0,0xf1,0xf2,0xf3,0xf4,0xf5,0xf6,0xf7,0xf8,0xf9,0xfa,0xfb,0xfc,0xfd,0xfe,0xff];
+        let cipher = hex::decode("601ec313775789a5b7a7f504bbf3d228f443e3ca4d62b59aca84e990cacaf5c5\
+            2b0930daa23de94ce87017ba2d84988ddfc9c58db67aada613c2dd08457941a6").unwrap();
+        assert_eq!(aes.encrypt(&key, &counter, &plain).unwrap(),cipher);
+        assert_eq!(aes.decrypt(&key, &counter, &cipher).unwrap(),plain);
+    }
+}
@@ -0,0 +1,115 @@
+// BLAKE2b (RFC 7693) with optional key and 1 to 64 byte output
+use std::convert::TryInto;
+
+const IV: [u64;8] = [
+    0x6a09e667f3bcc908, 0xbb67ae8584caa73b, 0x3c6ef372fe94f82b, 0xa54ff53a5f1d36f1,
+    0x510e527fade682d1, 0x9b05688c2b3e6c1f, 0x1f83d9abfb41bd6b, 0x5be0cd19137e2179];
+
+const SIGMA: [[usize;16];10] = [
+    [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15],
+    [14, 10, 4, 8, 9, 15, 13, 6, 1, 12, 0, 2, 11, 7, 5, 3],
+    [11, 8, 12, 0, 5, 2, 15, 13, 10, 14, 3, 6, 7, 1, 9, 4],
+    [7, 9, 3, 1, 13, 12, 11, 14, 2, 6, 5, 10, 4, 0, 15, 8],
+    [9, 0, 5, 7, 2, 4, 10, 15, 14, 1, 11, 12, 6, 8, 3, 13],
+    [2, 12, 6, 10, 0, 11, 8, 3, 4, 13, 7, 5, 15, 14, 1, 9],
+    [12, 5, 1, 15, 14, 13, 4, 10, 0, 7, 6, 3, 9, 2, 8, 11],
+    [13, 11, 7, 14, 12, 1, 3, 9, 5, 0, 15, 4, 8, 6, 2, 10],
+    [6, 15, 14, 9, 11, 3, 0, 8, 12, 2, 13, 7, 1, 4, 10, 5],
+    [10, 2, 8, 4, 7, 6, 1, 5, 15, 11, 9, 14, 3, 12, 13, 0]];
+
+fn mix(v: &mut [u64;16], a: usize, b: usize, c: usize, d: usize, x: u64, y: u64) {
+    v[a] = v[a].wrapping_add(v[b]).wrapping_add(x);
+    v[d] = (v[d] ^ v[a]).rotate_right(32);
+    v[c] = v[c].wrapping_add(v[d]);
+    v[b] = (v[b] ^ v[c]).rotate_right(24);
+    v[a] = v[a].wrapping_add(v[b]).wrapping_add(y);
+    v[d] = (v[d] ^ v[a]).rotate_right(16);
+    v[c] = v[c].wrapping_add(v[d]);
+    v[b] = (v[b] ^ v[c]).rotate_right(63);
+}
+
+fn compress(h: &mut [u64;8], block: &[u8], t: u128, last: bool) {
+    let m: Vec<u64> = block.chunks(8).map(|w| u64::from_le_bytes(w.try_into().unwrap())).collect();
+    let mut v = [0u64;16];
+    v[..8].copy_from_slice(h);
+    v[8..].copy_from_slice(&IV);
+    v[12] ^= t as u64;
+    v[13] ^= (t >> 64) as u64;
+    if last {
+        v[14] = !v[14];
+    }
+    for i in 0..12 {
+        let s = &SIGMA[i % 10];
+        mix(&mut v, 0, 4, 8, 12, m[s[0]], m[s[1]]);
+        mix(&mut v, 1, 5, 9, 13, m[s[2]], m[s[3]]);
+        mix(&mut v, 2, 6, 10, 14, m[s[4]], m[s[5]]);
+        mix(&mut v, 3, 7, 11, 15, m[s[6]], m[s[7]]);
+        mix(&mut v, 0, 5, 10, 15, m[s[8]], m[s[9]]);
+        mix(&mut v, 1, 6, 11, 12, m[s[10]], m[s[11]]);
+        mix(&mut v, 2, 7, 8, 13, m[s[12]], m[s[13]]);
+        mix(&mut v, 3, 4, 9, 14, m[s[14]], m[s[15]]);
+    }
+    for i in 0..8 {
+        h[i] ^= v[i] ^ v[i + 8];
+    }
+}
+
+/// BLAKE2b of `input` with a key of up to 64 bytes (empty for none), `out_len` bytes long.
+///
+/// Panics if `out_len` is not in 1..=64 or the key is longer than 64 bytes.
+pub fn blake2b(input: &[u8], key: &[u8], out_len: usize) -> Vec<u8> {
+    assert!((1..=64).contains(&out_len), "output length must be 1 to 64 bytes");
+    assert!(key.len() <= 64, "key longer than 64 bytes");
+    let mut h = IV;
+    h[0] ^= 0x01010000 ^ (key.len() as u64) << 8 ^ out_len as u64;
+
+    let mut msg = Vec::with_capacity(input.len() + 128);
+    if !key.is_empty() {
+        msg.extend_from_slice(key);
+        msg.resize(128, 0);
+    }
+    msg.extend_from_slice(input);
+    let len = msg.len();
+    // the last block is processed separately, zero-padded, even when the input is empty
+    let last = if len == 0 {0} else {(len - 1) / 128 * 128};
+    for (i, block) in msg[..last].chunks(128).enumerate() {
+        compress(&mut h, block, (i as u128 + 1) * 128, false);
+    }
+    let mut block = [0u8;128];
+    block[..len - last].copy_from_slice(&msg[last..]);
+    compress(&mut h, &block, len as u128, true);
+
+    h.iter().flat_map(|w| w.to_le_bytes().to_vec()).take(out_len).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utility::codec::hex;
+
+    #[test]
+    fn test_blake2b() {
+        // RFC 7693 appendix A, then values from Python's hashlib
+        assert_eq!(blake2b(b"abc", &[], 64),hex::decode(
+            "ba80a53f981c4d0d6a2797b69f12f6e94c212f14685ac4b74b12bb6fdbffa2d1\
+             7d87c5392aab792dc252d5de4533cc9518d38aa8dbf1925ab92386edd4009923").unwrap());
+        assert_eq!(blake2b(b"", &[], 64),hex::decode(
+            "786a02f742015903c6c6fd852552d272912f4740e15847618a86e217f71f5419\
+             d25e1031afee585313896444934eb04b903a685b1448b755d56f701afe9be2ce").unwrap());
+        assert_eq!(blake2b(&[0x61;128], &[], 32),hex::decode(
+            "ae2aa48507885c4c950fb809b2076f959cde9f8ea6da260d9a3587df33dac450").unwrap());
+        assert_eq!(blake2b(&[0x61;129], &[], 20),hex::decode("eeff408d65ecf3235b2586586d331fea9014b8d8").unwrap());
+    }
+
+    #[test]
+    fn test_keyed() {
+        // the first and last keyed vectors of the BLAKE2 reference test suite
+        let key: Vec<u8> = (0..64).collect();
+        assert_eq!(blake2b(b"", &key, 64),hex::decode(
+            "10ebb67700b1868efb4417987acf4690ae9d972fb7a590c2f02871799aaa4786\
+             b5e996e8f0f4eb981fc214b005f42d2ff4233499391653df7aefcbc13fc51568").unwrap());
+        assert_eq!(blake2b(&(0..=254).collect::<Vec<u8>>(), &key, 64),hex::decode(
+            "142709d62e28fcccd0af97fad0f8465b971e82201dc51070faa0372aa43e9248\
+             4be1c1e73ba10906d5d1853db6a4106e0a7bf9800d373d6dee2d46d62ef2a461").unwrap());
+    }
+}
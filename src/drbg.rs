@@ -0,0 +1,704 @@
+// Deterministic random bit generators (NIST SP 800-90A Rev. 1)
+use std::convert::TryInto;
+use rand::RngCore;
+use rand::rngs::OsRng;
+use crate::aes::{AES,CipherMode,KeyLength};
+use crate::hmac::hmac_sha256;
+use crate::sha256::sha256;
+
+/// Source of fresh entropy for forced reseeds and prediction resistance.
+pub type EntropySource = Box<dyn FnMut(&mut [u8]) + Send>;
+
+const MAX_REQUEST_BYTES: usize = 1 << 16;
+const RESEED_INTERVAL: u64 = 1 << 48;
+
+/// Common interface of the SP 800-90A generators.
+pub trait Drbg {
+    fn reseed(&mut self, entropy: &[u8], additional: &[u8]) -> Result<(),&'static str>;
+
+    /// Fills `out`, reseeding first from the entropy source when prediction resistance
+    /// is enabled or the reseed interval has been exceeded.
+    fn generate(&mut self, out: &mut [u8], additional: &[u8]) -> Result<(),&'static str>;
+}
+
+fn os_entropy() -> EntropySource {
+    Box::new(|buf: &mut [u8]| OsRng.fill_bytes(buf))
+}
+
+// Reseed bookkeeping shared by all generators
+struct Control {
+    reseed_counter: u64,
+    reseed_interval: u64,
+    prediction_resistance: bool,
+    entropy: EntropySource,
+}
+
+impl Control {
+    fn new() -> Control {
+        Control {reseed_counter: 1, reseed_interval: RESEED_INTERVAL, prediction_resistance: false, entropy: os_entropy()}
+    }
+
+    // Fresh entropy if this generate call has to reseed first
+    fn reseed_entropy(&mut self, len: usize) -> Option<Vec<u8>> {
+        if self.prediction_resistance || self.reseed_counter > self.reseed_interval {
+            let mut e = vec![0;len];
+            (self.entropy)(&mut e);
+            Some(e)
+        } else {
+            None
+        }
+    }
+}
+
+/// HMAC_DRBG with HMAC-SHA256
+pub struct HmacDrbg {
+    k: [u8;32],
+    v: [u8;32],
+    ctl: Control,
+}
+
+impl HmacDrbg {
+    const SECURITY_STRENGTH: usize = 32;
+
+    /// Instantiates from OS entropy.
+    pub fn new(personalization: &[u8]) -> HmacDrbg {
+        let mut entropy = [0;HmacDrbg::SECURITY_STRENGTH];
+        let mut nonce = [0;HmacDrbg::SECURITY_STRENGTH/2];
+        OsRng.fill_bytes(&mut entropy);
+        OsRng.fill_bytes(&mut nonce);
+        HmacDrbg::instantiate(&entropy, &nonce, personalization).unwrap()
+    }
+
+    pub fn instantiate(entropy: &[u8], nonce: &[u8], personalization: &[u8]) -> Result<HmacDrbg,&'static str> {
+        if entropy.len() < HmacDrbg::SECURITY_STRENGTH {
+            return Err("Insufficient entropy");
+        }
+
+        let mut drbg = HmacDrbg {k: [0;32], v: [1;32], ctl: Control::new()};
+        drbg.update(&[entropy, nonce, personalization].concat());
+        Ok(drbg)
+    }
+
+    pub fn with_prediction_resistance(mut self, enabled: bool) -> Self {
+        self.ctl.prediction_resistance = enabled;
+        self
+    }
+
+    pub fn with_reseed_interval(mut self, interval: u64) -> Self {
+        self.ctl.reseed_interval = interval;
+        self
+    }
+
+    pub fn with_entropy_source(mut self, source: EntropySource) -> Self {
+        self.ctl.entropy = source;
+        self
+    }
+
+    fn update(&mut self, provided: &[u8]) {
+        self.k = hmac_sha256(&self.k, &[&self.v[..], &[0x00], provided].concat());
+        self.v = hmac_sha256(&self.k, &self.v);
+        if provided.is_empty() {
+            return;
+        }
+        self.k = hmac_sha256(&self.k, &[&self.v[..], &[0x01], provided].concat());
+        self.v = hmac_sha256(&self.k, &self.v);
+    }
+}
+
+impl Drbg for HmacDrbg {
+    fn reseed(&mut self, entropy: &[u8], additional: &[u8]) -> Result<(),&'static str> {
+        if entropy.len() < HmacDrbg::SECURITY_STRENGTH {
+            return Err("Insufficient entropy");
+        }
+
+        self.update(&[entropy, additional].concat());
+        self.ctl.reseed_counter = 1;
+        Ok(())
+    }
+
+    fn generate(&mut self, out: &mut [u8], additional: &[u8]) -> Result<(),&'static str> {
+        if out.len() > MAX_REQUEST_BYTES {
+            return Err("Too many bytes requested");
+        }
+
+        let mut additional = additional;
+        if let Some(e) = self.ctl.reseed_entropy(HmacDrbg::SECURITY_STRENGTH) {
+            self.reseed(&e, additional)?;
+            additional = &[];
+        }
+
+        if !additional.is_empty() {
+            self.update(additional);
+        }
+        for chunk in out.chunks_mut(32) {
+            self.v = hmac_sha256(&self.k, &self.v);
+            chunk.copy_from_slice(&self.v[..chunk.len()]);
+        }
+        self.update(additional);
+        self.ctl.reseed_counter += 1;
+
+        Ok(())
+    }
+}
+
+/// Hash_DRBG with SHA-256
+pub struct HashDrbg {
+    v: [u8;HashDrbg::SEED_LEN],
+    c: [u8;HashDrbg::SEED_LEN],
+    ctl: Control,
+}
+
+impl HashDrbg {
+    const SECURITY_STRENGTH: usize = 32;
+    const SEED_LEN: usize = 440/8;
+
+    /// Instantiates from OS entropy.
+    pub fn new(personalization: &[u8]) -> HashDrbg {
+        let mut entropy = [0;HashDrbg::SECURITY_STRENGTH];
+        let mut nonce = [0;HashDrbg::SECURITY_STRENGTH/2];
+        OsRng.fill_bytes(&mut entropy);
+        OsRng.fill_bytes(&mut nonce);
+        HashDrbg::instantiate(&entropy, &nonce, personalization).unwrap()
+    }
+
+    pub fn instantiate(entropy: &[u8], nonce: &[u8], personalization: &[u8]) -> Result<HashDrbg,&'static str> {
+        if entropy.len() < HashDrbg::SECURITY_STRENGTH {
+            return Err("Insufficient entropy");
+        }
+
+        let v = HashDrbg::hash_df(&[entropy, nonce, personalization].concat());
+        let c = HashDrbg::hash_df(&[&[0x00], &v[..]].concat());
+        Ok(HashDrbg {v, c, ctl: Control::new()})
+    }
+
+    pub fn with_prediction_resistance(mut self, enabled: bool) -> Self {
+        self.ctl.prediction_resistance = enabled;
+        self
+    }
+
+    pub fn with_reseed_interval(mut self, interval: u64) -> Self {
+        self.ctl.reseed_interval = interval;
+        self
+    }
+
+    pub fn with_entropy_source(mut self, source: EntropySource) -> Self {
+        self.ctl.entropy = source;
+        self
+    }
+
+    fn hash_df(input: &[u8]) -> [u8;HashDrbg::SEED_LEN] {
+        let mut out = [0;HashDrbg::SEED_LEN];
+        let bits = (HashDrbg::SEED_LEN as u32 * 8).to_be_bytes();
+
+        for (i, chunk) in out.chunks_mut(32).enumerate() {
+            let h = sha256(&[&[i as u8 + 1], &bits[..], input].concat());
+            chunk.copy_from_slice(&h[..chunk.len()]);
+        }
+
+        out
+    }
+
+    // v = (v + x) mod 2^seedlen, with x right-aligned
+    fn add(v: &mut [u8;HashDrbg::SEED_LEN], x: &[u8]) {
+        let mut carry = 0u16;
+        for i in 0..HashDrbg::SEED_LEN {
+            let a = v[HashDrbg::SEED_LEN-1-i] as u16;
+            let b = if i < x.len() {x[x.len()-1-i] as u16} else {0};
+            let s = a + b + carry;
+            v[HashDrbg::SEED_LEN-1-i] = s as u8;
+            carry = s >> 8;
+        }
+    }
+}
+
+impl Drbg for HashDrbg {
+    fn reseed(&mut self, entropy: &[u8], additional: &[u8]) -> Result<(),&'static str> {
+        if entropy.len() < HashDrbg::SECURITY_STRENGTH {
+            return Err("Insufficient entropy");
+        }
+
+        self.v = HashDrbg::hash_df(&[&[0x01], &self.v[..], entropy, additional].concat());
+        self.c = HashDrbg::hash_df(&[&[0x00], &self.v[..]].concat());
+        self.ctl.reseed_counter = 1;
+        Ok(())
+    }
+
+    fn generate(&mut self, out: &mut [u8], additional: &[u8]) -> Result<(),&'static str> {
+        if out.len() > MAX_REQUEST_BYTES {
+            return Err("Too many bytes requested");
+        }
+
+        let mut additional = additional;
+        if let Some(e) = self.ctl.reseed_entropy(HashDrbg::SECURITY_STRENGTH) {
+            self.reseed(&e, additional)?;
+            additional = &[];
+        }
+
+        if !additional.is_empty() {
+            let w = sha256(&[&[0x02], &self.v[..], additional].concat());
+            HashDrbg::add(&mut self.v, &w);
+        }
+
+        let mut data = self.v;
+        for chunk in out.chunks_mut(32) {
+            let h = sha256(&data);
+            chunk.copy_from_slice(&h[..chunk.len()]);
+            HashDrbg::add(&mut data, &[1]);
+        }
+
+        let h = sha256(&[&[0x03], &self.v[..]].concat());
+        let c = self.c;
+        HashDrbg::add(&mut self.v, &h);
+        HashDrbg::add(&mut self.v, &c);
+        HashDrbg::add(&mut self.v, &self.ctl.reseed_counter.to_be_bytes());
+        self.ctl.reseed_counter += 1;
+
+        Ok(())
+    }
+}
+
+/// CTR_DRBG with AES, with or without the block cipher derivation function
+pub struct CtrDrbg {
+    aes: AES,
+    key: Vec<u8>,
+    v: [u8;16],
+    use_df: bool,
+    ctl: Control,
+}
+
+impl CtrDrbg {
+    /// Instantiates from OS entropy, using the derivation function.
+    pub fn new(keylen: KeyLength, personalization: &[u8]) -> CtrDrbg {
+        let strength = keylen as usize/8;
+        let mut entropy = vec![0;strength];
+        let mut nonce = vec![0;strength/2];
+        OsRng.fill_bytes(&mut entropy);
+        OsRng.fill_bytes(&mut nonce);
+        CtrDrbg::instantiate(keylen, true, &entropy, &nonce, personalization).unwrap()
+    }
+
+    /// Without the derivation function the entropy input must be exactly seedlen bytes
+    /// (key length + 16), no nonce is used and the personalization string is at most seedlen bytes.
+    pub fn instantiate(keylen: KeyLength, use_df: bool, entropy: &[u8], nonce: &[u8], personalization: &[u8]) -> Result<CtrDrbg,&'static str> {
+        let klen = keylen as usize/8;
+        let mut drbg = CtrDrbg {
+            aes: AES::new(keylen, CipherMode::EBC),
+            key: vec![0;klen],
+            v: [0;16],
+            use_df,
+            ctl: Control::new(),
+        };
+
+        let seed = if use_df {
+            if entropy.len() < klen {
+                return Err("Insufficient entropy");
+            }
+            drbg.df(&[entropy, nonce, personalization].concat())
+        } else {
+            drbg.xor_seed(entropy, personalization)?
+        };
+        drbg.update(&seed);
+
+        Ok(drbg)
+    }
+
+    pub fn with_prediction_resistance(mut self, enabled: bool) -> Self {
+        self.ctl.prediction_resistance = enabled;
+        self
+    }
+
+    pub fn with_reseed_interval(mut self, interval: u64) -> Self {
+        self.ctl.reseed_interval = interval;
+        self
+    }
+
+    pub fn with_entropy_source(mut self, source: EntropySource) -> Self {
+        self.ctl.entropy = source;
+        self
+    }
+
+    fn seed_len(&self) -> usize {
+        self.key.len() + 16
+    }
+
+    fn entropy_len(&self) -> usize {
+        if self.use_df {
+            self.key.len()
+        } else {
+            self.seed_len()
+        }
+    }
+
+    fn increment(v: &mut [u8;16]) {
+        for x in v.iter_mut().rev() {
+            *x = x.wrapping_add(1);
+            if *x != 0 {
+                break;
+            }
+        }
+    }
+
+    fn update(&mut self, provided: &[u8]) {
+        let mut temp = Vec::with_capacity(self.seed_len()+16);
+        while temp.len() < self.seed_len() {
+            CtrDrbg::increment(&mut self.v);
+            temp.extend_from_slice(&self.aes.encrypt_block(&self.key, &self.v));
+        }
+        temp.truncate(self.seed_len());
+        for (t, p) in temp.iter_mut().zip(provided.iter()) {
+            *t ^= p;
+        }
+
+        let klen = self.key.len();
+        self.key.copy_from_slice(&temp[..klen]);
+        self.v.copy_from_slice(&temp[klen..]);
+    }
+
+    // entropy XOR (input padded to seedlen), used without the derivation function
+    fn xor_seed(&self, entropy: &[u8], input: &[u8]) -> Result<Vec<u8>,&'static str> {
+        if entropy.len() != self.seed_len() {
+            return Err("Entropy input must be seedlen bytes without the derivation function");
+        }
+        if input.len() > self.seed_len() {
+            return Err("Input longer than seedlen");
+        }
+
+        let mut seed = entropy.to_vec();
+        for (s, x) in seed.iter_mut().zip(input.iter()) {
+            *s ^= x;
+        }
+        Ok(seed)
+    }
+
+    // Block_Cipher_df, returning seedlen bytes
+    fn df(&self, input: &[u8]) -> Vec<u8> {
+        let klen = self.key.len();
+        let seed_len = self.seed_len();
+
+        let mut s = Vec::with_capacity(input.len()+25);
+        s.extend_from_slice(&(input.len() as u32).to_be_bytes());
+        s.extend_from_slice(&(seed_len as u32).to_be_bytes());
+        s.extend_from_slice(input);
+        s.push(0x80);
+        while s.len() % 16 != 0 {
+            s.push(0x00);
+        }
+
+        let k: Vec<u8> = (0..klen as u8).collect();
+        let mut temp = Vec::with_capacity(seed_len+16);
+        let mut i = 0u32;
+        while temp.len() < seed_len {
+            let mut iv = [0u8;16];
+            iv[..4].copy_from_slice(&i.to_be_bytes());
+            temp.extend_from_slice(&self.bcc(&k, &[&iv[..], &s[..]].concat()));
+            i += 1;
+        }
+
+        let k = &temp[..klen];
+        let mut x: [u8;16] = temp[klen..klen+16].try_into().unwrap();
+        let mut out = Vec::with_capacity(seed_len+16);
+        while out.len() < seed_len {
+            x = self.aes.encrypt_block(k, &x);
+            out.extend_from_slice(&x);
+        }
+        out.truncate(seed_len);
+        out
+    }
+
+    fn bcc(&self, key: &[u8], data: &[u8]) -> [u8;16] {
+        let mut chain = [0u8;16];
+        for block in data.chunks(16) {
+            for (c, b) in chain.iter_mut().zip(block.iter()) {
+                *c ^= b;
+            }
+            chain = self.aes.encrypt_block(key, &chain);
+        }
+        chain
+    }
+}
+
+impl Drbg for CtrDrbg {
+    fn reseed(&mut self, entropy: &[u8], additional: &[u8]) -> Result<(),&'static str> {
+        let seed = if self.use_df {
+            if entropy.len() < self.key.len() {
+                return Err("Insufficient entropy");
+            }
+            self.df(&[entropy, additional].concat())
+        } else {
+            self.xor_seed(entropy, additional)?
+        };
+
+        self.update(&seed);
+        self.ctl.reseed_counter = 1;
+        Ok(())
+    }
+
+    fn generate(&mut self, out: &mut [u8], additional: &[u8]) -> Result<(),&'static str> {
+        if out.len() > MAX_REQUEST_BYTES {
+            return Err("Too many bytes requested");
+        }
+        if !self.use_df && additional.len() > self.seed_len() {
+            return Err("Input longer than seedlen");
+        }
+
+        let mut additional = additional;
+        if let Some(e) = self.ctl.reseed_entropy(self.entropy_len()) {
+            self.reseed(&e, additional)?;
+            additional = &[];
+        }
+
+        let mut input = vec![0;self.seed_len()];
+        if !additional.is_empty() {
+            if self.use_df {
+                input = self.df(additional);
+            } else {
+                input[..additional.len()].copy_from_slice(additional);
+            }
+            self.update(&input);
+        }
+
+        for chunk in out.chunks_mut(16) {
+            CtrDrbg::increment(&mut self.v);
+            let block = self.aes.encrypt_block(&self.key, &self.v);
+            chunk.copy_from_slice(&block[..chunk.len()]);
+        }
+        self.update(&input);
+        self.ctl.reseed_counter += 1;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utility::codec;
+
+    fn hex(s: &str) -> Vec<u8> {
+        codec::hex::decode(s).unwrap()
+    }
+
+    // Entropy source returning a fixed sequence of inputs
+    fn scripted(inputs: Vec<Vec<u8>>) -> EntropySource {
+        let mut inputs = inputs.into_iter();
+        Box::new(move |buf: &mut [u8]| buf.copy_from_slice(&inputs.next().unwrap()))
+    }
+
+    // CAVP drbgvectors_pr_true: the entropy source supplies EntropyInputPR1 and 2, one
+    // before each generate call with AdditionalInput1 and 2; the second output is checked.
+    fn cavp_pr_true<D: Drbg>(drbg: D, arm: impl FnOnce(D, EntropySource) -> D, entropy_pr: [&str;2], add: [&str;2], len: usize) -> Vec<u8> {
+        let mut drbg = arm(drbg, scripted(entropy_pr.iter().map(|e| hex(e)).collect()));
+        let mut out = vec![0;len];
+        drbg.generate(&mut out, &hex(add[0])).unwrap();
+        drbg.generate(&mut out, &hex(add[1])).unwrap();
+        out
+    }
+
+    // CAVP drbgvectors_pr_false: reseed with EntropyInputReseed and AdditionalInputReseed,
+    // then generate with AdditionalInput1 and 2; the second output is checked.
+    fn cavp_pr_false<D: Drbg>(mut drbg: D, entropy_reseed: &str, add_reseed: &str, add: [&str;2], len: usize) -> Vec<u8> {
+        drbg.reseed(&hex(entropy_reseed), &hex(add_reseed)).unwrap();
+        let mut out = vec![0;len];
+        drbg.generate(&mut out, &hex(add[0])).unwrap();
+        drbg.generate(&mut out, &hex(add[1])).unwrap();
+        out
+    }
+
+    // Reseeds with additional input and generates with additional input, then rearms the
+    // generator so the next generate call reseeds from the entropy source.
+    fn run_scenario<D: Drbg>(mut drbg: D, elen: usize, rearm: impl FnOnce(D, EntropySource) -> D) -> (Vec<u8>,Vec<u8>) {
+        let mut out1 = vec![0;64];
+        let mut out2 = vec![0;64];
+        drbg.reseed(&vec![0x44;elen], &[0x55;20]).unwrap();
+        drbg.generate(&mut out1, &[0x66;20]).unwrap();
+
+        let mut drbg = rearm(drbg, scripted(vec![vec![0x77;elen]]));
+        drbg.generate(&mut out2, &[0x88;20]).unwrap();
+        (out1, out2)
+    }
+
+    // CAVP drbgvectors_no_reseed, COUNT = 0: the second of two 1024-bit outputs is checked
+    #[test]
+    fn test_hmac_drbg_cavp() {
+        let mut drbg = HmacDrbg::instantiate(
+            &hex("ca851911349384bffe89de1cbdc46e6831e44d34a4fb935ee285dd14b71a7488"),
+            &hex("659ba96c601dc69fc902940805ec0ca8"), &[]).unwrap();
+        let mut out = [0;128];
+        drbg.generate(&mut out, &[]).unwrap();
+        drbg.generate(&mut out, &[]).unwrap();
+        assert_eq!(out.to_vec(),hex("e528e9abf2dece54d47c7e75e5fe302149f817ea9fb4bee6f4199697d04d5b89d54fbb978a15b5c443c9ec21036d2460b6f73ebad0dc2aba6e624abf07745bc107694bb7547bb0995f70de25d6b29e2d3011bb19d27676c07162c8b5ccde0668961df86803482cb37ed6d5c0bb8d50cf1f50d476aa0458bdaba806f48be9dcb8"));
+    }
+
+    // CAVS 14.3 HMAC_DRBG SHA-256 with 256-bit personalization and additional input, COUNT = 0
+    #[test]
+    fn test_hmac_drbg_cavp_pr() {
+        let drbg = HmacDrbg::instantiate(&hex("4294671d493dc085b5184607d7de2ff2b6aceb734a1b026f6cfee7c5a90f03da"),
+            &hex("d071544e599235d5eb38b64b551d2a6e"),
+            &hex("63bc769ae1d95a98bde870e4db7776297041d37c8a5c688d4e024b78d83f4d78")).unwrap();
+        let out = cavp_pr_true(drbg, |d, src| d.with_prediction_resistance(true).with_entropy_source(src),
+            ["db9b4790b62336fbb9a684b82947065393eeef8f57bd2477141ad17e776dac34",
+             "4a9abe80f6f522f29878bedf8245b27940a76471006fb4a4110beb4decb6c341"],
+            ["28848becd3f47696f124f4b14853a456156f69be583a7d4682cff8d44b39e1d3",
+             "8bfce0b7132661c3cd78175d83926f643e36f7608eec2c5dac3ddcbacc8c2182"], 128);
+        assert_eq!(out,hex("e580dc969194b2b18a97478aef9d1a72390aff14562747bf080d741527a6655ce7fc135325b457483a9f9c70f91165a811cf4524b50d51199a0df3bd60d12abac27d0bf6618e6b114e05420352e23f3603dfe8a225dc19b3d1fff1dc245dc6b1df24c741744bec3f9437dbbf222df84881a457a589e7815ef132f686b760f012"));
+
+        let drbg = HmacDrbg::instantiate(&hex("cdb0d9117cc6dbc9ef9dcb06a97579841d72dc18b2d46a1cb61e314012bdf416"),
+            &hex("d0c0d01d156016d0eb6b7e9c7c3c8da8"),
+            &hex("6f0fb9eab3f9ea7ab0a719bfa879bf0aaed683307fda0c6d73ce018b6e34faaa")).unwrap();
+        let out = cavp_pr_false(drbg, "8ec6f7d5a8e2e88f43986f70b86e050d07c84b931bcf18e601c5a3eee3064c82",
+            "1ab4ca9014fa98a55938316de8ba5a68c629b0741bdd058c4d70c91cda5099b3",
+            ["16e2d0721b58d839a122852abd3bf2c942a31c84d82fca74211871880d7162ff",
+             "53686f042a7b087d5d2eca0d2a96de131f275ed7151189f7ca52deaa78b79fb2"], 128);
+        assert_eq!(out,hex("dda04a2ca7b8147af1548f5d086591ca4fd951a345ce52b3cd49d47e84aa31a183e31fbc42a1ff1d95afec7143c8008c97bc2a9c091df0a763848391f68cb4a366ad89857ac725a53b303ddea767be8dc5f605b1b95f6d24c9f06be65a973a089320b3cc42569dcfd4b92b62a993785b0301b3fc452445656fce22664827b88f"));
+    }
+
+    #[test]
+    fn test_hash_drbg_cavp() {
+        let mut drbg = HashDrbg::instantiate(
+            &hex("a65ad0f345db4e0effe875c3a2e71f42c7129d620ff5c119a9ef55f05185e0fb"),
+            &hex("8581f9317517276e06e9607ddbcbcc2e"), &[]).unwrap();
+        let mut out = [0;128];
+        drbg.generate(&mut out, &[]).unwrap();
+        drbg.generate(&mut out, &[]).unwrap();
+        assert_eq!(out.to_vec(),hex("d3e160c35b99f340b2628264d1751060e0045da383ff57a57d73a673d2b8d80daaf6a6c35a91bb4579d73fd0c8fed111b0391306828adfed528f018121b3febdc343e797b87dbb63db1333ded9d1ece177cfa6b71fe8ab1da46624ed6415e51ccde2c7ca86e283990eeaeb91120415528b2295910281b02dd431f4c9f70427df"));
+    }
+
+    // CAVS 14.3 Hash_DRBG SHA-256 with 256-bit personalization and additional input, COUNT = 14
+    #[test]
+    fn test_hash_drbg_cavp_pr() {
+        let drbg = HashDrbg::instantiate(&hex("066dc8ce75b28966a685163fe2a4d427fbdb616650616ba282fc332b4e6f1220"),
+            &hex("559f7c64897083ec2d7370d9f0e5071f"),
+            &hex("886f549aad1ac63d18cbcc6685daa2c2f79eb0894cb4aef1ac544fce57f15e11")).unwrap();
+        let out = cavp_pr_true(drbg, |d, src| d.with_prediction_resistance(true).with_entropy_source(src),
+            ["ff80b7d26a05bc8a7abe53286b0eeb733b715a205bfa4ff63703deadb6ea0ef4",
+             "c73832534681ede37e03846d3c841767297d246c689241d2e775be7ec996293d"],
+            ["b7215f14ac7bafd0a91772ba22f719afbd20b311636c2b1e83e4a823353fc6ea",
+             "ced31f7e0dae5bb5c043e246b29473e2fd39512ead4569eee3e3803314aba7a3"], 128);
+        assert_eq!(out,hex("60c234cfafb468033bf195e578ce266e1465326a96a9e03f8b893670ef62754d5e80d553a1f84950208b9343079f2ef856e9c570618597b5dc82a2daeaa3fd9b2fd2a0d71bc62935ccb83da0679805a0e31efee4f0e513b08317faca935e382948d272db763e6df32510ff1b99fff8c60eb0dd292ebcbbc80a016ed3b00e4eab"));
+    }
+
+    #[test]
+    fn test_ctr_drbg_cavp() {
+        // AES-256 no df
+        let mut drbg = CtrDrbg::instantiate(KeyLength::KL256, false,
+            &hex("df5d73faa468649edda33b5cca79b0b05600419ccb7a879ddfec9db32ee494e5531b51de16a30f769262474c73bec010"), &[], &[]).unwrap();
+        let mut out = [0;64];
+        drbg.generate(&mut out, &[]).unwrap();
+        drbg.generate(&mut out, &[]).unwrap();
+        assert_eq!(out.to_vec(),hex("d1c07cd95af8a7f11012c84ce48bb8cb87189e99d40fccb1771c619bdf82ab2280b1dc2f2581f39164f7ac0c510494b3a43c41b7db17514c87b107ae793e01c5"));
+
+        // AES-256 use df
+        let mut drbg = CtrDrbg::instantiate(KeyLength::KL256, true,
+            &hex("36401940fa8b1fba91a1661f211d78a0b9389a74e5bccfece8d766af1a6d3b14"),
+            &hex("496f25b0f1301b4f501be30380a137eb"), &[]).unwrap();
+        drbg.generate(&mut out, &[]).unwrap();
+        drbg.generate(&mut out, &[]).unwrap();
+        assert_eq!(out.to_vec(),hex("5862eb38bd558dd978a696e6df164782ddd887e7e9a6c9f3f1fbafb78941b535a64912dfd224c6dc7454e5250b3d97165e16260c2faf1cc7735cb75fb4f07e1d"));
+    }
+
+    #[test]
+    fn test_ctr_drbg_cavp_pr() {
+        // AES-256 no df, 384-bit personalization and additional input, COUNT = 0 with all inputs
+        let drbg = CtrDrbg::instantiate(KeyLength::KL256, false,
+            &hex("ae7ebe062971f5eb32e5b21444750785de816595ad2cbe80a209c8f8ab04b5468166de8c6ae522d8f10b56386a3b424f"), &[],
+            &hex("55860dae57fcac297087c137efb796878a75868f6e7681114e9b73ed0c67e3c62bfc9f5d77e8caa59bcdb223f4ffd247")).unwrap();
+        let out = cavp_pr_false(drbg,
+            "a42407931bfeca70e6ee5dd197021a129525051c07468e8b25587c5ad50abe9204e882fe847b8fd47cf7b4360e5aa034",
+            "ee4c88d1eb05f4853663eada501d2fc4b4984b283a88db579af2113031e03d9bc570de943dd168918f3ba8065581fea7",
+            ["4b4b03ef19b0f259dca2b3ee3ae4cd86c3895a784b3d8eee043a2003c08289f8fffdad141e6b1ab2174d8d5d79c1e581",
+             "3062b33f116b46e20fe3c354726ae9b2a3a4c51922c8107863cb86f1f0bdad7554075659d91c371e2b11b1e8106a1ed5"], 64);
+        assert_eq!(out,hex("0d270518baeafac160ff1cb28c11ef68712c764c0c01674e6c9ca2cc9c7e0e8accfd3c753635ee070081eee7628af6187fbc2854b3c204461a796cf3f3fcb092"));
+
+        // AES-256 use df, 256-bit personalization and additional input, from the older CAVS
+        // files whose 128-bit outputs follow generate, reseed, generate for PR = False
+        let drbg = CtrDrbg::instantiate(KeyLength::KL256, true,
+            &hex("6168fc1af0b5956b85099b743f1378493b85ec93133ba94f96ab2ce4c88fdd6a"), &hex("add2bbbab76589c3216c55332b36ffa4"),
+            &hex("6ecae72072d3845a32d34b2472c4632b9d12240c23268e8316370bd1064f686d")).unwrap();
+        let out = cavp_pr_true(drbg, |d, src| d.with_prediction_resistance(true).with_entropy_source(src),
+            ["0b23afdff162d7d34397f87704a84220bdf60fc1172f9f54bb561786680ebaa9",
+             "bf6c592a0d440fae9a5e0373d8a6e1cf25613824869e53e8a4df56f406079c0f"],
+            ["7e084abbe3217cc923d2f8b07398ba847423ab068ae222d37bce9bd24a76b8de",
+             "946bc99fab8dc5ec71881d008c8968e4c8077736176d7978c7064e99042829c3"], 16);
+        assert_eq!(out,hex("224ab4b8b6ee7db19ec9f9a0d9e29700"));
+
+        // AES-128 use df, CAVS 14.3 with 128-bit personalization and additional input, COUNT = 0
+        let drbg = CtrDrbg::instantiate(KeyLength::KL128, true,
+            &hex("92898f31fa1cff6d182f260643dff818"), &hex("c2a4d972c3b9b697"),
+            &hex("ea65ee60264e7eb60e8268c4373c5c0b")).unwrap();
+        let out = cavp_pr_true(drbg, |d, src| d.with_prediction_resistance(true).with_entropy_source(src),
+            ["20728a06f86f8dd441e272b7c42ce810", "3db0f094f305503317863e2208f7a501"],
+            ["1a40fae3cc6c7ca0f8daba59236dad1d", "9f72766cc746e5ed2e532012bc59318c"], 64);
+        assert_eq!(out,hex("5a3539870f4d22a40924ee71c96fac720ad6f08882d0832873ec3f93d8ab4523f07eac45145e939fb1d676433db6e80888f6da89087742fe1af43fc423c51f68"));
+
+        let mut drbg = CtrDrbg::instantiate(KeyLength::KL256, true,
+            &hex("a53e371017439193591e475087aaddd5c1c386cdca0ddb68e002d80fdc401a47"), &hex("a94da55afdc50ce51c9a3b8a4c448440"),
+            &hex("8b52a24a93c34ea71e1ca705eb829ba65de4d4e07fa3d86b37845ff1c7d5f6d2")).unwrap();
+        let mut out = [0;16];
+        drbg.generate(&mut out, &hex("20f422edf85ca16a01cfbe5f8d6c947fae12a857db2aa9bfc7b36581808d0d46")).unwrap();
+        drbg.reseed(&hex("dd40e5987b2716731568d276bf0c6715757903d3dede914642ddd467c879c81e"),
+            &hex("7fd81fbd2ab51c115d834e99f65ca54020ed388ed59ee07593fe125e5d73fb75")).unwrap();
+        drbg.generate(&mut out, &hex("cd2cff14693e4c9efdfe260de986004930bab1c65057772a62392c3b74ebc90d")).unwrap();
+        assert_eq!(out.to_vec(),hex("4f78beb94d978ce9d097feadfafd355e"));
+    }
+
+    // Regression checks only: the expected outputs below were produced by this implementation
+    // and are not known-answer values. They pin down that prediction resistance and a forced
+    // reseed from the reseed interval take the same path through the entropy source.
+    #[test]
+    fn test_hmac_drbg_reseed() {
+        let expected = (hex("c38bceb91920e15f6f899b7b97412e7e6e4ddb505674fc1927280f9b2472802089ab3d0e79ff2b05ba87c383c662713a280673a7735a1f9783a36e94b47a2362"),
+            hex("3b9bdd54dc3f76225c35c0fe4940e6191e865a62093f492a20c3dc055e8e10c33e4e0435c3d3dfad2c50a5753f298f41aab7f3cdd91e7c455006bf68f82aa7d0"));
+        let drbg = || HmacDrbg::instantiate(&[0x11;32], &[0x22;16], &[0x33;16]).unwrap();
+
+        assert_eq!(run_scenario(drbg(), 32, |d, src| d.with_prediction_resistance(true).with_entropy_source(src)),expected);
+        assert_eq!(run_scenario(drbg(), 32, |d, src| d.with_reseed_interval(1).with_entropy_source(src)),expected);
+    }
+
+    #[test]
+    fn test_hash_drbg_reseed() {
+        let expected = (hex("e0c6286eb62188eda3c2a3613c0dbb04e988db79c8d415e4f64cbf79a6f9d6c24c6db5baff3fcbd6852ced5fcc690110d50232fd4e090333109abe657c667d60"),
+            hex("3a1fc6444a50265e0adf841c114a94fabc62783682ba52c6080bafc922e7082be8c8249b3c13a410540757b35d6e7224b4348c7926fd9289a977f57d012bcebd"));
+        let drbg = || HashDrbg::instantiate(&[0x11;32], &[0x22;16], &[0x33;16]).unwrap();
+
+        assert_eq!(run_scenario(drbg(), 32, |d, src| d.with_prediction_resistance(true).with_entropy_source(src)),expected);
+        assert_eq!(run_scenario(drbg(), 32, |d, src| d.with_reseed_interval(1).with_entropy_source(src)),expected);
+    }
+
+    #[test]
+    fn test_ctr_drbg_reseed() {
+        let expected = (hex("1c0f8afe1913a661ec01f521e6e698932616d1409858cf4f6dfecafa096ae15894398d0eca86eedfa232366e8c22e787bfee3f8db689497e5b6138b1ae899ced"),
+            hex("0697ea4eb600bd7f472990fbe7c17a35056a21e9ea770ea73efd490b34ffed2ececf3aeb302bf498f15eb435c3b9de1fd5a2e2738af31ab3c927f9f8058f6b8b"));
+        let drbg = CtrDrbg::instantiate(KeyLength::KL256, false, &[0x11;48], &[], &[0x33;16]).unwrap();
+        assert_eq!(run_scenario(drbg, 48, |d, src| d.with_prediction_resistance(true).with_entropy_source(src)),expected);
+
+        let expected = (hex("9cd5510d77758d32a92d1be77295c29625c23cd5e0f0b70e59ab5f28e998a2805730368a15a7bdfdc641ec342c141fad968cf64a84bc96969bf942c53985c75d"),
+            hex("c5931269d61ca2a848b54d077b1f434f868595bd0783e590a3e50e0a6f7a82a08154ba833739312653e7479893d9cd45da56a869f325833c4e90f653a2fbeab7"));
+        let drbg = CtrDrbg::instantiate(KeyLength::KL256, true, &[0x11;32], &[0x22;16], &[0x33;16]).unwrap();
+        assert_eq!(run_scenario(drbg, 32, |d, src| d.with_reseed_interval(1).with_entropy_source(src)),expected);
+
+        let expected = (hex("0eeeef515076a5e74d528cdd58069eac60a6af93f942fb4f7493b696b6827f2ab786be7229add1de393a832545f4895547bf97f103a254c9676468d5342a308c"),
+            hex("0cf77e40660bdc50d0e6fa345b46469df75e2375b3052d593bd55c8c0a59120e9a44e96ac40315fa2533aa7ee96485781b5c5130d6df49dc47d456b655b58fe5"));
+        let drbg = CtrDrbg::instantiate(KeyLength::KL128, true, &[0x11;16], &[0x22;16], &[0x33;16]).unwrap();
+        assert_eq!(run_scenario(drbg, 16, |d, src| d.with_prediction_resistance(true).with_entropy_source(src)),expected);
+    }
+
+    #[test]
+    fn test_errors() {
+        assert!(HmacDrbg::instantiate(&[0;16], &[], &[]).is_err());
+        assert!(CtrDrbg::instantiate(KeyLength::KL256, false, &[0;32], &[], &[]).is_err());
+
+        let mut drbg = HmacDrbg::new(b"crusto");
+        let mut out = vec![0;MAX_REQUEST_BYTES+1];
+        assert!(drbg.generate(&mut out, &[]).is_err());
+        assert!(drbg.generate(&mut out[..MAX_REQUEST_BYTES], &[]).is_ok());
+    }
+
+    #[test]
+    fn test_os_seeded() {
+        let mut a = [0;32];
+        let mut b = [0;32];
+        HashDrbg::new(&[]).generate(&mut a, &[]).unwrap();
+        CtrDrbg::new(KeyLength::KL256, &[]).with_prediction_resistance(true).generate(&mut b, &[]).unwrap();
+        assert_ne!(a,b);
+    }
+}
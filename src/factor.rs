@@ -0,0 +1,419 @@
+use num::{BigUint,Integer};
+use num::traits::identities::{One,Zero};
+use crate::ntheory;
+use crate::prime::{self,SMALL_PRIMES};
+
+/// Factors `n` completely.
+///
+/// Returns the prime factors in ascending order, repeated according to multiplicity.
+/// Every factor is checked with `prime::primality_test` before it is returned.
+/// Moduli without any weakness the individual methods exploit may take impractically long.
+pub fn factorize(n: &BigUint) -> Vec<BigUint> {
+    let mut factors = Vec::new();
+    if n.is_zero() {
+        return factors;
+    }
+
+    let mut n = n.clone();
+    for p in SMALL_PRIMES.iter() {
+        while (&n % p).is_zero() {
+            factors.push(BigUint::from(*p));
+            n /= *p;
+        }
+    }
+
+    let mut stack = vec![n];
+    while let Some(m) = stack.pop() {
+        if m.is_one() {
+            continue;
+        }
+        if prime::primality_test(&m) {
+            factors.push(m);
+            continue;
+        }
+
+        let d = find_factor(&m);
+        stack.push(&m / &d);
+        stack.push(d);
+    }
+
+    factors.sort();
+    factors
+}
+
+// Tries the cheap special-purpose methods first, then ECM with growing bounds until a factor is found
+fn find_factor(n: &BigUint) -> BigUint {
+    if let Some(d) = fermat(n, 1 << 12) {
+        return d;
+    }
+    if let Some(d) = pollard_p_minus_1(n, 1 << 16) {
+        return d;
+    }
+    if let Some(d) = williams_p_plus_1(n, 1 << 14) {
+        return d;
+    }
+    if let Some(d) = pollard_rho(n, 1 << 20) {
+        return d;
+    }
+
+    let mut b1 = 2000;
+    loop {
+        if let Some(d) = ecm(n, 25, b1) {
+            return d;
+        }
+        b1 *= 5;
+    }
+}
+
+/// Pollard's rho method with Brent's cycle detection.
+///
+/// Tries the polynomials `x^2 + c` for `c = 1, 2, ...` until `max_iter` iterations are used up.
+pub fn pollard_rho(n: &BigUint, max_iter: u64) -> Option<BigUint> {
+    if n.is_even() {
+        return if *n > BigUint::from(2u8) {Some(BigUint::from(2u8))} else {None};
+    }
+
+    const M: u64 = 128;
+    let mut iter = 0u64;
+    let mut c = BigUint::one();
+
+    while iter < max_iter {
+        let f = |x: &BigUint| (x * x + &c) % n;
+        let mut y = BigUint::from(2u8);
+        let mut x = y.clone();
+        let mut ys = y.clone();
+        let mut q = BigUint::one();
+        let mut g = BigUint::one();
+        let mut r = 1u64;
+
+        while g.is_one() && iter < max_iter {
+            x = y.clone();
+            for _ in 0..r {
+                y = f(&y);
+            }
+
+            let mut k = 0;
+            while k < r && g.is_one() {
+                ys = y.clone();
+                for _ in 0..M.min(r - k) {
+                    y = f(&y);
+                    q = q * abs_diff(&x, &y) % n;
+                }
+                g = q.gcd(n);
+                k += M;
+                iter += M.min(r);
+            }
+            r <<= 1;
+        }
+
+        if g == *n {
+            // the batched product overshot, retrace one step at a time
+            loop {
+                ys = f(&ys);
+                g = abs_diff(&x, &ys).gcd(n);
+                if !g.is_one() {
+                    break;
+                }
+            }
+        }
+
+        if !g.is_one() && g != *n {
+            return Some(g);
+        }
+        c += 1u8;
+    }
+
+    None
+}
+
+/// Pollard's p-1 method, finding a prime factor `p` of `n` whose `p-1` is `bound`-smooth.
+pub fn pollard_p_minus_1(n: &BigUint, bound: u64) -> Option<BigUint> {
+    let mut a = BigUint::from(2u8);
+
+    for (i, q) in primes_up_to(bound).into_iter().enumerate() {
+        a = a.modpow(&BigUint::from(prime_power(q, bound)), n);
+
+        if i % 64 == 63 {
+            if let Some(d) = nontrivial_gcd(&a, 1, n) {
+                return Some(d);
+            }
+        }
+    }
+
+    nontrivial_gcd(&a, 1, n)
+}
+
+/// Williams' p+1 method, finding a prime factor `p` of `n` whose `p+1` is `bound`-smooth.
+///
+/// Each seed only succeeds for about half of the primes, so several seeds are tried.
+pub fn williams_p_plus_1(n: &BigUint, bound: u64) -> Option<BigUint> {
+    let primes = primes_up_to(bound);
+
+    for seed in [3u8, 5, 7, 9, 11, 13].iter() {
+        let mut v = BigUint::from(*seed) % n;
+
+        for (i, q) in primes.iter().enumerate() {
+            v = lucas_v(&v, prime_power(*q, bound), n);
+
+            if i % 64 == 63 {
+                if let Some(d) = nontrivial_gcd(&v, 2, n) {
+                    return Some(d);
+                }
+            }
+        }
+
+        if let Some(d) = nontrivial_gcd(&v, 2, n) {
+            return Some(d);
+        }
+    }
+
+    None
+}
+
+/// Fermat's method, finding a factor of `n = a^2 - b^2` when the two factors are close together.
+pub fn fermat(n: &BigUint, max_iter: u64) -> Option<BigUint> {
+    if n.is_even() {
+        return if *n > BigUint::from(2u8) {Some(BigUint::from(2u8))} else {None};
+    }
+
+    let mut a = n.sqrt();
+    if &a * &a < *n {
+        a += 1u8;
+    }
+
+    for _ in 0..max_iter {
+        let b2 = &a * &a - n;
+        let b = b2.sqrt();
+        if &b * &b == b2 {
+            let d = &a - &b;
+            return if d.is_one() {None} else {Some(d)};
+        }
+        a += 1u8;
+    }
+
+    None
+}
+
+/// Lenstra's elliptic curve method (stage 1) on Montgomery curves with Suyama's parametrization.
+///
+/// Runs `curves` curves with the smoothness bound `b1`. Curves are chosen deterministically.
+pub fn ecm(n: &BigUint, curves: usize, b1: u64) -> Option<BigUint> {
+    if n.is_even() {
+        return if *n > BigUint::from(2u8) {Some(BigUint::from(2u8))} else {None};
+    }
+
+    let primes = primes_up_to(b1);
+
+    for sigma in 6..6+curves as u64 {
+        let sigma = BigUint::from(sigma);
+        let u = sub_mod(&(&sigma * &sigma % n), &BigUint::from(5u8), n);
+        let v = (&sigma << 2u8) % n;
+        let u3 = u.modpow(&BigUint::from(3u8), n);
+        let v3 = v.modpow(&BigUint::from(3u8), n);
+
+        // (A+2)/4 = (v-u)^3 (3u+v) / (16 u^3 v)
+        let num = sub_mod(&v, &u, n).modpow(&BigUint::from(3u8), n) * ((&u * 3u8 + &v) % n) % n;
+        let den = ((&u3 * &v) << 4u8) % n;
+        let den_inv = match ntheory::mod_inverse(&den, n) {
+            Some(x) => x,
+            None => {
+                let g = den.gcd(n);
+                if !g.is_one() && g != *n {
+                    return Some(g);
+                }
+                continue;
+            },
+        };
+        let curve = Curve {n, a24: num * den_inv % n};
+
+        let mut p = (u3, v3);
+        for q in primes.iter() {
+            p = curve.mul(&p, prime_power(*q, b1));
+        }
+
+        let g = p.1.gcd(n);
+        if !g.is_one() && g != *n {
+            return Some(g);
+        }
+    }
+
+    None
+}
+
+// Montgomery curve By^2 = x^3 + Ax^2 + x over Z/nZ, using projective (X:Z) coordinates
+struct Curve<'a> {
+    n: &'a BigUint,
+    a24: BigUint,
+}
+
+impl Curve<'_> {
+    fn double(&self, (x, z): &(BigUint,BigUint)) -> (BigUint,BigUint) {
+        let n = self.n;
+        let s = (x + z) % n;
+        let d = sub_mod(x, z, n);
+        let t1 = &s * &s % n;
+        let t2 = &d * &d % n;
+        let t = sub_mod(&t1, &t2, n);
+        (&t1 * &t2 % n, &t * ((&t2 + &self.a24 * &t) % n) % n)
+    }
+
+    fn add(&self, (xp, zp): &(BigUint,BigUint), (xq, zq): &(BigUint,BigUint), (xd, zd): &(BigUint,BigUint)) -> (BigUint,BigUint) {
+        let n = self.n;
+        let u = sub_mod(xp, zp, n) * ((xq + zq) % n) % n;
+        let v = ((xp + zp) % n) * sub_mod(xq, zq, n) % n;
+        let s = (&u + &v) % n;
+        let d = sub_mod(&u, &v, n);
+        (zd * (&s * &s % n) % n, xd * (&d * &d % n) % n)
+    }
+
+    fn mul(&self, p: &(BigUint,BigUint), k: u64) -> (BigUint,BigUint) {
+        let mut r0 = p.clone();
+        let mut r1 = self.double(p);
+
+        for i in (0..63 - k.leading_zeros()).rev() {
+            if k >> i & 1 == 1 {
+                r0 = self.add(&r1, &r0, p);
+                r1 = self.double(&r1);
+            } else {
+                r1 = self.add(&r0, &r1, p);
+                r0 = self.double(&r0);
+            }
+        }
+
+        r0
+    }
+}
+
+// V_k(P) of the Lucas sequence with Q = 1, computed with a Montgomery ladder
+fn lucas_v(p: &BigUint, k: u64, n: &BigUint) -> BigUint {
+    let two = BigUint::from(2u8);
+    let mut x = p.clone();
+    let mut y = sub_mod(&(p * p % n), &two, n);
+
+    for i in (0..63 - k.leading_zeros()).rev() {
+        if k >> i & 1 == 1 {
+            x = sub_mod(&(&x * &y % n), p, n);
+            y = sub_mod(&(&y * &y % n), &two, n);
+        } else {
+            y = sub_mod(&(&x * &y % n), p, n);
+            x = sub_mod(&(&x * &x % n), &two, n);
+        }
+    }
+
+    x
+}
+
+// gcd(a - c, n) if it is a proper factor of n
+fn nontrivial_gcd(a: &BigUint, c: u8, n: &BigUint) -> Option<BigUint> {
+    let g = sub_mod(a, &BigUint::from(c), n).gcd(n);
+    if !g.is_one() && g != *n {
+        Some(g)
+    } else {
+        None
+    }
+}
+
+// largest power of the prime q not exceeding bound
+fn prime_power(q: u64, bound: u64) -> u64 {
+    let mut r = q;
+    while r <= bound / q {
+        r *= q;
+    }
+    r
+}
+
+fn primes_up_to(bound: u64) -> Vec<u64> {
+    let bound = bound as usize;
+    let mut sieve = vec![true; bound + 1];
+    let mut primes = Vec::new();
+
+    for i in 2..=bound {
+        if sieve[i] {
+            primes.push(i as u64);
+            let mut j = i * i;
+            while j <= bound {
+                sieve[j] = false;
+                j += i;
+            }
+        }
+    }
+
+    primes
+}
+
+fn sub_mod(a: &BigUint, b: &BigUint, n: &BigUint) -> BigUint {
+    (a % n + n - b % n) % n
+}
+
+fn abs_diff(a: &BigUint, b: &BigUint) -> BigUint {
+    if a > b {
+        a - b
+    } else {
+        b - a
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn big(s: &str) -> BigUint {
+        BigUint::parse_bytes(s.as_bytes(), 10).unwrap()
+    }
+
+    fn assert_factor(n: &BigUint, d: Option<BigUint>) {
+        let d = d.unwrap();
+        assert!(!d.is_one() && d != *n);
+        assert!((n % d).is_zero());
+    }
+
+    #[test]
+    fn test_pollard_rho() {
+        assert_factor(&big("10403"), pollard_rho(&big("10403"), 1 << 10));
+        let n = big("6917529065222045707");
+        assert_factor(&n, pollard_rho(&n, 1 << 20));
+    }
+
+    #[test]
+    fn test_pollard_p_minus_1() {
+        // 8608456956238879741 - 1 = 2^2 * 3 * 5 * 7^2 * 11 * ... * 47
+        let n = big("19849750292660521347079766180743985969");
+        assert_eq!(pollard_p_minus_1(&n, 100),Some(big("8608456956238879741")));
+    }
+
+    #[test]
+    fn test_williams_p_plus_1() {
+        // p + 1 is 300-smooth while p - 1 is not
+        let p = big("26493241700023745535511765196325042138600588497174898315001309105447491731228753");
+        let n = &p * big("4611686018427388919");
+        assert_eq!(pollard_p_minus_1(&n, 300),None);
+        assert_eq!(williams_p_plus_1(&n, 300),Some(p));
+    }
+
+    #[test]
+    fn test_fermat() {
+        let p = prime::gen_prime(512);
+        let mut q = &p + 2u8;
+        while !prime::primality_test(&q) {
+            q += 2u8;
+        }
+        let d = fermat(&(&p * &q), 16).unwrap();
+        assert_eq!(d,p);
+    }
+
+    #[test]
+    fn test_ecm() {
+        let n = big("1180591735015083748141");
+        assert_factor(&n, ecm(&n, 50, 10000));
+    }
+
+    #[test]
+    fn test_factorize() {
+        assert_eq!(factorize(&big("1")),vec![]);
+        assert_eq!(factorize(&big("360")),[2u32,2,2,3,3,5].iter().map(|x| BigUint::from(*x)).collect::<Vec<_>>());
+        assert_eq!(factorize(&big("18446744073709551617")),vec![big("274177"),big("67280421310721")]);
+
+        let n = big("1180591735015083748141");
+        assert_eq!(factorize(&(&n * &n * 9u8)),vec![big("3"),big("3"),big("17179869989"),big("17179869989"),big("68719480169"),big("68719480169")]);
+    }
+}
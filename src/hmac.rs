@@ -0,0 +1,68 @@
+use crate::sha256::sha256;
+use crate::sha512::{sha384, sha512};
+
+// RFC 2104 over a hash with an N-byte output and a B-byte block
+fn hmac<const N: usize, const B: usize>(hash: fn(&[u8]) -> [u8;N], key: &[u8], msg: &[u8]) -> [u8;N] {
+    let mut k = [0u8;B];
+    if key.len() > B {
+        k[..N].copy_from_slice(&hash(key));
+    } else {
+        k[..key.len()].copy_from_slice(key);
+    }
+
+    let mut inner: Vec<u8> = k.iter().map(|x| x^0x36).collect();
+    inner.extend_from_slice(msg);
+
+    let mut outer: Vec<u8> = k.iter().map(|x| x^0x5c).collect();
+    outer.extend_from_slice(&hash(&inner));
+
+    hash(&outer)
+}
+
+/// HMAC-SHA256 (RFC 2104)
+pub fn hmac_sha256(key: &[u8], msg: &[u8]) -> [u8;32] {
+    hmac::<32, 64>(sha256, key, msg)
+}
+
+/// HMAC-SHA384 (RFC 4231)
+pub fn hmac_sha384(key: &[u8], msg: &[u8]) -> [u8;48] {
+    hmac::<48, 128>(sha384, key, msg)
+}
+
+/// HMAC-SHA512 (RFC 4231)
+pub fn hmac_sha512(key: &[u8], msg: &[u8]) -> [u8;64] {
+    hmac::<64, 128>(sha512, key, msg)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utility::codec::hex;
+
+    // RFC 4231 test cases 1, 2 and 6
+    #[test]
+    fn test_hmac_sha256() {
+        assert_eq!(hmac_sha256(&[0x0b;20], b"Hi There").to_vec(),
+            hex::decode("b0344c61d8db38535ca8afceaf0bf12b881dc200c9833da726e9376c2e32cff7").unwrap());
+        assert_eq!(hmac_sha256(b"Jefe", b"what do ya want for nothing?").to_vec(),
+            hex::decode("5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843").unwrap());
+        assert_eq!(hmac_sha256(&[0xaa;131], b"Test Using Larger Than Block-Size Key - Hash Key First").to_vec(),
+            hex::decode("60e431591ee0b67f0d8a26aacbf5b77f8e0bc6213728c5140546040f0ee37f54").unwrap());
+    }
+
+    #[test]
+    fn test_hmac_sha512() {
+        assert_eq!(hmac_sha384(&[0x0b;20], b"Hi There").to_vec(),hex::decode(
+            "afd03944d84895626b0825f4ab46907f15f9dadbe4101ec682aa034c7cebc59c\
+             faea9ea9076ede7f4af152e8b2fa9cb6").unwrap());
+        assert_eq!(hmac_sha512(&[0x0b;20], b"Hi There").to_vec(),hex::decode(
+            "87aa7cdea5ef619d4ff0b4241a1d6cb02379f4e2ce4ec2787ad0b30545e17cde\
+             daa833b7d6b8a702038b274eaea3f4e4be9d914eeb61f1702e696c203a126854").unwrap());
+        assert_eq!(hmac_sha384(b"Jefe", b"what do ya want for nothing?").to_vec(),hex::decode(
+            "af45d2e376484031617f78d2b58a6b1b9c7ef464f5a01b47e42ec3736322445e\
+             8e2240ca5e69e2c78b3239ecfab21649").unwrap());
+        assert_eq!(hmac_sha512(&[0xaa;131], b"Test Using Larger Than Block-Size Key - Hash Key First").to_vec(),
+            hex::decode("80b24263c7c1a3ebb71493c1dd7be8b49b46d1f41b4aeec1121b013783f8f352\
+             6b56d037e05f2598bd0fd2215d6a1e5295e64f73f63f0aec8b915a985d786598").unwrap());
+    }
+}
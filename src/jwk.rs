@@ -0,0 +1,357 @@
+// JSON Web Keys (RFC 7517, RFC 7518 section 6) for RSA and symmetric keys, JWK Sets
+// and RFC 7638 thumbprints
+use crate::rsa;
+use crate::sha256::sha256;
+use crate::utility::base64::URL_SAFE_NO_PAD;
+use crate::utility::json::{self, Value};
+use num::BigUint;
+use std::fmt;
+
+#[derive(Debug,Clone,PartialEq,Eq)]
+pub enum Error {
+    Json(json::Error),
+    /// A required member is absent or not a string
+    MissingMember(&'static str),
+    /// A member that is not minimal base64url
+    InvalidMember(&'static str),
+    UnsupportedKeyType(String),
+    /// RSA parameters that do not form a key; private keys need their two primes
+    InvalidKey,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Json(e) => write!(f, "{}", e),
+            Error::MissingMember(name) => write!(f, "missing member {:?}", name),
+            Error::InvalidMember(name) => write!(f, "invalid member {:?}", name),
+            Error::UnsupportedKeyType(kty) => write!(f, "unsupported key type {:?}", kty),
+            Error::InvalidKey => write!(f, "invalid key parameters"),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<json::Error> for Error {
+    fn from(e: json::Error) -> Self {
+        Error::Json(e)
+    }
+}
+
+/// Key material of a JWK.
+pub enum Key {
+    RsaPublic(rsa::PublicKey),
+    RsaPrivate(rsa::PrivateKey),
+    /// Symmetric key bytes, e.g. for `aes::AES` or `hmac::hmac_sha256`
+    Oct(Vec<u8>),
+}
+
+/// A JSON Web Key with the optional `kid`, `use` and `alg` members; other members are
+/// ignored when parsing.
+pub struct Jwk {
+    pub key: Key,
+    pub kid: Option<String>,
+    /// The `use` member, `sig` or `enc`
+    pub usage: Option<String>,
+    pub alg: Option<String>,
+}
+
+// base64urlUInt: big-endian without leading zero octets
+fn encode_uint(x: &BigUint) -> Value {
+    Value::String(URL_SAFE_NO_PAD.encode(&x.to_bytes_be()))
+}
+
+fn member<'a>(v: &'a Value, name: &'static str) -> Result<&'a str,Error> {
+    v.get(name).and_then(Value::as_str).ok_or(Error::MissingMember(name))
+}
+
+fn bytes_member(v: &Value, name: &'static str) -> Result<Vec<u8>,Error> {
+    URL_SAFE_NO_PAD.decode(member(v, name)?).map_err(|_| Error::InvalidMember(name))
+}
+
+fn uint_member(v: &Value, name: &'static str) -> Result<BigUint,Error> {
+    let b = bytes_member(v, name)?;
+    if b.is_empty() || (b[0] == 0 && b.len() > 1) {
+        return Err(Error::InvalidMember(name));
+    }
+    Ok(BigUint::from_bytes_be(&b))
+}
+
+fn optional_member(v: &Value, name: &str) -> Option<String> {
+    v.get(name).and_then(Value::as_str).map(str::to_string)
+}
+
+impl Jwk {
+    pub fn new(key: Key) -> Jwk {
+        Jwk {key, kid: None, usage: None, alg: None}
+    }
+
+    pub fn with_kid(mut self, kid: &str) -> Self {
+        self.kid = Some(kid.to_string());
+        self
+    }
+
+    /// Sets `kid` to the thumbprint, a common choice of key ID.
+    pub fn with_thumbprint_kid(self) -> Self {
+        let kid = self.thumbprint();
+        self.with_kid(&kid)
+    }
+
+    pub fn with_usage(mut self, usage: &str) -> Self {
+        self.usage = Some(usage.to_string());
+        self
+    }
+
+    pub fn with_alg(mut self, alg: &str) -> Self {
+        self.alg = Some(alg.to_string());
+        self
+    }
+
+    pub fn kty(&self) -> &'static str {
+        match self.key {
+            Key::RsaPublic(_) | Key::RsaPrivate(_) => "RSA",
+            Key::Oct(_) => "oct",
+        }
+    }
+
+    // the required public members, in lexicographic order as RFC 7638 wants
+    fn required_members(&self) -> Vec<(String, Value)> {
+        let rsa = |pk: &rsa::PublicKey| vec![
+            ("e".to_string(), encode_uint(pk.e())), ("kty".to_string(), "RSA".into()), ("n".to_string(), encode_uint(pk.n())),
+        ];
+        match &self.key {
+            Key::RsaPublic(pk) => rsa(pk),
+            Key::RsaPrivate(sk) => rsa(&rsa::PublicKey::from_private_key(sk)),
+            Key::Oct(k) => vec![("k".to_string(), Value::String(URL_SAFE_NO_PAD.encode(k))), ("kty".to_string(), "oct".into())],
+        }
+    }
+
+    /// RFC 7638 thumbprint: base64url SHA-256 of the required members
+    pub fn thumbprint(&self) -> String {
+        URL_SAFE_NO_PAD.encode(&sha256(Value::Object(self.required_members()).to_string().as_bytes()))
+    }
+
+    /// The public part of an RSA key, with the same `kid`, `use` and `alg`; `None` for
+    /// symmetric keys, which have none.
+    pub fn to_public(&self) -> Option<Jwk> {
+        let pk = match &self.key {
+            Key::RsaPublic(pk) => rsa::PublicKey::new(pk.n().clone(), pk.e().clone()),
+            Key::RsaPrivate(sk) => rsa::PublicKey::from_private_key(sk),
+            Key::Oct(_) => return None,
+        };
+        Some(Jwk {key: Key::RsaPublic(pk), kid: self.kid.clone(), usage: self.usage.clone(), alg: self.alg.clone()})
+    }
+
+    fn to_value(&self) -> Value {
+        let mut members = vec![("kty".to_string(), self.kty().into())];
+        for (name, value) in [("kid", &self.kid), ("use", &self.usage), ("alg", &self.alg)].iter() {
+            if let Some(v) = value {
+                members.push((name.to_string(), v.as_str().into()));
+            }
+        }
+        let mut rsa = |pk: &rsa::PublicKey| {
+            members.push(("n".to_string(), encode_uint(pk.n())));
+            members.push(("e".to_string(), encode_uint(pk.e())));
+        };
+        match &self.key {
+            Key::RsaPublic(pk) => rsa(pk),
+            Key::RsaPrivate(sk) => {
+                rsa(&rsa::PublicKey::from_private_key(sk));
+                let (dp, dq, qi) = sk.crt_params();
+                for (name, x) in [("d", sk.d()), ("p", sk.p()), ("q", sk.q()), ("dp", &dp), ("dq", &dq), ("qi", &qi)].iter() {
+                    members.push((name.to_string(), encode_uint(x)));
+                }
+            },
+            Key::Oct(k) => members.push(("k".to_string(), Value::String(URL_SAFE_NO_PAD.encode(k)))),
+        }
+        Value::Object(members)
+    }
+
+    /// Compact JSON, members in the order `kty`, `kid`, `use`, `alg`, then the key's
+    pub fn to_json(&self) -> String {
+        self.to_value().to_string()
+    }
+
+    fn from_value(v: &Value) -> Result<Jwk,Error> {
+        let key = match member(v, "kty")? {
+            "RSA" => {
+                let pk = rsa::PublicKey::new(uint_member(v, "n")?, uint_member(v, "e")?);
+                if pk.n() <= &BigUint::from(1u8) || pk.e() <= &BigUint::from(1u8) {
+                    return Err(Error::InvalidKey);
+                }
+                if v.get("d").is_none() {
+                    Key::RsaPublic(pk)
+                } else {
+                    // keys given by d alone, or with more than two primes, are not supported
+                    if v.get("p").is_none() || v.get("oth").is_some() {
+                        return Err(Error::InvalidKey);
+                    }
+                    let sk = rsa::PrivateKey::from_components(&pk, &uint_member(v, "d")?, &uint_member(v, "p")?,
+                        &uint_member(v, "q")?).ok_or(Error::InvalidKey)?;
+                    let crt = (uint_member(v, "dp")?, uint_member(v, "dq")?, uint_member(v, "qi")?);
+                    if crt != sk.crt_params() {
+                        return Err(Error::InvalidKey);
+                    }
+                    Key::RsaPrivate(sk)
+                }
+            },
+            "oct" => Key::Oct(bytes_member(v, "k")?),
+            kty => return Err(Error::UnsupportedKeyType(kty.to_string())),
+        };
+        Ok(Jwk {key, kid: optional_member(v, "kid"), usage: optional_member(v, "use"), alg: optional_member(v, "alg")})
+    }
+
+    pub fn from_json(text: &str) -> Result<Jwk,Error> {
+        Jwk::from_value(&Value::parse(text)?)
+    }
+}
+
+/// A JWK Set, `{"keys":[...]}`.
+#[derive(Default)]
+pub struct JwkSet {
+    pub keys: Vec<Jwk>,
+}
+
+impl JwkSet {
+    pub fn new() -> JwkSet {
+        JwkSet {keys: Vec::new()}
+    }
+
+    pub fn with_key(mut self, key: Jwk) -> Self {
+        self.keys.push(key);
+        self
+    }
+
+    /// The first key with `kid`.
+    pub fn find(&self, kid: &str) -> Option<&Jwk> {
+        self.keys.iter().find(|k| k.kid.as_deref() == Some(kid))
+    }
+
+    /// The public parts of the RSA keys, as published at a JWKS endpoint; symmetric keys
+    /// are left out.
+    pub fn to_public(&self) -> JwkSet {
+        JwkSet {keys: self.keys.iter().filter_map(Jwk::to_public).collect()}
+    }
+
+    pub fn to_json(&self) -> String {
+        let keys = Value::Array(self.keys.iter().map(Jwk::to_value).collect());
+        Value::Object(vec![("keys".to_string(), keys)]).to_string()
+    }
+
+    /// Keys of other types than RSA and oct are skipped, as RFC 7517 section 5 suggests;
+    /// any other invalid key fails the whole set.
+    pub fn from_json(text: &str) -> Result<JwkSet,Error> {
+        let v = Value::parse(text)?;
+        let keys = v.get("keys").and_then(Value::as_array).ok_or(Error::MissingMember("keys"))?;
+        let mut set = JwkSet::new();
+        for k in keys.iter() {
+            match Jwk::from_value(k) {
+                Ok(jwk) => set.keys.push(jwk),
+                Err(Error::UnsupportedKeyType(_)) => {},
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(set)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utility::pem::Pem;
+
+    // the 512-bit key of the rsa tests; members as Python's `cryptography` computes them
+    const PRIVATE_PEM: &str = include_str!("pkcs8/testdata/key.pem");
+    const PRIVATE_JWK: &str = "{\"kty\":\"RSA\",\
+        \"n\":\"yftQHft2DItDldyv_rCEm5LV_LfcMJ5Yyj0M-Tl9E9HtjkUtCSfp_ZCdOXvhv0T2QDazG6UpZv8KlknbxR-now\",\"e\":\"AQAB\",\
+        \"d\":\"ZofAX7fqcOuw3z4ryOnqJt8e6S6cA8Id6GudTlY0e_5JTRHLSbkfGvKbn4ccieXKP-q7Qncn6BJrA4PNkAAJYQ\",\
+        \"p\":\"9OwMMciNDiXKDSJEMLGIPk0bwqoFLh7ONxVbsOPERhc\",\"q\":\"0x4PDk8hTQWIZcg21EoUWVk6EIH7Qf2H0YTRWUxt7lU\",\
+        \"dp\":\"6tEZC-3clvDSG-97czfyiNDCycraNpKgYR1gEGL-_bc\",\"dq\":\"PjAnjTZ__7k2gYzlockkj2E6Omyff8PYAz7VkDF0XHU\",\
+        \"qi\":\"cG8JQlIfuFEiWzeBAEwXYYqLFf6_sFuD0EKIdSkHHag\"}";
+
+    #[test]
+    fn test_rsa() {
+        let sk = rsa::PrivateKey::from_pem(PRIVATE_PEM).unwrap();
+        let jwk = Jwk::new(Key::RsaPrivate(sk));
+        assert_eq!(jwk.to_json(),PRIVATE_JWK);
+        let parsed = Jwk::from_json(PRIVATE_JWK).unwrap();
+        assert_eq!(parsed.to_json(),PRIVATE_JWK);
+
+        let public = jwk.with_kid("k1").with_usage("sig").with_alg("RS256").to_public().unwrap();
+        assert_eq!(public.to_json(),"{\"kty\":\"RSA\",\"kid\":\"k1\",\"use\":\"sig\",\"alg\":\"RS256\",\
+            \"n\":\"yftQHft2DItDldyv_rCEm5LV_LfcMJ5Yyj0M-Tl9E9HtjkUtCSfp_ZCdOXvhv0T2QDazG6UpZv8KlknbxR-now\",\"e\":\"AQAB\"}");
+        assert_eq!(public.thumbprint(),parsed.thumbprint());
+        assert_eq!(public.thumbprint(),"ugEjt2Sd1cAqi5UyNGbdiH9ekcb8JsBFsUan7dX-Ezc");
+    }
+
+    #[test]
+    fn test_thumbprint() {
+        // RFC 7638 section 3.1
+        let jwk = Jwk::from_json("{\"kty\":\"RSA\",\
+            \"n\":\"0vx7agoebGcQSuuPiLJXZptN9nndrQmbXEps2aiAFbWhM78LhWx4cbbfAAtVT86zwu1RK7aPFFxuhDR1L6tSoc_BJECPebWKRXjB\
+            ZCiFV4n3oknjhMstn64tZ_2W-5JsGY4Hc5n9yBXArwl93lqt7_RN5w6Cf0h4QyQ5v-65YGjQR0_FDW2QvzqY368QQMicAtaSqzs8KJZgnYb9c7d0zgdAZHzu6qMQvRL\
+            5hajrn1n91CbOpbISD08qNLyrdkt-bFTWhAI4vMQFh6WeZu0fM4lFd2NcRwr3XPksINHaQ-G_xBniIqbw0Ls1jF44-csFCur-kEgU8awapJzKnqDKgw\",\
+            \"e\":\"AQAB\",\"alg\":\"RS256\",\"kid\":\"2011-04-29\"}").unwrap();
+        assert_eq!(jwk.thumbprint(),"NzbLsXh8uDCcd-6MNwXF4W_7noWXFZAfHkxZsRGC9Xs");
+        assert_eq!(jwk.kid.as_deref(),Some("2011-04-29"));
+        assert_eq!(jwk.alg.as_deref(),Some("RS256"));
+    }
+
+    #[test]
+    fn test_oct() {
+        // RFC 7517 appendix A.3
+        let text = "{\"kty\":\"oct\",\"alg\":\"A128KW\",\"k\":\"GawgguFyGrWKav7AX4VKUg\"}";
+        let jwk = Jwk::from_json(text).unwrap();
+        match &jwk.key {
+            Key::Oct(k) => assert_eq!(k.len(),16),
+            _ => panic!("not an oct key"),
+        }
+        assert_eq!(jwk.to_json(),text);
+        assert!(jwk.to_public().is_none());
+        assert_eq!(jwk.thumbprint(),Jwk::new(Key::Oct(URL_SAFE_NO_PAD.decode("GawgguFyGrWKav7AX4VKUg").unwrap())).thumbprint());
+    }
+
+    #[test]
+    fn test_set() {
+        let sk = rsa::PrivateKey::from_pem(PRIVATE_PEM).unwrap();
+        let set = JwkSet::new()
+            .with_key(Jwk::new(Key::RsaPrivate(sk)).with_thumbprint_kid())
+            .with_key(Jwk::new(Key::Oct(vec![7;32])).with_kid("hmac"));
+        assert_eq!(set.find("hmac").map(Jwk::kty),Some("oct"));
+        assert_eq!(set.find("ugEjt2Sd1cAqi5UyNGbdiH9ekcb8JsBFsUan7dX-Ezc").map(Jwk::kty),Some("RSA"));
+        assert!(set.find("missing").is_none());
+
+        let published = set.to_public();
+        assert_eq!(published.keys.len(),1);
+        let text = published.to_json();
+        assert!(text.starts_with("{\"keys\":[{\"kty\":\"RSA\",\"kid\":\"ugEjt2Sd1cAqi5UyNGbdiH9ekcb8JsBFsUan7dX-Ezc\",\"n\":"));
+        assert!(!text.contains("\"d\""));
+
+        let with_ec = text.replace("[{", "[{\"kty\":\"EC\",\"crv\":\"P-256\"},{");
+        let parsed = JwkSet::from_json(&with_ec).unwrap();
+        assert_eq!(parsed.keys.len(),1);
+        assert_eq!(parsed.to_json(),text);
+        assert_eq!(JwkSet::from_json("{}").err(),Some(Error::MissingMember("keys")));
+        assert_eq!(JwkSet::from_json("{\"keys\":[{\"kty\":\"oct\"}]}").err(),Some(Error::MissingMember("k")));
+    }
+
+    #[test]
+    fn test_invalid() {
+        let check = |from: &str, to: &str, err: Error| {
+            assert_eq!(Jwk::from_json(&PRIVATE_JWK.replace(from, to)).err(),Some(err));
+        };
+        check("\"e\":\"AQAB\"", "\"e\":\"AAEAAQ\"", Error::InvalidMember("e"));
+        check("\"e\":\"AQAB\"", "\"e\":\"AQAB=\"", Error::InvalidMember("e"));
+        check("\"e\":\"AQAB\"", "\"e\":65537", Error::MissingMember("e"));
+        check("\"e\":\"AQAB\"", "\"e\":\"AQAD\"", Error::InvalidKey);
+        check("\"qi\":\"cG8J", "\"qi\":\"cG8K", Error::InvalidKey);
+        check("\"p\":", "\"x\":", Error::InvalidKey);
+        check("\"kty\":\"RSA\"", "\"kty\":\"EC\"", Error::UnsupportedKeyType("EC".to_string()));
+        check("{", "[", Error::Json(json::Error::UnexpectedChar(6)));
+
+        // p == q has no CRT coefficient, and used to panic computing it
+        let equal_primes = "{\"kty\":\"RSA\",\"n\":\"CQ\",\"e\":\"Aw\",\"d\":\"AQ\",\"p\":\"Aw\",\"q\":\"Aw\",\"dp\":\"AQ\",\"dq\":\"AQ\",\"qi\":\"AQ\"}";
+        assert_eq!(Jwk::from_json(equal_primes).err(),Some(Error::InvalidKey));
+    }
+}
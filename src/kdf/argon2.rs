@@ -0,0 +1,521 @@
+// Argon2 (RFC 9106): Argon2d, Argon2i and Argon2id at version 0x13, and password hashes
+// in the PHC string format (`$argon2id$v=19$m=65536,t=3,p=4$<salt>$<hash>`)
+//
+// Memory is kept as one vector of blocks per segment, indexed by slice and lane, so the
+// lanes of a slice are filled in parallel threads that each borrow their own segments
+// mutably and the finished slices shared. There are at most as many threads as the
+// available parallelism, each taking a run of neighbouring lanes.
+use crate::blake2b::blake2b;
+use crate::rng::Rng;
+use crate::utility::base64::STANDARD_NO_PAD;
+use crate::utility::ct;
+use std::convert::TryInto;
+use std::fmt;
+use std::thread;
+
+const VERSION: u32 = 0x13;
+const SYNC_POINTS: usize = 4;
+const BLOCK_WORDS: usize = 128;
+const SALT_LEN: usize = 16;
+const HASH_LEN: usize = 32;
+
+/// Default memory limit: 1 GiB.
+pub const DEFAULT_MAX_MEMORY: usize = 1 << 30;
+/// Default limit on passes, so that a PHC string cannot demand unbounded work.
+pub const DEFAULT_MAX_TIME_COST: u32 = 32;
+
+type Block = [u64;BLOCK_WORDS];
+
+#[derive(Copy,Clone,Debug,PartialEq,Eq)]
+pub enum Variant {
+    Argon2d = 0,
+    Argon2i = 1,
+    Argon2id = 2,
+}
+
+impl Variant {
+    pub fn name(self) -> &'static str {
+        match self {
+            Variant::Argon2d => "argon2d",
+            Variant::Argon2i => "argon2i",
+            Variant::Argon2id => "argon2id",
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Variant> {
+        [Variant::Argon2d, Variant::Argon2i, Variant::Argon2id].iter().cloned().find(|v| v.name() == name)
+    }
+}
+
+#[derive(Clone,Debug,PartialEq,Eq)]
+pub enum Error {
+    /// Fewer than 8 KiB per lane
+    InvalidMemoryCost,
+    /// No passes
+    InvalidTimeCost,
+    /// Lanes not in 1..2^24
+    InvalidLanes,
+    /// A salt shorter than 8 bytes
+    SaltTooShort,
+    /// A tag shorter than 4 bytes
+    OutputTooShort,
+    /// The parameters need this many bytes, more than the limit
+    MemoryLimitExceeded(usize),
+    /// The parameters ask for this many passes, more than the limit
+    TimeCostLimitExceeded(u32),
+    /// A PHC string that does not parse, naming the offending field
+    InvalidHash(&'static str),
+    UnsupportedVersion(u32),
+    /// The password does not match the hash
+    VerificationFailed,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::InvalidMemoryCost => write!(f, "memory cost below 8 KiB per lane"),
+            Error::InvalidTimeCost => write!(f, "time cost must be positive"),
+            Error::InvalidLanes => write!(f, "invalid number of lanes"),
+            Error::SaltTooShort => write!(f, "salt shorter than 8 bytes"),
+            Error::OutputTooShort => write!(f, "output shorter than 4 bytes"),
+            Error::MemoryLimitExceeded(n) => write!(f, "{} bytes needed, more than the memory limit", n),
+            Error::TimeCostLimitExceeded(t) => write!(f, "{} passes requested, more than the limit", t),
+            Error::InvalidHash(field) => write!(f, "invalid password hash: bad {}", field),
+            Error::UnsupportedVersion(v) => write!(f, "unsupported Argon2 version {}", v),
+            Error::VerificationFailed => write!(f, "password verification failed"),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+// H' (RFC 9106 section 3.3): BLAKE2b stretched to `len` bytes
+fn h_prime(input: &[u8], len: usize) -> Vec<u8> {
+    let input = [&(len as u32).to_le_bytes()[..], input].concat();
+    if len <= 64 {
+        return blake2b(&input, &[], len);
+    }
+    let r = len.div_ceil(32) - 2;
+    let mut out = Vec::with_capacity(len);
+    let mut v = blake2b(&input, &[], 64);
+    for _ in 1..r {
+        out.extend_from_slice(&v[..32]);
+        v = blake2b(&v, &[], 64);
+    }
+    out.extend_from_slice(&v[..32]);
+    out.extend_from_slice(&blake2b(&v, &[], len - 32 * r));
+    out
+}
+
+fn block_from_bytes(bytes: &[u8]) -> Block {
+    let mut block = [0u64;BLOCK_WORDS];
+    for (w, b) in block.iter_mut().zip(bytes.chunks(8)) {
+        *w = u64::from_le_bytes(b.try_into().unwrap());
+    }
+    block
+}
+
+// the BLAKE2b round function with multiplications added (BlaMka)
+fn gb(v: &mut Block, a: usize, b: usize, c: usize, d: usize) {
+    let f = |x: u64, y: u64| x.wrapping_add(y).wrapping_add(2u64.wrapping_mul(x & 0xffffffff).wrapping_mul(y & 0xffffffff));
+    v[a] = f(v[a], v[b]);
+    v[d] = (v[d] ^ v[a]).rotate_right(32);
+    v[c] = f(v[c], v[d]);
+    v[b] = (v[b] ^ v[c]).rotate_right(24);
+    v[a] = f(v[a], v[b]);
+    v[d] = (v[d] ^ v[a]).rotate_right(16);
+    v[c] = f(v[c], v[d]);
+    v[b] = (v[b] ^ v[c]).rotate_right(63);
+}
+
+// the permutation P on the 16 words of `block` at `idx`
+fn permute(block: &mut Block, idx: &[usize;16]) {
+    for &(a, b, c, d) in [(0, 4, 8, 12), (1, 5, 9, 13), (2, 6, 10, 14), (3, 7, 11, 15),
+                          (0, 5, 10, 15), (1, 6, 11, 12), (2, 7, 8, 13), (3, 4, 9, 14)].iter() {
+        gb(block, idx[a], idx[b], idx[c], idx[d]);
+    }
+}
+
+// the compression function G (section 3.5): P over the rows, then the columns
+fn compress(x: &Block, y: &Block) -> Block {
+    let mut r = [0u64;BLOCK_WORDS];
+    for i in 0..BLOCK_WORDS {
+        r[i] = x[i] ^ y[i];
+    }
+    let mut z = r;
+    for i in 0..8 {
+        let idx: [usize;16] = std::array::from_fn(|j| 16 * i + j);
+        permute(&mut z, &idx);
+    }
+    for i in 0..8 {
+        let idx: [usize;16] = std::array::from_fn(|j| 2 * i + 16 * (j / 2) + j % 2);
+        permute(&mut z, &idx);
+    }
+    for (z, r) in z.iter_mut().zip(r.iter()) {
+        *z ^= r;
+    }
+    z
+}
+
+// Block geometry and the position being filled
+struct Position {
+    pass: usize,
+    slice: usize,
+    lane: usize,
+}
+
+struct Instance {
+    variant: Variant,
+    passes: usize,
+    lanes: usize,
+    lane_len: usize,
+    segment_len: usize,
+}
+
+impl Instance {
+    // the block at `index` of `lane` outside the current slice
+    fn block<'a>(&self, before: &'a [Vec<Vec<Block>>], after: &'a [Vec<Vec<Block>>], slice: usize, lane: usize,
+            index: usize) -> &'a Block {
+        let (s, offset) = (index / self.segment_len, index % self.segment_len);
+        if s < slice {
+            &before[s][lane][offset]
+        } else {
+            &after[s - slice - 1][lane][offset]
+        }
+    }
+
+    fn fill_segment(&self, before: &[Vec<Vec<Block>>], after: &[Vec<Vec<Block>>], pos: Position, segment: &mut [Block]) {
+        let Position {pass, slice, lane} = pos;
+        let independent = match self.variant {
+            Variant::Argon2d => false,
+            Variant::Argon2i => true,
+            Variant::Argon2id => pass == 0 && slice < SYNC_POINTS / 2,
+        };
+        let zero = [0u64;BLOCK_WORDS];
+        let mut input = [0u64;BLOCK_WORDS];
+        let header = [pass, lane, slice, self.lane_len * self.lanes, self.passes, self.variant as usize];
+        for (i, v) in header.iter().enumerate() {
+            input[i] = *v as u64;
+        }
+        let mut addresses = zero;
+
+        let start = if pass == 0 && slice == 0 {2} else {0};
+        for offset in start..self.segment_len {
+            if independent && (offset % BLOCK_WORDS == 0 || offset == start) {
+                input[6] += 1;
+                addresses = compress(&zero, &compress(&zero, &input));
+            }
+            let prev = if offset > 0 {
+                segment[offset - 1]
+            } else {
+                *self.block(before, after, slice, lane, (slice * self.segment_len + self.lane_len - 1) % self.lane_len)
+            };
+            let pseudo = if independent {addresses[offset % BLOCK_WORDS]} else {prev[0]};
+            let (j1, j2) = (pseudo & 0xffffffff, pseudo >> 32);
+
+            // the reference index (section 3.4.1.2)
+            let ref_lane = if pass == 0 && slice == 0 {lane} else {j2 as usize % self.lanes};
+            let same_lane = ref_lane == lane;
+            let finished = if pass == 0 {slice * self.segment_len} else {self.lane_len - self.segment_len};
+            let area = if same_lane {finished + offset - 1} else {finished - (offset == 0) as usize};
+            let y = (area as u64 * ((j1 * j1) >> 32)) >> 32;
+            let window_start = if pass == 0 {0} else {(slice + 1) % SYNC_POINTS * self.segment_len};
+            let ref_index = (window_start + area - 1 - y as usize) % self.lane_len;
+
+            let mut block = if same_lane && ref_index / self.segment_len == slice {
+                compress(&prev, &segment[ref_index % self.segment_len])
+            } else {
+                compress(&prev, self.block(before, after, slice, ref_lane, ref_index))
+            };
+            if pass > 0 {
+                block.iter_mut().zip(segment[offset].iter()).for_each(|(b, o)| *b ^= o);
+            }
+            segment[offset] = block;
+        }
+    }
+}
+
+/// Argon2 with its cost parameters, optional secret and associated data, and the memory
+/// limit checked before hashing.
+#[derive(Clone)]
+pub struct Argon2 {
+    variant: Variant,
+    m_cost: u32,
+    t_cost: u32,
+    lanes: u32,
+    secret: Vec<u8>,
+    ad: Vec<u8>,
+    max_memory: usize,
+    max_time_cost: u32,
+}
+
+impl Argon2 {
+    /// `m_cost` in KiB, `t_cost` passes, `lanes` computed in parallel.
+    pub fn new(variant: Variant, m_cost: u32, t_cost: u32, lanes: u32) -> Result<Argon2,Error> {
+        if lanes == 0 || lanes >= 1 << 24 {
+            return Err(Error::InvalidLanes);
+        }
+        if t_cost == 0 {
+            return Err(Error::InvalidTimeCost);
+        }
+        if (m_cost as u64) < 8 * lanes as u64 {
+            return Err(Error::InvalidMemoryCost);
+        }
+        Ok(Argon2 {variant, m_cost, t_cost, lanes, secret: Vec::new(), ad: Vec::new(), max_memory: DEFAULT_MAX_MEMORY,
+            max_time_cost: DEFAULT_MAX_TIME_COST})
+    }
+
+    /// Argon2id with 64 MiB, 3 passes and 4 lanes, the second recommended option of
+    /// RFC 9106 section 4
+    pub fn recommended() -> Argon2 {
+        Argon2::new(Variant::Argon2id, 1 << 16, 3, 4).unwrap()
+    }
+
+    /// A key mixed into every hash, e.g. a pepper kept out of the password database.
+    pub fn with_secret(mut self, secret: &[u8]) -> Self {
+        self.secret = secret.to_vec();
+        self
+    }
+
+    pub fn with_associated_data(mut self, ad: &[u8]) -> Self {
+        self.ad = ad.to_vec();
+        self
+    }
+
+    pub fn with_max_memory(mut self, bytes: usize) -> Self {
+        self.max_memory = bytes;
+        self
+    }
+
+    pub fn with_max_time_cost(mut self, passes: u32) -> Self {
+        self.max_time_cost = passes;
+        self
+    }
+
+    /// A `len`-byte tag of `password` and `salt`.
+    pub fn hash(&self, password: &[u8], salt: &[u8], len: usize) -> Result<Vec<u8>,Error> {
+        let workers = thread::available_parallelism().map_or(1, |n| n.get());
+        self.hash_with_workers(password, salt, len, workers)
+    }
+
+    fn hash_with_workers(&self, password: &[u8], salt: &[u8], len: usize, workers: usize) -> Result<Vec<u8>,Error> {
+        if salt.len() < 8 {
+            return Err(Error::SaltTooShort);
+        }
+        if len < 4 {
+            return Err(Error::OutputTooShort);
+        }
+        if self.t_cost > self.max_time_cost {
+            return Err(Error::TimeCostLimitExceeded(self.t_cost));
+        }
+        let lanes = self.lanes as usize;
+        let lane_len = (self.m_cost as usize / (SYNC_POINTS * lanes)) * SYNC_POINTS;
+        let memory = (lane_len * lanes).saturating_mul(1024);
+        if memory > self.max_memory {
+            return Err(Error::MemoryLimitExceeded(memory));
+        }
+        let instance = Instance {variant: self.variant, passes: self.t_cost as usize, lanes, lane_len,
+            segment_len: lane_len / SYNC_POINTS};
+
+        let mut h0_input = Vec::new();
+        for v in [self.lanes, len as u32, self.m_cost, self.t_cost, VERSION, self.variant as u32].iter() {
+            h0_input.extend_from_slice(&v.to_le_bytes());
+        }
+        for field in [password, salt, &self.secret, &self.ad].iter() {
+            h0_input.extend_from_slice(&(field.len() as u32).to_le_bytes());
+            h0_input.extend_from_slice(field);
+        }
+        let h0 = blake2b(&h0_input, &[], 64);
+
+        // memory[slice][lane] is a segment
+        let mut memory = vec![vec![vec![[0u64;BLOCK_WORDS];instance.segment_len];lanes];SYNC_POINTS];
+        for (lane, segment) in memory[0].iter_mut().enumerate() {
+            for (i, block) in segment.iter_mut().take(2).enumerate() {
+                let input = [&h0[..], &(i as u32).to_le_bytes(), &(lane as u32).to_le_bytes()].concat();
+                *block = block_from_bytes(&h_prime(&input, 1024));
+            }
+        }
+
+        let chunk = lanes.div_ceil(workers.clamp(1, lanes));
+        for pass in 0..instance.passes {
+            for slice in 0..SYNC_POINTS {
+                let (before, rest) = memory.split_at_mut(slice);
+                let (current, after) = rest.split_first_mut().unwrap();
+                let (before, after, instance) = (&*before, &*after, &instance);
+                let fill = move |first: usize, segments: &mut [Vec<Block>]| {
+                    for (i, segment) in segments.iter_mut().enumerate() {
+                        instance.fill_segment(before, after, Position {pass, slice, lane: first + i}, segment);
+                    }
+                };
+                if chunk == lanes {
+                    fill(0, current);
+                } else {
+                    thread::scope(|s| {
+                        for (i, segments) in current.chunks_mut(chunk).enumerate() {
+                            s.spawn(move || fill(i * chunk, segments));
+                        }
+                    });
+                }
+            }
+        }
+
+        let mut last = [0u64;BLOCK_WORDS];
+        for segment in memory[SYNC_POINTS - 1].iter() {
+            last.iter_mut().zip(segment[instance.segment_len - 1].iter()).for_each(|(l, b)| *l ^= b);
+        }
+        let bytes: Vec<u8> = last.iter().flat_map(|w| w.to_le_bytes().to_vec()).collect();
+        Ok(h_prime(&bytes, len))
+    }
+
+    /// Hashes `password` with a random 16-byte salt into a PHC string with a 32-byte tag.
+    /// The secret and associated data are not part of the string.
+    pub fn hash_password(&self, password: &[u8], rng: &mut Rng) -> Result<String,Error> {
+        let salt = rng.generate_bytes(SALT_LEN);
+        let hash = self.hash(password, &salt, HASH_LEN)?;
+        Ok(format!("${}$v={}$m={},t={},p={}${}${}", self.variant.name(), VERSION, self.m_cost, self.t_cost, self.lanes,
+            STANDARD_NO_PAD.encode(&salt), STANDARD_NO_PAD.encode(&hash)))
+    }
+
+    /// Checks `password` against a PHC string. Variant and costs come from the string;
+    /// the secret, associated data and memory and time limits from `self`.
+    pub fn verify_password(&self, phc: &str, password: &[u8]) -> Result<(),Error> {
+        let fields: Vec<&str> = phc.split('$').collect();
+        if fields.len() != 6 || !fields[0].is_empty() {
+            return Err(Error::InvalidHash("format"));
+        }
+        let variant = Variant::from_name(fields[1]).ok_or(Error::InvalidHash("algorithm"))?;
+        let version = fields[2].strip_prefix("v=").and_then(decimal).ok_or(Error::InvalidHash("version"))?;
+        if version != VERSION {
+            return Err(Error::UnsupportedVersion(version));
+        }
+        let costs: Vec<Option<u32>> = fields[3].split(',').zip(["m=", "t=", "p="].iter())
+            .map(|(f, name)| f.strip_prefix(name).and_then(decimal)).collect();
+        let (m_cost, t_cost, lanes) = match costs.as_slice() {
+            [Some(m), Some(t), Some(p)] if fields[3].split(',').count() == 3 => (*m, *t, *p),
+            _ => return Err(Error::InvalidHash("parameters")),
+        };
+        let salt = STANDARD_NO_PAD.decode(fields[4]).map_err(|_| Error::InvalidHash("salt"))?;
+        let expected = STANDARD_NO_PAD.decode(fields[5]).map_err(|_| Error::InvalidHash("hash"))?;
+
+        let argon2 = Argon2 {variant, secret: self.secret.clone(), ad: self.ad.clone(), max_memory: self.max_memory,
+            max_time_cost: self.max_time_cost,
+            ..Argon2::new(variant, m_cost, t_cost, lanes)?};
+        let hash = argon2.hash(password, &salt, expected.len())?;
+        if ct::bytes_eq(&hash, &expected) {
+            Ok(())
+        } else {
+            Err(Error::VerificationFailed)
+        }
+    }
+}
+
+// a PHC decimal: digits without leading zeros
+fn decimal(s: &str) -> Option<u32> {
+    if s.is_empty() || !s.bytes().all(|c| c.is_ascii_digit()) || (s.starts_with('0') && s.len() > 1) {
+        return None;
+    }
+    s.parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utility::codec::hex;
+
+    #[test]
+    fn test_rfc9106() {
+        // section 5: 32 KiB, 3 passes, 4 lanes, with secret and associated data
+        let cases = [
+            (Variant::Argon2d, "512b391b6f1162975371d30919734294f868e3be3984f3c1a13a4db9fabe4acb"),
+            (Variant::Argon2i, "c814d9d1dc7f37aa13f0d77f2494bda1c8de6b016dd388d29952a4c4672b6ce8"),
+            (Variant::Argon2id, "0d640df58d78766c08c037a34a8b53c9d01ef0452d75b65eb52520e96b01e659"),
+        ];
+        for (variant, tag) in cases.iter() {
+            let argon2 = Argon2::new(*variant, 32, 3, 4).unwrap().with_secret(&[3;8]).with_associated_data(&[4;12]);
+            assert_eq!(argon2.hash(&[1;32], &[2;16], 32).unwrap(),hex::decode(tag).unwrap());
+        }
+    }
+
+    #[test]
+    fn test_hash() {
+        // values from Python's `cryptography`; a long tag exercises H' beyond 64 bytes
+        let argon2 = Argon2::new(Variant::Argon2i, 64, 1, 2).unwrap();
+        assert_eq!(argon2.hash(b"pw", b"saltsaltsalt", 100).unwrap(),hex::decode(
+            "11e73c3240f89a1334cc4944f2ac0a1c3d3700189429aa2827e5934be47b1044646010a1c352d2af46e36b4398426a37\
+             88d306efc9b6afa804b43fd2a80c4d10073f3bd94e6fc2be8ecbb7d6aa6bba3287acdee0fb95fbb63e8f733008347de3\
+             12c39736").unwrap());
+        let argon2 = Argon2::new(Variant::Argon2id, 19456, 2, 1).unwrap();
+        assert_eq!(argon2.hash(b"password", b"somesaltsomesalt", 32).unwrap(),
+            hex::decode("2b5dc4054886ec957ef59c73b661c54dd6fb274590b278f657c6d96aac8fa6d1").unwrap());
+    }
+
+    #[test]
+    fn test_workers() {
+        // more lanes than threads, split evenly and unevenly; from Python's `cryptography`
+        let argon2 = Argon2::new(Variant::Argon2id, 512, 2, 64).unwrap();
+        let tag = hex::decode("9e7d3c0d85f0df200685c2e9ad530b3e3567612c50c40aadb3a7243c82f5b8c4").unwrap();
+        for workers in [1, 3, 8, 64, 100].iter() {
+            assert_eq!(argon2.hash_with_workers(b"password", b"somesaltsomesalt", 32, *workers).unwrap(),tag);
+        }
+        assert_eq!(argon2.hash(b"password", b"somesaltsomesalt", 32).unwrap(),tag);
+    }
+
+    #[test]
+    fn test_params() {
+        assert_eq!(Argon2::new(Variant::Argon2id, 32, 1, 0).err(),Some(Error::InvalidLanes));
+        assert_eq!(Argon2::new(Variant::Argon2id, 32, 1, 1 << 24).err(),Some(Error::InvalidLanes));
+        assert_eq!(Argon2::new(Variant::Argon2id, 32, 0, 1).err(),Some(Error::InvalidTimeCost));
+        assert_eq!(Argon2::new(Variant::Argon2id, 31, 1, 4).err(),Some(Error::InvalidMemoryCost));
+
+        let argon2 = Argon2::new(Variant::Argon2id, 32, 1, 4).unwrap();
+        assert_eq!(argon2.hash(b"pw", b"1234567", 32),Err(Error::SaltTooShort));
+        assert_eq!(argon2.hash(b"pw", b"12345678", 3),Err(Error::OutputTooShort));
+        assert_eq!(argon2.with_max_memory(31 * 1024).hash(b"pw", b"12345678", 32),Err(Error::MemoryLimitExceeded(32 * 1024)));
+        assert_eq!(Argon2::recommended().with_max_memory(1 << 20).hash(b"pw", b"12345678", 32),
+            Err(Error::MemoryLimitExceeded(1 << 26)));
+        assert_eq!(Argon2::new(Variant::Argon2id, 32, 33, 1).unwrap().hash(b"pw", b"12345678", 32),
+            Err(Error::TimeCostLimitExceeded(33)));
+        assert!(Argon2::new(Variant::Argon2id, 32, 33, 1).unwrap().with_max_time_cost(33).hash(b"pw", b"12345678", 32).is_ok());
+    }
+
+    #[test]
+    fn test_phc() {
+        // from Python's `cryptography`; m = 100 is rounded down to 96 KiB for 3 lanes
+        let phc = "$argon2id$v=19$m=100,t=2,p=3$c29tZXNhbHRzb21lc2FsdA$suCb5f3kXp455yDxcVjIY1PDRV91oB88wpDxT23aUac";
+        let argon2 = Argon2::recommended();
+        assert_eq!(argon2.verify_password(phc, b"password"),Ok(()));
+        assert_eq!(argon2.verify_password(phc, b"Password"),Err(Error::VerificationFailed));
+        assert_eq!(argon2.clone().with_secret(b"pepper").verify_password(phc, b"password"),Err(Error::VerificationFailed));
+
+        let mut rng = Rng::from_seed([5;32]);
+        let peppered = Argon2::new(Variant::Argon2i, 64, 2, 2).unwrap().with_secret(b"pepper");
+        let phc = peppered.hash_password(b"hunter2", &mut rng).unwrap();
+        assert!(phc.starts_with("$argon2i$v=19$m=64,t=2,p=2$"));
+        assert_eq!(phc.len(),"$argon2i$v=19$m=64,t=2,p=2$".len() + 22 + 1 + 43);
+        assert_eq!(argon2.clone().with_secret(b"pepper").verify_password(&phc, b"hunter2"),Ok(()));
+        assert_eq!(argon2.verify_password(&phc, b"hunter2"),Err(Error::VerificationFailed));
+        assert_ne!(peppered.hash_password(b"hunter2", &mut rng).unwrap(),phc);
+    }
+
+    #[test]
+    fn test_phc_invalid() {
+        let phc = "$argon2id$v=19$m=100,t=2,p=3$c29tZXNhbHRzb21lc2FsdA$suCb5f3kXp455yDxcVjIY1PDRV91oB88wpDxT23aUac";
+        let check = |from: &str, to: &str, err: Error| {
+            assert_eq!(Argon2::recommended().verify_password(&phc.replacen(from, to, 1), b"password"),Err(err));
+        };
+        check("$argon2id", "argon2id", Error::InvalidHash("format"));
+        check("$v=19", "", Error::InvalidHash("format"));
+        check("argon2id", "argon2x", Error::InvalidHash("algorithm"));
+        check("v=19", "v=16", Error::UnsupportedVersion(16));
+        check("v=19", "v=019", Error::InvalidHash("version"));
+        check("m=100,t=2,p=3", "t=2,m=100,p=3", Error::InvalidHash("parameters"));
+        check("p=3", "p=3,keyid=AA", Error::InvalidHash("parameters"));
+        check("t=2", "t=+2", Error::InvalidHash("parameters"));
+        check("t=2", "t=0", Error::InvalidTimeCost);
+        check("m=100", "m=4194304", Error::MemoryLimitExceeded(4194300 * 1024));
+        check("t=2", "t=4294967295", Error::TimeCostLimitExceeded(4294967295));
+        check("c29tZX", "c29tZ!", Error::InvalidHash("salt"));
+        check("$suCb", "$suC=", Error::InvalidHash("hash"));
+        check("$c29tZXNhbHRzb21lc2FsdA", "$c29tZXNhbA", Error::SaltTooShort);
+    }
+}
@@ -0,0 +1,186 @@
+// HKDF (RFC 5869) over HMAC-SHA256, HMAC-SHA384 and HMAC-SHA512
+//
+// `expand_labeled` binds a label and context into `info` with the HkdfLabel encoding of
+// TLS 1.3 (RFC 8446 section 7.1), so that each derived key and IV gets its own name;
+// `tls13_expand_label` is HKDF-Expand-Label itself, with the "tls13 " label prefix.
+use crate::hmac::{hmac_sha256, hmac_sha384, hmac_sha512};
+use std::fmt;
+
+const LABEL_PREFIX: &[u8] = b"tls13 ";
+
+#[derive(Copy,Clone,Debug,PartialEq,Eq)]
+pub enum Hash {
+    Sha256,
+    Sha384,
+    Sha512,
+}
+
+impl Hash {
+    /// HashLen: the size of a pseudorandom key and of each expansion block
+    pub fn output_len(self) -> usize {
+        match self {
+            Hash::Sha256 => 32,
+            Hash::Sha384 => 48,
+            Hash::Sha512 => 64,
+        }
+    }
+
+    fn hmac(self, key: &[u8], msg: &[u8]) -> Vec<u8> {
+        match self {
+            Hash::Sha256 => hmac_sha256(key, msg).to_vec(),
+            Hash::Sha384 => hmac_sha384(key, msg).to_vec(),
+            Hash::Sha512 => hmac_sha512(key, msg).to_vec(),
+        }
+    }
+}
+
+#[derive(Copy,Clone,Debug,PartialEq,Eq)]
+pub enum Error {
+    /// More than 255 blocks of output requested
+    OutputTooLong,
+    /// A pseudorandom key shorter than HashLen
+    PrkTooShort,
+    /// A label or context too long for the HkdfLabel encoding
+    LabelTooLong,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::OutputTooLong => write!(f, "requested output too long"),
+            Error::PrkTooShort => write!(f, "pseudorandom key too short"),
+            Error::LabelTooLong => write!(f, "label or context too long"),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+/// HKDF-Extract: a pseudorandom key from input keying material. An empty `salt` stands
+/// for HashLen zero bytes.
+pub fn extract(hash: Hash, salt: &[u8], ikm: &[u8]) -> Vec<u8> {
+    hash.hmac(salt, ikm)
+}
+
+/// HKDF-Expand: `len` bytes of output keying material bound to `info`.
+pub fn expand(hash: Hash, prk: &[u8], info: &[u8], len: usize) -> Result<Vec<u8>,Error> {
+    if len > 255 * hash.output_len() {
+        return Err(Error::OutputTooLong);
+    }
+    if prk.len() < hash.output_len() {
+        return Err(Error::PrkTooShort);
+    }
+    let mut out = Vec::with_capacity(len + hash.output_len());
+    let mut t = Vec::new();
+    for i in 1..=len.div_ceil(hash.output_len()) as u8 {
+        t = hash.hmac(prk, &[&t, info, &[i]].concat());
+        out.extend_from_slice(&t);
+    }
+    out.truncate(len);
+    Ok(out)
+}
+
+/// Extract followed by expand.
+pub fn hkdf(hash: Hash, salt: &[u8], ikm: &[u8], info: &[u8], len: usize) -> Result<Vec<u8>,Error> {
+    expand(hash, &extract(hash, salt, ikm), info, len)
+}
+
+/// Expands `prk` with an HkdfLabel of `len`, `label` and `context` as info. The label is
+/// used as given, for protocols that choose their own prefix.
+pub fn expand_labeled(hash: Hash, prk: &[u8], label: &[u8], context: &[u8], len: usize) -> Result<Vec<u8>,Error> {
+    if len > 255 * hash.output_len() {
+        return Err(Error::OutputTooLong);
+    }
+    if label.len() > 255 || context.len() > 255 {
+        return Err(Error::LabelTooLong);
+    }
+    let mut info = (len as u16).to_be_bytes().to_vec();
+    info.push(label.len() as u8);
+    info.extend_from_slice(label);
+    info.push(context.len() as u8);
+    info.extend_from_slice(context);
+    expand(hash, prk, &info, len)
+}
+
+/// HKDF-Expand-Label of TLS 1.3: `expand_labeled` with "tls13 " + `label`.
+pub fn tls13_expand_label(hash: Hash, secret: &[u8], label: &str, context: &[u8], len: usize) -> Result<Vec<u8>,Error> {
+    expand_labeled(hash, secret, &[LABEL_PREFIX, label.as_bytes()].concat(), context, len)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sha256::sha256;
+    use crate::utility::codec::hex;
+
+    #[test]
+    fn test_rfc5869() {
+        // test cases 1 to 3
+        let cases: [(Vec<u8>, Vec<u8>, Vec<u8>, _, _); 3] = [
+            (vec![0x0b;22], (0..13).collect(), (0xf0..0xfa).collect(),
+                "077709362c2e32df0ddc3f0dc47bba6390b6c73bb50f9c3122ec844ad7c2b3e5",
+                "3cb25f25faacd57a90434f64d0362f2a2d2d0a90cf1a5a4c5db02d56ecc4c5bf34007208d5b887185865"),
+            ((0..0x50).collect(), (0x60..0xb0).collect(), (0xb0..=0xff).collect(),
+                "06a6b88c5853361a06104c9ceb35b45cef760014904671014a193f40c15fc244",
+                "b11e398dc80327a1c8e7f78c596a49344f012eda2d4efad8a050cc4c19afa97c59045a99cac7827271cb41c65e590e09\
+                 da3275600c2f09b8367793a9aca3db71cc30c58179ec3e87c14c01d5c1f3434f1d87"),
+            (vec![0x0b;22], vec![], vec![],
+                "19ef24a32c717b167f33a91d6f648bdf96596776afdb6377ac434c1c293ccb04",
+                "8da4e775a563c18f715f802a063c5a31b8a11f5c5ee1879ec3454e5f3c738d2d9d201395faa4b61a96c8"),
+        ];
+        for (ikm, salt, info, prk, okm) in cases.iter() {
+            let okm = hex::decode(okm).unwrap();
+            assert_eq!(extract(Hash::Sha256, salt, ikm),hex::decode(prk).unwrap());
+            assert_eq!(expand(Hash::Sha256, &hex::decode(prk).unwrap(), info, okm.len()).unwrap(),okm);
+            assert_eq!(hkdf(Hash::Sha256, salt, ikm, info, okm.len()).unwrap(),okm);
+        }
+    }
+
+    #[test]
+    fn test_sha384_sha512() {
+        let okm = hkdf(Hash::Sha384, b"salt", b"secret", b"info", 42).unwrap();
+        assert_eq!(okm,hex::decode("29c042775183ec5dbc2c085eb49502b15d9e8abe4a4c1ef98e8e0fb95ad5f6a9fa0e85c3523ecd2f6155").unwrap());
+        let prk = extract(Hash::Sha512, b"salt", b"secret");
+        assert_eq!(prk,hex::decode("57e6f1ea12666f2277f8ac044f50ff7eab5e7f3557dbdbca0a21e21a0afa1bd6\
+            260cc09686e369fbee8d2da27296d7c2e4864b75accc2c7477cbecf3a38c9be7").unwrap());
+        assert_eq!(expand(Hash::Sha512, &prk, b"info", 42).unwrap(),
+            hex::decode("90e269f053d383c4b2070be93238adf358f3d67bd7b17ca3de95f10a50a8385e66d8241c8c22c91cee75").unwrap());
+    }
+
+    #[test]
+    fn test_tls13_expand_label() {
+        // RFC 8448 section 3: the early secret and its "derived" secret
+        let early = extract(Hash::Sha256, &[], &[0;32]);
+        assert_eq!(early,hex::decode("33ad0a1c607ec03b09e6cd9893680ce210adf300aa1f2660e1b22e10f170f92a").unwrap());
+        assert_eq!(tls13_expand_label(Hash::Sha256, &early, "derived", &sha256(b""), 32).unwrap(),
+            hex::decode("6f2615a108c702c5678f54fc9dbab69716c076189c48250cebeac3576c3611ba").unwrap());
+
+        let key = tls13_expand_label(Hash::Sha256, &early, "key", &[], 16).unwrap();
+        let iv = tls13_expand_label(Hash::Sha256, &early, "iv", &[], 16).unwrap();
+        assert_ne!(key,iv);
+        assert_eq!(tls13_expand_label(Hash::Sha256, &early, &"x".repeat(250), &[], 16),Err(Error::LabelTooLong));
+        assert_eq!(tls13_expand_label(Hash::Sha256, &early, "key", &[0;256], 16),Err(Error::LabelTooLong));
+    }
+
+    #[test]
+    fn test_expand_labeled() {
+        let prk = [1u8;32];
+        assert_eq!(expand_labeled(Hash::Sha256, &prk, b"tls13 key", b"ctx", 16),
+            tls13_expand_label(Hash::Sha256, &prk, "key", b"ctx", 16));
+        // the info is 00 10, the length-prefixed label and the empty context
+        assert_eq!(expand_labeled(Hash::Sha256, &prk, b"key", &[], 16),
+            expand(Hash::Sha256, &prk, b"\x00\x10\x03key\x00", 16));
+        assert!(expand_labeled(Hash::Sha256, &prk, &[0;255], &[], 16).is_ok());
+        assert_eq!(expand_labeled(Hash::Sha256, &prk, &[0;256], &[], 16),Err(Error::LabelTooLong));
+        assert_eq!(expand_labeled(Hash::Sha256, &prk, b"key", &[], 255 * 32 + 1),Err(Error::OutputTooLong));
+    }
+
+    #[test]
+    fn test_limits() {
+        let prk = [1u8;32];
+        assert_eq!(expand(Hash::Sha256, &prk, &[], 255 * 32).unwrap().len(),255 * 32);
+        assert_eq!(expand(Hash::Sha256, &prk, &[], 255 * 32 + 1),Err(Error::OutputTooLong));
+        assert_eq!(expand(Hash::Sha384, &prk, &[], 16),Err(Error::PrkTooShort));
+        assert!(expand(Hash::Sha256, &prk, &[], 0).unwrap().is_empty());
+    }
+}
@@ -0,0 +1,5 @@
+// Key derivation functions: stretching passwords into keys and expanding shared secrets.
+pub mod pbkdf2;
+pub mod hkdf;
+pub mod scrypt;
+pub mod argon2;
@@ -0,0 +1,73 @@
+// PBKDF2 (RFC 8018 section 5.2) with HMAC-SHA256
+use crate::aes::KeyLength;
+use crate::hmac::hmac_sha256;
+
+/// Iteration count for new password-derived keys, as OWASP recommends for HMAC-SHA256.
+pub const DEFAULT_ITERATIONS: u32 = 600_000;
+
+/// Derives `len` bytes from `password` and `salt`.
+///
+/// Panics if `iterations` is zero or `len` exceeds (2^32 - 1) * 32.
+pub fn pbkdf2_hmac_sha256(password: &[u8], salt: &[u8], iterations: u32, len: usize) -> Vec<u8> {
+    assert!(iterations > 0, "iteration count must be positive");
+    let blocks = len.div_ceil(32);
+    assert!(blocks <= u32::MAX as usize, "derived key too long");
+    let mut out = Vec::with_capacity(blocks * 32);
+    for i in 1..=blocks as u32 {
+        let mut u = hmac_sha256(password, &[salt, &i.to_be_bytes()].concat());
+        let mut t = u;
+        for _ in 1..iterations {
+            u = hmac_sha256(password, &u);
+            t.iter_mut().zip(u.iter()).for_each(|(t, u)| *t ^= u);
+        }
+        out.extend_from_slice(&t);
+    }
+    out.truncate(len);
+    out
+}
+
+/// A key of `keylen` for `aes::AES`, derived from a passphrase.
+pub fn aes_key(password: &[u8], salt: &[u8], iterations: u32, keylen: KeyLength) -> Vec<u8> {
+    pbkdf2_hmac_sha256(password, salt, iterations, keylen as usize / 8)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aes::{CipherMode, AES};
+    use crate::utility::codec::hex;
+
+    #[test]
+    fn test_pbkdf2() {
+        // RFC 7914 section 11
+        assert_eq!(pbkdf2_hmac_sha256(b"passwd", b"salt", 1, 64), hex::decode(
+            "55ac046e56e3089fec1691c22544b605f94185216dde0465e68b9d57c20dacbc\
+             49ca9cccf179b645991664b39d77ef317c71b845b1e30bd509112041d3a19783").unwrap());
+        assert_eq!(pbkdf2_hmac_sha256(b"password", b"salt", 4096, 20),
+            hex::decode("c5e478d59288c841aa530db6845c4c8d962893a0").unwrap());
+        assert_eq!(pbkdf2_hmac_sha256(b"passwordPASSWORDpassword", b"saltSALTsaltSALTsaltSALTsaltSALTsalt", 4096, 40),
+            hex::decode("348c89dbcbd32b2f32d814b8116e84cf2b17347ebc1800181c4e2a1fb8dd53e1c635518c7dac47e9").unwrap());
+        assert_eq!(pbkdf2_hmac_sha256(b"pass\0word", b"sa\0lt", 4096, 16),
+            hex::decode("89b69d0516f829893c696226650a8687").unwrap());
+        assert!(pbkdf2_hmac_sha256(b"password", b"salt", 1, 0).is_empty());
+    }
+
+    #[test]
+    fn test_aes_key() {
+        let key = aes_key(b"password", b"salt", 4096, KeyLength::KL256);
+        assert_eq!(key,pbkdf2_hmac_sha256(b"password", b"salt", 4096, 32));
+        assert_eq!(aes_key(b"password", b"salt", 4096, KeyLength::KL128),key[..16].to_vec());
+        assert_eq!(aes_key(b"password", b"salt", 4096, KeyLength::KL192).len(),24);
+
+        let aes = AES::new(KeyLength::KL256, CipherMode::CBC);
+        let iv = [1u8;16];
+        let cipher = aes.encrypt(&key, &iv, &[0x42;32]).unwrap();
+        assert_eq!(aes.decrypt(&aes_key(b"password", b"salt", 4096, KeyLength::KL256), &iv, &cipher).unwrap(),vec![0x42;32]);
+    }
+
+    #[test]
+    #[should_panic(expected = "iteration count must be positive")]
+    fn test_zero_iterations() {
+        pbkdf2_hmac_sha256(b"password", b"salt", 0, 32);
+    }
+}
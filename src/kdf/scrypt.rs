@@ -0,0 +1,215 @@
+// scrypt (RFC 7914): PBKDF2-HMAC-SHA256 around ROMix with the Salsa20/8 BlockMix
+//
+// ROMix needs 128 * r * N bytes, and the p blocks are mixed one after another.
+// Derivation fails if the parameters need more than the memory limit of `Params`.
+use super::pbkdf2::pbkdf2_hmac_sha256;
+use std::convert::{TryFrom, TryInto};
+use std::fmt;
+
+/// Default memory limit: 1 GiB.
+pub const DEFAULT_MAX_MEMORY: usize = 1 << 30;
+
+#[derive(Copy,Clone,Debug,PartialEq,Eq)]
+pub enum Error {
+    /// N is not a power of two greater than 1, or not below 2^(16r)
+    InvalidCost,
+    /// r or p is zero, or r * p is not below 2^30
+    InvalidBlockSize,
+    /// The parameters need this many bytes, more than the limit
+    MemoryLimitExceeded(usize),
+    OutputTooLong,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::InvalidCost => write!(f, "invalid cost parameter N"),
+            Error::InvalidBlockSize => write!(f, "invalid block size or parallelism"),
+            Error::MemoryLimitExceeded(n) => write!(f, "{} bytes needed, more than the memory limit", n),
+            Error::OutputTooLong => write!(f, "requested output too long"),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+/// Cost parameters N, r and p, and the memory limit checked before deriving.
+#[derive(Copy,Clone,Debug,PartialEq,Eq)]
+pub struct Params {
+    log_n: u8,
+    r: u32,
+    p: u32,
+    max_memory: usize,
+}
+
+impl Params {
+    pub fn new(n: u64, r: u32, p: u32) -> Result<Params,Error> {
+        if r == 0 || p == 0 || (r as u64) * (p as u64) >= 1 << 30 {
+            return Err(Error::InvalidBlockSize);
+        }
+        if n < 2 || !n.is_power_of_two() || (16 * r as u64) < 64 && n >= 1 << (16 * r) {
+            return Err(Error::InvalidCost);
+        }
+        Ok(Params {log_n: n.trailing_zeros() as u8, r, p, max_memory: DEFAULT_MAX_MEMORY})
+    }
+
+    /// N = 2^17, r = 8, p = 1: 128 MiB, the interactive-login choice of OWASP
+    pub fn recommended() -> Params {
+        Params::new(1 << 17, 8, 1).unwrap()
+    }
+
+    pub fn with_max_memory(mut self, bytes: usize) -> Self {
+        self.max_memory = bytes;
+        self
+    }
+
+    pub fn n(&self) -> u64 {
+        1 << self.log_n
+    }
+
+    pub fn r(&self) -> u32 {
+        self.r
+    }
+
+    pub fn p(&self) -> u32 {
+        self.p
+    }
+
+    /// Bytes needed to derive: ROMix's table and the p blocks, or `None` on overflow
+    pub fn memory(&self) -> Option<usize> {
+        let block = 128usize.checked_mul(self.r as usize)?;
+        let table = block.checked_mul(usize::try_from(self.n()).ok()?)?;
+        table.checked_add(block.checked_mul(self.p as usize)?)
+    }
+}
+
+// Salsa20/8 core (RFC 7914 section 3)
+fn salsa20_8(b: &mut [u32;16]) {
+    let mut x = *b;
+    for _ in 0..4 {
+        for &(a, c, d, e) in [(0, 4, 8, 12), (5, 9, 13, 1), (10, 14, 2, 6), (15, 3, 7, 11)].iter() {
+            x[c] ^= x[a].wrapping_add(x[e]).rotate_left(7);
+            x[d] ^= x[c].wrapping_add(x[a]).rotate_left(9);
+            x[e] ^= x[d].wrapping_add(x[c]).rotate_left(13);
+            x[a] ^= x[e].wrapping_add(x[d]).rotate_left(18);
+        }
+        for &(a, c, d, e) in [(0, 1, 2, 3), (5, 6, 7, 4), (10, 11, 8, 9), (15, 12, 13, 14)].iter() {
+            x[c] ^= x[a].wrapping_add(x[e]).rotate_left(7);
+            x[d] ^= x[c].wrapping_add(x[a]).rotate_left(9);
+            x[e] ^= x[d].wrapping_add(x[c]).rotate_left(13);
+            x[a] ^= x[e].wrapping_add(x[d]).rotate_left(18);
+        }
+    }
+    for (b, x) in b.iter_mut().zip(x.iter()) {
+        *b = b.wrapping_add(*x);
+    }
+}
+
+// BlockMix (section 4) over 2r 16-word blocks; even outputs go first, then odd ones
+fn block_mix(input: &[u32], out: &mut [u32]) {
+    let r = input.len() / 32;
+    let mut x: [u32;16] = input[input.len() - 16..].try_into().unwrap();
+    for (i, block) in input.chunks(16).enumerate() {
+        x.iter_mut().zip(block.iter()).for_each(|(x, b)| *x ^= b);
+        salsa20_8(&mut x);
+        let j = (i / 2 + (i % 2) * r) * 16;
+        out[j..j + 16].copy_from_slice(&x);
+    }
+}
+
+// ROMix (section 5) on one 128r-byte block
+fn ro_mix(block: &mut [u8], n: usize) {
+    let words = block.len() / 4;
+    let mut x: Vec<u32> = block.chunks(4).map(|w| u32::from_le_bytes(w.try_into().unwrap())).collect();
+    let mut y = vec![0u32;words];
+    let mut v = vec![0u32;words * n];
+    for i in 0..n {
+        v[i * words..(i + 1) * words].copy_from_slice(&x);
+        block_mix(&x, &mut y);
+        std::mem::swap(&mut x, &mut y);
+    }
+    for _ in 0..n {
+        // Integerify: the first 64 bits of the last 16-word block, modulo N
+        let j = (x[words - 16] as u64 | (x[words - 15] as u64) << 32) as usize & (n - 1);
+        x.iter_mut().zip(v[j * words..(j + 1) * words].iter()).for_each(|(x, v)| *x ^= v);
+        block_mix(&x, &mut y);
+        std::mem::swap(&mut x, &mut y);
+    }
+    for (b, x) in block.chunks_mut(4).zip(x.iter()) {
+        b.copy_from_slice(&x.to_le_bytes());
+    }
+}
+
+/// Derives `len` bytes from `password` and `salt`.
+pub fn scrypt(password: &[u8], salt: &[u8], params: &Params, len: usize) -> Result<Vec<u8>,Error> {
+    if len as u64 > (u32::MAX as u64) * 32 {
+        return Err(Error::OutputTooLong);
+    }
+    let memory = params.memory().ok_or(Error::MemoryLimitExceeded(usize::MAX))?;
+    if memory > params.max_memory {
+        return Err(Error::MemoryLimitExceeded(memory));
+    }
+    let block_len = 128 * params.r as usize;
+    let mut b = pbkdf2_hmac_sha256(password, salt, 1, block_len * params.p as usize);
+    for block in b.chunks_mut(block_len) {
+        ro_mix(block, params.n() as usize);
+    }
+    Ok(pbkdf2_hmac_sha256(password, &b, 1, len))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utility::codec::hex;
+
+    #[test]
+    fn test_salsa20_8() {
+        // RFC 7914 section 8
+        let input = hex::decode("7e879a214f3ec9867ca940e641718f26baee555b8c61c1b50df846116dcd3b1d\
+            ee24f319df9b3d8514121e4b5ac5aa3276021d2909c74829edebc68db8b8c25e").unwrap();
+        let mut b = [0u32;16];
+        b.iter_mut().zip(input.chunks(4)).for_each(|(b, w)| *b = u32::from_le_bytes(w.try_into().unwrap()));
+        salsa20_8(&mut b);
+        let out: Vec<u8> = b.iter().flat_map(|w| w.to_le_bytes().to_vec()).collect();
+        assert_eq!(out,hex::decode("a41f859c6608cc993b81cacb020cef05044b2181a2fd337dfd7b1c6396682f29\
+            b4393168e3c9e6bcfe6bc5b7a06d96bae424cc102c91745c24ad673dc7618f81").unwrap());
+    }
+
+    #[test]
+    fn test_scrypt() {
+        // RFC 7914 section 12, except the 1 GiB case
+        let cases = [
+            ("", "", 16, 1, 1, "77d6576238657b203b19ca42c18a0497f16b4844e3074ae8dfdffa3fede21442\
+                fcd0069ded0948f8326a753a0fc81f17e8d3e0fb2e0d3628cf35e20c38d18906"),
+            ("password", "NaCl", 1024, 8, 16, "fdbabe1c9d3472007856e7190d01e9fe7c6ad7cbc8237830e77376634b373162\
+                2eaf30d92e22a3886ff109279d9830dac727afb94a83ee6d8360cbdfa2cc0640"),
+            ("pleaseletmein", "SodiumChloride", 16384, 8, 1, "7023bdcb3afd7348461c06cd81fd38ebfda8fbba904f8e3ea9b543f6545da1f2\
+                d5432955613f0fcf62d49705242a9af9e61e85dc0d651e40dfcf017b45575887"),
+        ];
+        for (password, salt, n, r, p, dk) in cases.iter() {
+            let params = Params::new(*n, *r, *p).unwrap();
+            assert_eq!(scrypt(password.as_bytes(), salt.as_bytes(), &params, 64).unwrap(),hex::decode(dk).unwrap());
+        }
+    }
+
+    #[test]
+    fn test_params() {
+        assert_eq!(Params::new(1000, 8, 1),Err(Error::InvalidCost));
+        assert_eq!(Params::new(1, 8, 1),Err(Error::InvalidCost));
+        assert_eq!(Params::new(1 << 16, 1, 1),Err(Error::InvalidCost));
+        assert!(Params::new(1 << 15, 1, 1).is_ok());
+        assert_eq!(Params::new(16, 0, 1),Err(Error::InvalidBlockSize));
+        assert_eq!(Params::new(16, 1 << 15, 1 << 15),Err(Error::InvalidBlockSize));
+
+        let params = Params::recommended();
+        assert_eq!((params.n(), params.r(), params.p()),(1 << 17, 8, 1));
+        assert_eq!(params.memory(),Some(128 * 8 * ((1 << 17) + 1)));
+
+        // the last RFC case needs just over 1 GiB
+        let params = Params::new(1 << 20, 8, 1).unwrap();
+        assert_eq!(scrypt(b"pleaseletmein", b"SodiumChloride", &params, 64),
+            Err(Error::MemoryLimitExceeded((1 << 30) + 1024)));
+        let params = Params::new(16, 1, 1).unwrap().with_max_memory(128 * 16);
+        assert_eq!(scrypt(b"", b"", &params, 64),Err(Error::MemoryLimitExceeded(128 * 17)));
+    }
+}
@@ -1,6 +1,17 @@
 pub mod rng;
 pub mod aes;
 pub mod sha256;
+pub mod sha512;
+pub mod blake2b;
+pub mod hmac;
 pub mod prime;
+pub mod ntheory;
+pub mod factor;
 pub mod rsa;
-pub mod utility;
\ No newline at end of file
+pub mod drbg;
+pub mod utility;
+pub mod x509;
+pub mod pkcs8;
+pub mod ssh;
+pub mod jwk;
+pub mod kdf;
\ No newline at end of file
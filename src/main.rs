@@ -1,7 +1,6 @@
-use crusto::aes::AES;
 use std::env;
 
 fn main() {
-    let args: Vec<String>=env::args().collect();
+    let _args: Vec<String>=env::args().collect();
 
 }
\ No newline at end of file
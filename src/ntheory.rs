@@ -0,0 +1,303 @@
+use num::{BigInt,BigUint,Integer,bigint::Sign};
+use num::traits::{identities::{One,Zero},Signed};
+
+/// Modular inverse of `a` modulo `m`, or `None` if `gcd(a, m) != 1`.
+pub fn mod_inverse(a: &BigUint, m: &BigUint) -> Option<BigUint> {
+    if m.is_zero() {
+        return None;
+    }
+
+    let a = BigInt::from_biguint(Sign::Plus, a % m);
+    let m = BigInt::from_biguint(Sign::Plus, m.clone());
+    let exgcd = a.extended_gcd(&m);
+    if !exgcd.gcd.is_one() {
+        return None;
+    }
+
+    exgcd.x.mod_floor(&m).to_biguint()
+}
+
+/// Chinese remainder theorem over `(residue, modulus)` pairs.
+///
+/// Moduli need not be pairwise coprime. Returns `(x, lcm)` with `0 <= x < lcm` satisfying
+/// every congruence, or `None` if the system is inconsistent.
+pub fn crt(congruences: &[(BigUint,BigUint)]) -> Option<(BigUint,BigUint)> {
+    let mut x = BigUint::zero();
+    let mut m = BigUint::one();
+
+    for (r2, m2) in congruences.iter() {
+        if m2.is_zero() {
+            return None;
+        }
+
+        let g = m.gcd(m2);
+        let r2 = r2 % m2;
+        let r1 = &x % &g;
+        if r1 != &r2 % &g {
+            return None;
+        }
+
+        // x + m*t = r2 (mod m2)  =>  t = (r2-x)/g * (m/g)^-1 (mod m2/g)
+        let m2g = m2 / &g;
+        let diff = (BigInt::from_biguint(Sign::Plus, r2) - BigInt::from_biguint(Sign::Plus, x.clone())) / BigInt::from_biguint(Sign::Plus, g.clone());
+        let diff = diff.mod_floor(&BigInt::from_biguint(Sign::Plus, m2g.clone())).to_biguint().unwrap();
+        let t = (diff * mod_inverse(&(&m / &g), &m2g)?) % &m2g;
+
+        x += &m * t;
+        m = &m / &g * m2;
+        x %= &m;
+    }
+
+    Some((x,m))
+}
+
+/// Jacobi symbol `(a/n)` for odd positive `n`.
+pub fn jacobi(a: &BigInt, n: &BigUint) -> i8 {
+    assert!(n.bit(0), "n must be odd and positive");
+
+    let mut a = a.mod_floor(&BigInt::from_biguint(Sign::Plus, n.clone())).to_biguint().unwrap();
+    let mut n = n.clone();
+    let mut t = 1i8;
+
+    while !a.is_zero() {
+        while !a.bit(0) {
+            a >>= 1u8;
+            // n = 3, 5 (mod 8)
+            if n.bit(1) != n.bit(2) {
+                t = -t;
+            }
+        }
+        std::mem::swap(&mut a, &mut n);
+        if a.bit(1) && n.bit(1) {
+            t = -t;
+        }
+        a %= &n;
+    }
+
+    if n.is_one() {
+        t
+    } else {
+        0
+    }
+}
+
+/// Legendre symbol `(a/p)` for an odd prime `p`.
+pub fn legendre(a: &BigInt, p: &BigUint) -> i8 {
+    let a = a.mod_floor(&BigInt::from_biguint(Sign::Plus, p.clone())).to_biguint().unwrap();
+    if a.is_zero() {
+        return 0;
+    }
+
+    let r = a.modpow(&((p - 1u8) >> 1u8), p);
+    if r.is_one() {
+        1
+    } else {
+        -1
+    }
+}
+
+/// Square root of `a` modulo a prime `p` by the Tonelli-Shanks algorithm.
+///
+/// Returns the smaller of the two roots, or `None` if `a` is a non-residue.
+pub fn tonelli_shanks(a: &BigUint, p: &BigUint) -> Option<BigUint> {
+    let a = a % p;
+    if let Some(r) = trivial_sqrt(&a, p) {
+        return r;
+    }
+
+    let mut q = p - 1u8;
+    let mut s = 0u32;
+    while !q.bit(0) {
+        q >>= 1u8;
+        s += 1;
+    }
+
+    let mut z = BigUint::from(2u8);
+    while legendre(&BigInt::from_biguint(Sign::Plus, z.clone()), p) != -1 {
+        z += 1u8;
+    }
+
+    let mut m = s;
+    let mut c = z.modpow(&q, p);
+    let mut t = a.modpow(&q, p);
+    let mut r = a.modpow(&((&q + 1u8) >> 1u8), p);
+
+    while !t.is_one() {
+        let mut i = 0;
+        let mut t2 = t.clone();
+        while !t2.is_one() {
+            t2 = &t2 * &t2 % p;
+            i += 1;
+        }
+
+        let mut b = c.clone();
+        for _ in 0..m-i-1 {
+            b = &b * &b % p;
+        }
+        m = i;
+        c = &b * &b % p;
+        t = t * &c % p;
+        r = r * b % p;
+    }
+
+    Some(smaller_root(r, p))
+}
+
+/// Square root of `a` modulo a prime `p` by Cipolla's algorithm.
+///
+/// Returns the smaller of the two roots, or `None` if `a` is a non-residue.
+pub fn cipolla(a: &BigUint, p: &BigUint) -> Option<BigUint> {
+    let a = a % p;
+    if let Some(r) = trivial_sqrt(&a, p) {
+        return r;
+    }
+
+    // find t such that t^2 - a is a non-residue
+    let mut t = BigUint::one();
+    let w = loop {
+        let w = (&t * &t + p - &a) % p;
+        if legendre(&BigInt::from_biguint(Sign::Plus, w.clone()), p) == -1 {
+            break w;
+        }
+        t += 1u8;
+    };
+
+    // (t + sqrt(w))^((p+1)/2) in F_p[sqrt(w)]
+    let mul = |(x1, y1): &(BigUint,BigUint), (x2, y2): &(BigUint,BigUint)| {
+        ((x1 * x2 + y1 * y2 % p * &w) % p, (x1 * y2 + y1 * x2) % p)
+    };
+
+    let e = (p + 1u8) >> 1u8;
+    let mut r = (BigUint::one(), BigUint::zero());
+    let mut b = (t, BigUint::one());
+    for i in 0..e.bits() {
+        if e.bit(i) {
+            r = mul(&r, &b);
+        }
+        b = mul(&b, &b);
+    }
+
+    Some(smaller_root(r.0, p))
+}
+
+/// Modular exponentiation allowing negative exponents, which use the inverse of `base`.
+pub fn mod_pow(base: &BigUint, exp: &BigInt, m: &BigUint) -> Option<BigUint> {
+    if m.is_zero() {
+        return None;
+    }
+
+    let e = exp.abs().to_biguint().unwrap();
+    if exp.is_negative() {
+        Some(mod_inverse(base, m)?.modpow(&e, m))
+    } else {
+        Some(base.modpow(&e, m))
+    }
+}
+
+// Roots for a = 0, p = 2 and non-residues, shared by both square root algorithms
+fn trivial_sqrt(a: &BigUint, p: &BigUint) -> Option<Option<BigUint>> {
+    if a.is_zero() || *p == BigUint::from(2u8) {
+        Some(Some(a.clone()))
+    } else if legendre(&BigInt::from_biguint(Sign::Plus, a.clone()), p) != 1 {
+        Some(None)
+    } else {
+        None
+    }
+}
+
+fn smaller_root(r: BigUint, p: &BigUint) -> BigUint {
+    let s = p - &r;
+    if s < r {
+        s
+    } else {
+        r
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn big(n: u64) -> BigUint {
+        BigUint::from(n)
+    }
+
+    #[test]
+    fn test_mod_inverse() {
+        assert_eq!(mod_inverse(&big(3), &big(11)),Some(big(4)));
+        assert_eq!(mod_inverse(&big(17), &big(3120)),Some(big(2753)));
+        assert_eq!(mod_inverse(&big(6), &big(9)),None);
+        assert_eq!(mod_inverse(&big(5), &big(0)),None);
+    }
+
+    #[test]
+    fn test_crt() {
+        let c = [(big(2),big(3)),(big(3),big(5)),(big(2),big(7))];
+        assert_eq!(crt(&c),Some((big(23),big(105))));
+
+        let c = [(big(3),big(4)),(big(5),big(6))];
+        assert_eq!(crt(&c),Some((big(11),big(12))));
+
+        let c = [(big(1),big(4)),(big(2),big(6))];
+        assert_eq!(crt(&c),None);
+
+        assert_eq!(crt(&[]),Some((big(0),big(1))));
+    }
+
+    #[test]
+    fn test_jacobi() {
+        assert_eq!(jacobi(&BigInt::from(1001), &big(9907)),-1);
+        assert_eq!(jacobi(&BigInt::from(19), &big(45)),1);
+        assert_eq!(jacobi(&BigInt::from(8), &big(21)),-1);
+        assert_eq!(jacobi(&BigInt::from(5), &big(21)),1);
+        assert_eq!(jacobi(&BigInt::from(6), &big(21)),0);
+        assert_eq!(jacobi(&BigInt::from(-1), &big(7)),-1);
+        assert_eq!(jacobi(&BigInt::from(-1), &big(13)),1);
+    }
+
+    #[test]
+    fn test_legendre() {
+        let p = big(23);
+        for a in 0..23i64 {
+            let is_square = (0..23i64).any(|x| x*x%23 == a);
+            let l = legendre(&BigInt::from(a), &p);
+            assert_eq!(l, jacobi(&BigInt::from(a), &p));
+            if a == 0 {
+                assert_eq!(l,0);
+            } else {
+                assert_eq!(l==1,is_square);
+            }
+        }
+    }
+
+    #[test]
+    fn test_sqrt_mod() {
+        let primes = [big(2),big(13),big(17),big(113),big(10009),big(1000000007)];
+        for p in primes.iter() {
+            for a in [0u64,1,2,3,4,5,10,11,64].iter() {
+                let a = big(*a);
+                let r1 = tonelli_shanks(&a, p);
+                let r2 = cipolla(&a, p);
+                assert_eq!(r1,r2);
+                if let Some(r) = r1 {
+                    assert_eq!(&r*&r%p,&a%p);
+                }
+            }
+        }
+
+        // p = 119*2^23+1 exercises the Tonelli-Shanks loop
+        let p = big(998244353);
+        let a = big(1234567);
+        let r = tonelli_shanks(&(&a*&a), &p).unwrap();
+        assert_eq!(r,a);
+        assert_eq!(cipolla(&(&a*&a), &p).unwrap(),a);
+    }
+
+    #[test]
+    fn test_mod_pow() {
+        assert_eq!(mod_pow(&big(3), &BigInt::from(4), &big(7)),Some(big(4)));
+        assert_eq!(mod_pow(&big(3), &BigInt::from(-1), &big(7)),Some(big(5)));
+        assert_eq!(mod_pow(&big(3), &BigInt::from(-2), &big(7)),Some(big(4)));
+        assert_eq!(mod_pow(&big(2), &BigInt::from(-1), &big(8)),None);
+    }
+}
@@ -0,0 +1,126 @@
+// OpenSSL's traditional encrypted PEM (`Proc-Type: 4,ENCRYPTED` and `DEK-Info` headers)
+//
+// The key is derived with EVP_BytesToKey: MD5, one iteration, and the first 8 bytes of
+// the IV as salt. Import only; new keys are written as PKCS#8.
+use super::{cbc_decrypt, Error};
+use crate::rsa;
+use crate::utility::codec::hex;
+use crate::utility::pem;
+use std::convert::TryInto;
+
+const K: [u32;64] = [
+    0xd76aa478, 0xe8c7b756, 0x242070db, 0xc1bdceee, 0xf57c0faf, 0x4787c62a, 0xa8304613, 0xfd469501,
+    0x698098d8, 0x8b44f7af, 0xffff5bb1, 0x895cd7be, 0x6b901122, 0xfd987193, 0xa679438e, 0x49b40821,
+    0xf61e2562, 0xc040b340, 0x265e5a51, 0xe9b6c7aa, 0xd62f105d, 0x02441453, 0xd8a1e681, 0xe7d3fbc8,
+    0x21e1cde6, 0xc33707d6, 0xf4d50d87, 0x455a14ed, 0xa9e3e905, 0xfcefa3f8, 0x676f02d9, 0x8d2a4c8a,
+    0xfffa3942, 0x8771f681, 0x6d9d6122, 0xfde5380c, 0xa4beea44, 0x4bdecfa9, 0xf6bb4b60, 0xbebfbc70,
+    0x289b7ec6, 0xeaa127fa, 0xd4ef3085, 0x04881d05, 0xd9d4d039, 0xe6db99e5, 0x1fa27cf8, 0xc4ac5665,
+    0xf4292244, 0x432aff97, 0xab9423a7, 0xfc93a039, 0x655b59c3, 0x8f0ccc92, 0xffeff47d, 0x85845dd1,
+    0x6fa87e4f, 0xfe2ce6e0, 0xa3014314, 0x4e0811a1, 0xf7537e82, 0xbd3af235, 0x2ad7d2bb, 0xeb86d391];
+
+const S: [u32;16] = [7, 12, 17, 22, 5, 9, 14, 20, 4, 11, 16, 23, 6, 10, 15, 21];
+
+// MD5 (RFC 1321), only needed for this key derivation
+fn md5(input: &[u8]) -> [u8;16] {
+    let mut msg = input.to_vec();
+    msg.push(0x80);
+    while msg.len() % 64 != 56 {
+        msg.push(0);
+    }
+    msg.extend_from_slice(&((input.len() as u64).wrapping_mul(8)).to_le_bytes());
+
+    let mut hash: [u32;4] = [0x67452301, 0xefcdab89, 0x98badcfe, 0x10325476];
+    for block in msg.chunks(64) {
+        let m: Vec<u32> = block.chunks(4).map(|w| u32::from_le_bytes(w.try_into().unwrap())).collect();
+        let [mut a, mut b, mut c, mut d] = hash;
+        for i in 0..64 {
+            let (f, g) = match i / 16 {
+                0 => ((b & c) | (!b & d), i),
+                1 => ((d & b) | (!d & c), (5 * i + 1) % 16),
+                2 => (b ^ c ^ d, (3 * i + 5) % 16),
+                _ => (c ^ (b | !d), (7 * i) % 16),
+            };
+            let f = f.wrapping_add(a).wrapping_add(K[i]).wrapping_add(m[g]);
+            a = d;
+            d = c;
+            c = b;
+            b = b.wrapping_add(f.rotate_left(S[i / 16 * 4 + i % 4]));
+        }
+        for (h, v) in hash.iter_mut().zip([a, b, c, d].iter()) {
+            *h = h.wrapping_add(*v);
+        }
+    }
+
+    let mut out = [0u8;16];
+    for (o, h) in out.chunks_mut(4).zip(hash.iter()) {
+        o.copy_from_slice(&h.to_le_bytes());
+    }
+    out
+}
+
+// EVP_BytesToKey with MD5 and a single iteration
+fn bytes_to_key(password: &[u8], salt: &[u8], len: usize) -> Vec<u8> {
+    let mut key = Vec::with_capacity(len + 16);
+    let mut prev: Vec<u8> = Vec::new();
+    while key.len() < len {
+        prev = md5(&[&prev, password, salt].concat()).to_vec();
+        key.extend_from_slice(&prev);
+    }
+    key.truncate(len);
+    key
+}
+
+pub(super) fn decrypt(block: &pem::Block, password: &[u8]) -> Result<rsa::PrivateKey,Error> {
+    const INVALID: Error = Error::Pem(pem::Error::InvalidContent("invalid DEK-Info header"));
+    if block.header("Proc-Type") != Some("4,ENCRYPTED") {
+        return Err(Error::NotEncrypted);
+    }
+    let info = block.header("DEK-Info").ok_or(INVALID)?;
+    let (cipher, iv_hex) = info.split_at(info.find(',').ok_or(INVALID)?);
+    let keylen = match cipher {
+        "AES-128-CBC" => 16,
+        "AES-192-CBC" => 24,
+        "AES-256-CBC" => 32,
+        _ => return Err(Error::UnsupportedCipher(cipher.to_string())),
+    };
+    let iv: [u8;16] = hex::decode(iv_hex[1..].trim()).ok().and_then(|iv| iv.try_into().ok()).ok_or(INVALID)?;
+
+    let key = bytes_to_key(password, &iv[..8], keylen);
+    let plain = cbc_decrypt(&key, &iv, &block.data)?;
+    rsa::PrivateKey::from_der(&plain).map_err(|_| Error::DecryptionFailed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pkcs8::decrypt_pem;
+    use crate::pkcs8::tests::PRIVATE_PEM;
+    use crate::utility::pem::Pem;
+
+    // `openssl rsa -traditional -aes256` and `-aes128`, password "legacy-pass"
+    const AES256_PEM: &str = include_str!("testdata/key.aes256.pem");
+    const AES128_PEM: &str = include_str!("testdata/key.aes128.pem");
+
+    #[test]
+    fn test_md5() {
+        // RFC 1321 appendix A.5
+        assert_eq!(md5(b"").to_vec(),hex::decode("d41d8cd98f00b204e9800998ecf8427e").unwrap());
+        assert_eq!(md5(b"abc").to_vec(),hex::decode("900150983cd24fb0d6963f7d28e17f72").unwrap());
+        assert_eq!(md5(b"12345678901234567890123456789012345678901234567890123456789012345678901234567890").to_vec(),
+            hex::decode("57edf4a22be3c955ac49da2e2107b67a").unwrap());
+    }
+
+    #[test]
+    fn test_decrypt() {
+        let key = rsa::PrivateKey::from_pem(PRIVATE_PEM).unwrap();
+        for text in [AES256_PEM, AES128_PEM].iter() {
+            assert_eq!(decrypt_pem(text, b"legacy-pass").unwrap().to_der(),key.to_der());
+            assert_eq!(decrypt_pem(text, b"legacy-pas").err(),Some(Error::DecryptionFailed));
+        }
+
+        let des = AES256_PEM.replace("AES-256-CBC,7DDEB369D24391C27B8E26632A893AF4", "DES-EDE3-CBC,C6E5AF302D3A76E8");
+        assert_eq!(decrypt_pem(&des, b"legacy-pass").err(),Some(Error::UnsupportedCipher("DES-EDE3-CBC".to_string())));
+        let short_iv = AES256_PEM.replace("AF4\n", "\n");
+        assert!(matches!(decrypt_pem(&short_iv, b"legacy-pass").err(), Some(Error::Pem(_))));
+    }
+}
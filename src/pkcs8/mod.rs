@@ -0,0 +1,326 @@
+// Password-protected RSA private keys: PKCS#8 EncryptedPrivateKeyInfo (RFC 5958) with
+// PBES2 (RFC 8018), and import of OpenSSL's legacy encrypted PEM.
+//
+// Only encrypted forms are produced. `decrypt_pem` refuses keys stored in the clear.
+mod legacy;
+
+use crate::aes::{CipherMode, KeyLength, AES};
+use crate::kdf::pbkdf2::{self, pbkdf2_hmac_sha256};
+use crate::rng::Rng;
+use crate::rsa;
+use crate::utility::asn1::{self, Oid, Tag};
+use crate::utility::pem::{self, Pem};
+use crate::x509::oid::RSA_ENCRYPTION;
+use std::fmt;
+
+const PBES2: &[u64] = &[1, 2, 840, 113549, 1, 5, 13];
+const PBKDF2: &[u64] = &[1, 2, 840, 113549, 1, 5, 12];
+const HMAC_SHA256: &[u64] = &[1, 2, 840, 113549, 2, 9];
+const AES256_CBC: &[u64] = &[2, 16, 840, 1, 101, 3, 4, 1, 42];
+
+/// Iteration count for new keys when there is no reason to pick another
+pub const DEFAULT_ITERATIONS: u32 = pbkdf2::DEFAULT_ITERATIONS;
+/// Largest iteration count accepted from an encoded key, bounding the work it can demand
+pub const MAX_ITERATIONS: u32 = 10_000_000;
+const SALT_LEN: usize = 16;
+
+#[derive(Debug,Clone,PartialEq,Eq)]
+pub enum Error {
+    Pem(pem::Error),
+    Asn1(asn1::Error),
+    /// The key is stored in the clear
+    NotEncrypted,
+    /// A legacy `DEK-Info` cipher other than AES-CBC
+    UnsupportedCipher(String),
+    /// The password is wrong or the data is corrupt
+    DecryptionFailed,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Pem(e) => write!(f, "{}", e),
+            Error::Asn1(e) => write!(f, "{}", e),
+            Error::NotEncrypted => write!(f, "private key is not encrypted"),
+            Error::UnsupportedCipher(name) => write!(f, "unsupported cipher {}", name),
+            Error::DecryptionFailed => write!(f, "decryption failed"),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<pem::Error> for Error {
+    fn from(e: pem::Error) -> Self {
+        Error::Pem(e)
+    }
+}
+
+impl From<asn1::Error> for Error {
+    fn from(e: asn1::Error) -> Self {
+        Error::Asn1(e)
+    }
+}
+
+fn pad(data: &[u8]) -> Vec<u8> {
+    let n = 16 - data.len() % 16;
+    let mut padded = data.to_vec();
+    padded.resize(data.len() + n, n as u8);
+    padded
+}
+
+fn unpad(data: &[u8]) -> Result<&[u8],Error> {
+    let n = *data.last().ok_or(Error::DecryptionFailed)? as usize;
+    if n == 0 || n > 16 || n > data.len() || data[data.len() - n..].iter().any(|&b| b as usize != n) {
+        return Err(Error::DecryptionFailed);
+    }
+    Ok(&data[..data.len() - n])
+}
+
+// AES-CBC with PKCS#7 padding; `key` selects AES-128, -192 or -256
+fn cbc_decrypt(key: &[u8], iv: &[u8;16], data: &[u8]) -> Result<Vec<u8>,Error> {
+    let keylen = match key.len() {
+        16 => KeyLength::KL128,
+        24 => KeyLength::KL192,
+        _ => KeyLength::KL256,
+    };
+    let plain = AES::new(keylen, CipherMode::CBC).decrypt(key, iv, data).map_err(|_| Error::DecryptionFailed)?;
+    Ok(unpad(&plain)?.to_vec())
+}
+
+// PKCS#8 PrivateKeyInfo holding an RSA key
+fn encode_private_key_info(key: &rsa::PrivateKey) -> Vec<u8> {
+    asn1::encode(|w| w.write_sequence(|w| {
+        w.write_u64(0);
+        w.write_sequence(|w| {
+            w.write_oid(&Oid::new(RSA_ENCRYPTION));
+            w.write_null();
+        });
+        w.write_octet_string(&key.to_der());
+    }))
+}
+
+fn decode_private_key_info(der: &[u8]) -> Result<rsa::PrivateKey,asn1::Error> {
+    asn1::decode(der, |r| {
+        let mut info = r.read_sequence()?;
+        // v2 (OneAsymmetricKey) may add attributes and the public key after the key
+        if info.read_u64()? > 1 {
+            return Err(asn1::Error::InvalidValue("unknown PrivateKeyInfo version"));
+        }
+        let mut alg = info.read_sequence()?;
+        if alg.read_oid()?.arcs() != RSA_ENCRYPTION {
+            return Err(asn1::Error::InvalidValue("not an RSA key"));
+        }
+        alg.read_null()?;
+        alg.finish()?;
+        let key = rsa::PrivateKey::from_der(info.read_octet_string()?)?;
+        info.read_optional_context(0)?;
+        info.read_optional(Tag::context_primitive(1))?;
+        info.finish()?;
+        Ok(key)
+    })
+}
+
+/// A PKCS#8 `EncryptedPrivateKeyInfo` protected with PBES2, using PBKDF2-HMAC-SHA256
+/// and AES-256-CBC. This is what `openssl pkcs8 -topk8 -v2 aes-256-cbc -v2prf hmacWithSHA256`
+/// writes.
+#[derive(Debug,Clone,PartialEq,Eq)]
+pub struct EncryptedPrivateKeyInfo {
+    der: Vec<u8>,
+    salt: Vec<u8>,
+    iterations: u32,
+    iv: [u8;16],
+    data: Vec<u8>,
+}
+
+impl EncryptedPrivateKeyInfo {
+    /// Encrypts `key` under `password` with a random salt and IV drawn from `rng`.
+    pub fn encrypt(key: &rsa::PrivateKey, password: &[u8], iterations: u32, rng: &mut Rng) -> Self {
+        let salt = rng.generate_bytes(SALT_LEN);
+        let mut iv = [0u8;16];
+        rng.fill(&mut iv);
+        EncryptedPrivateKeyInfo::encrypt_with(key, password, iterations, salt, iv)
+    }
+
+    fn encrypt_with(key: &rsa::PrivateKey, password: &[u8], iterations: u32, salt: Vec<u8>, iv: [u8;16]) -> Self {
+        assert!(iterations > 0 && iterations <= MAX_ITERATIONS, "iteration count out of range");
+        let dk = pbkdf2_hmac_sha256(password, &salt, iterations, 32);
+        let plain = pad(&encode_private_key_info(key));
+        let data = AES::new(KeyLength::KL256, CipherMode::CBC).encrypt(&dk, &iv, &plain).unwrap();
+
+        let der = asn1::encode(|w| w.write_sequence(|w| {
+            w.write_sequence(|w| {
+                w.write_oid(&Oid::new(PBES2));
+                w.write_sequence(|w| {
+                    w.write_sequence(|w| {
+                        w.write_oid(&Oid::new(PBKDF2));
+                        w.write_sequence(|w| {
+                            w.write_octet_string(&salt);
+                            w.write_u64(iterations as u64);
+                            w.write_sequence(|w| {
+                                w.write_oid(&Oid::new(HMAC_SHA256));
+                                w.write_null();
+                            });
+                        });
+                    });
+                    w.write_sequence(|w| {
+                        w.write_oid(&Oid::new(AES256_CBC));
+                        w.write_octet_string(&iv);
+                    });
+                });
+            });
+            w.write_octet_string(&data);
+        }));
+        EncryptedPrivateKeyInfo {der, salt, iterations, iv, data}
+    }
+
+    /// Parses the encoding; schemes other than PBES2 with PBKDF2-HMAC-SHA256 and
+    /// AES-256-CBC are rejected as invalid values.
+    pub fn from_der(der: &[u8]) -> Result<EncryptedPrivateKeyInfo,asn1::Error> {
+        const UNSUPPORTED: asn1::Error = asn1::Error::InvalidValue("unsupported key encryption scheme");
+        asn1::decode(der, |r| {
+            let mut info = r.read_sequence()?;
+            let mut alg = info.read_sequence()?;
+            if alg.read_oid()?.arcs() != PBES2 {
+                return Err(UNSUPPORTED);
+            }
+            let mut params = alg.read_sequence()?;
+            alg.finish()?;
+
+            let mut kdf = params.read_sequence()?;
+            if kdf.read_oid()?.arcs() != PBKDF2 {
+                return Err(UNSUPPORTED);
+            }
+            let mut kdf_params = kdf.read_sequence()?;
+            kdf.finish()?;
+            let salt = kdf_params.read_octet_string()?.to_vec();
+            let iterations = kdf_params.read_u64()?;
+            if iterations == 0 || iterations > MAX_ITERATIONS as u64 {
+                return Err(asn1::Error::InvalidValue("iteration count out of range"));
+            }
+            if kdf_params.peek_tag() == Some(Tag::INTEGER) && kdf_params.read_u64()? != 32 {
+                return Err(UNSUPPORTED);
+            }
+            // the PRF defaults to HMAC-SHA1
+            let mut prf = kdf_params.read_sequence().map_err(|_| UNSUPPORTED)?;
+            if prf.read_oid()?.arcs() != HMAC_SHA256 {
+                return Err(UNSUPPORTED);
+            }
+            if !prf.is_empty() {
+                prf.read_null()?;
+            }
+            prf.finish()?;
+            kdf_params.finish()?;
+
+            let mut cipher = params.read_sequence()?;
+            if cipher.read_oid()?.arcs() != AES256_CBC {
+                return Err(UNSUPPORTED);
+            }
+            let mut iv = [0u8;16];
+            let iv_bytes = cipher.read_octet_string()?;
+            if iv_bytes.len() != 16 {
+                return Err(asn1::Error::InvalidValue("invalid IV length"));
+            }
+            iv.copy_from_slice(iv_bytes);
+            cipher.finish()?;
+            params.finish()?;
+
+            let data = info.read_octet_string()?.to_vec();
+            info.finish()?;
+            Ok(EncryptedPrivateKeyInfo {der: der.to_vec(), salt, iterations: iterations as u32, iv, data})
+        })
+    }
+
+    pub fn to_der(&self) -> &[u8] {
+        &self.der
+    }
+
+    pub fn iterations(&self) -> u32 {
+        self.iterations
+    }
+
+    pub fn decrypt(&self, password: &[u8]) -> Result<rsa::PrivateKey,Error> {
+        let dk = pbkdf2_hmac_sha256(password, &self.salt, self.iterations, 32);
+        let plain = cbc_decrypt(&dk, &self.iv, &self.data)?;
+        decode_private_key_info(&plain).map_err(|_| Error::DecryptionFailed)
+    }
+}
+
+impl Pem for EncryptedPrivateKeyInfo {
+    fn to_pem(&self) -> String {
+        pem::encode(&pem::Block::new("ENCRYPTED PRIVATE KEY", self.der.clone()))
+    }
+
+    fn from_pem(text: &str) -> Result<Self,pem::Error> {
+        let block = pem::parse_label(text, "ENCRYPTED PRIVATE KEY")?;
+        Ok(EncryptedPrivateKeyInfo::from_der(&block.data)?)
+    }
+}
+
+/// Decrypts the first private key in `text`: either an `ENCRYPTED PRIVATE KEY` block or
+/// an OpenSSL `RSA PRIVATE KEY` block with `Proc-Type: 4,ENCRYPTED` and `DEK-Info`
+/// headers. Unencrypted keys are refused with `Error::NotEncrypted`.
+pub fn decrypt_pem(text: &str, password: &[u8]) -> Result<rsa::PrivateKey,Error> {
+    let blocks = pem::parse(text, pem::Mode::Lax)?;
+    let block = blocks.iter()
+        .find(|b| ["ENCRYPTED PRIVATE KEY", "RSA PRIVATE KEY", "PRIVATE KEY"].contains(&b.label.as_str()))
+        .ok_or(pem::Error::NoBlock)?;
+    match block.label.as_str() {
+        "ENCRYPTED PRIVATE KEY" => EncryptedPrivateKeyInfo::from_der(&block.data)?.decrypt(password),
+        "RSA PRIVATE KEY" if block.header("Proc-Type").is_some() => legacy::decrypt(block, password),
+        _ => Err(Error::NotEncrypted),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // the 512-bit key of the rsa tests, as written by OpenSSL 3.5
+    pub(super) const PRIVATE_PEM: &str = include_str!("testdata/key.pem");
+    const PKCS8_PEM: &str = include_str!("testdata/key.p8.pem");
+
+    #[test]
+    fn test_openssl() {
+        let key = rsa::PrivateKey::from_pem(PRIVATE_PEM).unwrap();
+        let info = EncryptedPrivateKeyInfo::from_pem(PKCS8_PEM).unwrap();
+        assert_eq!(info.iterations(),1000);
+        assert_eq!(info.decrypt(b"correct horse").unwrap().to_der(),key.to_der());
+        assert_eq!(decrypt_pem(PKCS8_PEM, b"correct horse").unwrap().to_der(),key.to_der());
+        assert_eq!(info.decrypt(b"wrong horse").err(),Some(Error::DecryptionFailed));
+
+        // same salt and IV give the same bytes
+        let again = EncryptedPrivateKeyInfo::encrypt_with(&key, b"correct horse", 1000, info.salt.clone(), info.iv);
+        assert_eq!(again.to_pem(),PKCS8_PEM);
+    }
+
+    #[test]
+    fn test_encrypt() {
+        let key = rsa::PrivateKey::from_pem(PRIVATE_PEM).unwrap();
+        let mut rng = Rng::from_seed([8;32]);
+        let a = EncryptedPrivateKeyInfo::encrypt(&key, b"pw", 10, &mut rng);
+        let b = EncryptedPrivateKeyInfo::encrypt(&key, b"pw", 10, &mut rng);
+        assert_ne!(a.to_der(),b.to_der());
+        let parsed = EncryptedPrivateKeyInfo::from_pem(&a.to_pem()).unwrap();
+        assert_eq!(parsed,a);
+        assert_eq!(parsed.decrypt(b"pw").unwrap().to_der(),key.to_der());
+        assert_eq!(decrypt_pem(&b.to_pem(), b"pw").unwrap().to_der(),key.to_der());
+        assert!(a.decrypt(b"").is_err());
+
+        // the first INTEGER is the iteration count
+        let mut der = EncryptedPrivateKeyInfo::encrypt_with(&key, b"pw", 1, vec![0;8], [0;16]).der;
+        let i = der.windows(3).position(|w| w == [0x02, 0x01, 0x01]).unwrap();
+        der[i + 2] = 0;
+        assert_eq!(EncryptedPrivateKeyInfo::from_der(&der).unwrap_err(),asn1::Error::InvalidValue("iteration count out of range"));
+    }
+
+    #[test]
+    fn test_not_encrypted() {
+        assert_eq!(decrypt_pem(PRIVATE_PEM, b"pw").err(),Some(Error::NotEncrypted));
+        let key = rsa::PrivateKey::from_pem(PRIVATE_PEM).unwrap();
+        let plain = pem::encode(&pem::Block::new("PRIVATE KEY", encode_private_key_info(&key)));
+        assert_eq!(decrypt_pem(&plain, b"pw").err(),Some(Error::NotEncrypted));
+        assert_eq!(decode_private_key_info(&pem::parse_label(&plain, "PRIVATE KEY").unwrap().data).unwrap().to_der(),key.to_der());
+        assert_eq!(decrypt_pem("", b"pw").err(),Some(Error::Pem(pem::Error::NoBlock)));
+    }
+}
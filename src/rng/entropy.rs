@@ -0,0 +1,247 @@
+// Entropy sources with NIST SP 800-90B continuous health tests
+use rand::RngCore;
+use rand::rngs::OsRng;
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
+/// Raw noise source producing one 8-bit sample per byte.
+pub trait NoiseSource {
+    fn read(&mut self, buf: &mut [u8]) -> Result<(),&'static str>;
+}
+
+/// Operating system randomness.
+pub struct OsNoise;
+
+impl NoiseSource for OsNoise {
+    fn read(&mut self, buf: &mut [u8]) -> Result<(),&'static str> {
+        OsRng.try_fill_bytes(buf).map_err(|_| "OS entropy source failed")
+    }
+}
+
+/// Samples captured to a file, consumed in order.
+pub struct FileNoise {
+    file: File,
+}
+
+impl FileNoise {
+    pub fn open<P: AsRef<Path>>(path: P) -> std::io::Result<FileNoise> {
+        Ok(FileNoise {file: File::open(path)?})
+    }
+}
+
+impl NoiseSource for FileNoise {
+    fn read(&mut self, buf: &mut [u8]) -> Result<(),&'static str> {
+        self.file.read_exact(buf).map_err(|_| "Sample file exhausted")
+    }
+}
+
+/// Repetition Count Test (SP 800-90B 4.4.1)
+struct RepetitionCount {
+    cutoff: usize,
+    last: Option<u8>,
+    count: usize,
+}
+
+impl RepetitionCount {
+    fn new(h: f64) -> RepetitionCount {
+        RepetitionCount {cutoff: 1 + (20.0/h).ceil() as usize, last: None, count: 0}
+    }
+
+    fn test(&mut self, x: u8) -> bool {
+        if self.last == Some(x) {
+            self.count += 1;
+        } else {
+            self.last = Some(x);
+            self.count = 1;
+        }
+        self.count < self.cutoff
+    }
+}
+
+/// Adaptive Proportion Test (SP 800-90B 4.4.2) with the non-binary window of 512 samples
+struct AdaptiveProportion {
+    cutoff: usize,
+    first: u8,
+    count: usize,
+    seen: usize,
+}
+
+impl AdaptiveProportion {
+    const WINDOW: usize = 512;
+
+    fn new(h: f64) -> AdaptiveProportion {
+        let cutoff = 1 + critbinom(AdaptiveProportion::WINDOW, 2f64.powf(-h), 1.0 - 2f64.powi(-20));
+        AdaptiveProportion {cutoff, first: 0, count: 0, seen: 0}
+    }
+
+    fn test(&mut self, x: u8) -> bool {
+        if self.seen == 0 {
+            self.first = x;
+            self.count = 1;
+        } else if x == self.first {
+            self.count += 1;
+        }
+
+        self.seen = (self.seen + 1) % AdaptiveProportion::WINDOW;
+        self.count < self.cutoff
+    }
+}
+
+// Smallest k with P(X <= k) >= q for X ~ Binomial(n, p)
+fn critbinom(n: usize, p: f64, q: f64) -> usize {
+    let mut cdf = 0.0;
+    let mut ln_comb = 0.0;
+    for k in 0..=n {
+        if k > 0 {
+            ln_comb += ((n - k + 1) as f64).ln() - (k as f64).ln();
+        }
+        cdf += (ln_comb + k as f64 * p.ln() + (n - k) as f64 * (1.0 - p).ln()).exp();
+        if cdf >= q {
+            return k;
+        }
+    }
+    n
+}
+
+const STARTUP_SAMPLES: usize = 1024;
+
+/// Noise source monitored by the Repetition Count and Adaptive Proportion tests.
+///
+/// A startup test over 1024 samples runs on construction. Once any test fails the source
+/// stays in the error state and every read fails.
+pub struct MonitoredSource<S: NoiseSource> {
+    source: S,
+    rct: RepetitionCount,
+    apt: AdaptiveProportion,
+    failed: bool,
+}
+
+impl<S: NoiseSource> MonitoredSource<S> {
+    /// `min_entropy` is the claimed min-entropy per sample in bits, `0 < min_entropy <= 8`.
+    pub fn new(source: S, min_entropy: f64) -> Result<MonitoredSource<S>,&'static str> {
+        if !(min_entropy > 0.0 && min_entropy <= 8.0) {
+            return Err("Min-entropy per sample must be in (0, 8]");
+        }
+
+        let mut monitored = MonitoredSource {
+            source,
+            rct: RepetitionCount::new(min_entropy),
+            apt: AdaptiveProportion::new(min_entropy),
+            failed: false,
+        };
+
+        let mut samples = [0;STARTUP_SAMPLES];
+        monitored.read(&mut samples).map_err(|_| "Startup health test failed")?;
+        Ok(monitored)
+    }
+
+    pub fn is_healthy(&self) -> bool {
+        !self.failed
+    }
+
+    pub fn read(&mut self, buf: &mut [u8]) -> Result<(),&'static str> {
+        if self.failed {
+            return Err("Entropy source is in the error state");
+        }
+
+        self.source.read(buf)?;
+        for x in buf.iter() {
+            let rct = self.rct.test(*x);
+            let apt = self.apt.test(*x);
+            if !rct || !apt {
+                self.failed = true;
+                for v in buf.iter_mut() {
+                    *v = 0;
+                }
+                return Err(if !rct {"Repetition count test failed"} else {"Adaptive proportion test failed"});
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Fixed(Vec<u8>, usize);
+
+    impl NoiseSource for Fixed {
+        fn read(&mut self, buf: &mut [u8]) -> Result<(),&'static str> {
+            for v in buf.iter_mut() {
+                *v = self.0[self.1 % self.0.len()];
+                self.1 += 1;
+            }
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_cutoffs() {
+        // SP 800-90B table 2, W = 512
+        for (h, c) in [(0.5,410),(1.0,311),(2.0,177),(4.0,62),(8.0,13)].iter() {
+            assert_eq!(AdaptiveProportion::new(*h).cutoff,*c);
+        }
+        assert_eq!(RepetitionCount::new(1.0).cutoff,21);
+        assert_eq!(RepetitionCount::new(8.0).cutoff,4);
+    }
+
+    #[test]
+    fn test_os_noise() {
+        let mut source = MonitoredSource::new(OsNoise, 6.0).unwrap();
+        let mut buf = [0;4096];
+        for _ in 0..16 {
+            source.read(&mut buf).unwrap();
+        }
+        assert!(source.is_healthy());
+    }
+
+    #[test]
+    fn test_bad_min_entropy() {
+        for h in [0.0, -1.0, 8.5, f64::NAN].iter() {
+            let r = MonitoredSource::new(Fixed(vec![0], 0), *h);
+            assert_eq!(r.err(),Some("Min-entropy per sample must be in (0, 8]"));
+        }
+    }
+
+    #[test]
+    fn test_stuck_source() {
+        let r = MonitoredSource::new(Fixed(vec![0x42], 0), 4.0);
+        assert_eq!(r.err(),Some("Startup health test failed"));
+    }
+
+    #[test]
+    fn test_failures() {
+        // passes startup, then gets stuck
+        let mut samples: Vec<u8> = (0..=255).cycle().take(1024).collect();
+        samples.extend_from_slice(&[7;16]);
+        let mut source = MonitoredSource::new(Fixed(samples, 0), 4.0).unwrap();
+        let mut buf = [0;16];
+        assert_eq!(source.read(&mut buf),Err("Repetition count test failed"));
+        assert!(!source.is_healthy());
+        assert!(source.read(&mut buf).is_err());
+
+        // biased without long runs: every other sample is the same value
+        let samples: Vec<u8> = (0..=255u8).flat_map(|x| vec![0, x | 1]).collect();
+        let r = MonitoredSource::new(Fixed(samples, 0), 4.0);
+        assert_eq!(r.err(),Some("Startup health test failed"));
+    }
+
+    #[test]
+    fn test_file_noise() {
+        let path = std::env::temp_dir().join(format!("crusto-noise-{}", std::process::id()));
+        let mut data = vec![0;2048];
+        OsRng.fill_bytes(&mut data);
+        std::fs::write(&path, &data).unwrap();
+
+        let mut source = MonitoredSource::new(FileNoise::open(&path).unwrap(), 6.0).unwrap();
+        let mut buf = [0;1024];
+        source.read(&mut buf).unwrap();
+        assert_eq!(buf[..],data[1024..]);
+        assert!(source.read(&mut buf).is_err());
+
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,193 @@
+// Min-entropy estimators for 8-bit samples (NIST SP 800-90B section 6.3)
+use std::collections::HashMap;
+use std::path::Path;
+
+const Z: f64 = 2.576;
+const MAX_TUPLE: usize = 64;
+
+/// Per-sample min-entropy estimates in bits.
+#[derive(Debug,Clone,Copy)]
+pub struct Estimate {
+    pub most_common_value: f64,
+    pub t_tuple: f64,
+    pub lrs: f64,
+    /// Markov estimate of the bit string, scaled to 8-bit samples
+    pub markov: f64,
+    /// Minimum of the estimates above
+    pub min_entropy: f64,
+}
+
+/// Runs the estimators over a captured sample file, one sample per byte.
+pub fn estimate_file<P: AsRef<Path>>(path: P) -> std::io::Result<Estimate> {
+    Ok(estimate(&std::fs::read(path)?))
+}
+
+pub fn estimate(samples: &[u8]) -> Estimate {
+    assert!(samples.len() > 1, "not enough samples");
+
+    let most_common_value = most_common_value(samples);
+    let t_tuple = t_tuple(samples);
+    let lrs = lrs(samples);
+    let markov = 8.0 * markov(samples);
+    let min_entropy = [most_common_value, t_tuple, lrs, markov].iter().cloned().fold(8.0, f64::min);
+
+    Estimate {most_common_value, t_tuple, lrs, markov, min_entropy}
+}
+
+// upper bound of the 99% confidence interval, as min-entropy
+fn upper_bound_entropy(p: f64, l: usize) -> f64 {
+    let pu = (p + Z * (p * (1.0 - p) / (l as f64 - 1.0)).sqrt()).min(1.0);
+    -pu.log2()
+}
+
+/// Most Common Value estimate (6.3.1)
+pub fn most_common_value(samples: &[u8]) -> f64 {
+    let mut counts = [0usize;256];
+    for x in samples.iter() {
+        counts[*x as usize] += 1;
+    }
+
+    let p = *counts.iter().max().unwrap() as f64 / samples.len() as f64;
+    upper_bound_entropy(p, samples.len())
+}
+
+fn tuple_counts(samples: &[u8], len: usize) -> HashMap<&[u8],usize> {
+    let mut counts = HashMap::new();
+    for w in samples.windows(len) {
+        *counts.entry(w).or_insert(0) += 1;
+    }
+    counts
+}
+
+/// t-Tuple estimate (6.3.5)
+pub fn t_tuple(samples: &[u8]) -> f64 {
+    let l = samples.len();
+    let mut p_max = 0f64;
+
+    for i in 1..=MAX_TUPLE.min(l) {
+        let q = *tuple_counts(samples, i).values().max().unwrap();
+        if q < 35 {
+            break;
+        }
+        let p = q as f64 / (l - i + 1) as f64;
+        p_max = p_max.max(p.powf(1.0 / i as f64));
+    }
+
+    if p_max == 0.0 {
+        // no tuple is common enough, fall back to the per-sample bound
+        return most_common_value(samples);
+    }
+    upper_bound_entropy(p_max, l)
+}
+
+/// Longest Repeated Substring estimate (6.3.6)
+///
+/// Tuple lengths are capped at 64 samples, which only matters for badly broken sources.
+/// Returns 8 when no tuple length qualifies for the estimate.
+pub fn lrs(samples: &[u8]) -> f64 {
+    let l = samples.len();
+    let mut p_max = 0f64;
+
+    for w in 1..=MAX_TUPLE.min(l - 1) {
+        let counts = tuple_counts(samples, w);
+        if counts.values().all(|c| *c < 2) {
+            break;
+        }
+        if *counts.values().max().unwrap() >= 35 {
+            // below u, covered by the t-tuple estimate
+            continue;
+        }
+
+        let n = (l - w + 1) as f64;
+        let pairs: f64 = counts.values().map(|c| (*c as f64) * (*c as f64 - 1.0) / 2.0).sum();
+        let p = pairs / (n * (n - 1.0) / 2.0);
+        p_max = p_max.max(p.powf(1.0 / w as f64));
+    }
+
+    if p_max == 0.0 {
+        return 8.0;
+    }
+    upper_bound_entropy(p_max, l)
+}
+
+/// Markov estimate (6.3.3) of the samples as a bit string, in bits per bit
+pub fn markov(samples: &[u8]) -> f64 {
+    let bits: Vec<usize> = samples.iter().flat_map(|x| (0..8).rev().map(move |i| (*x >> i & 1) as usize)).collect();
+
+    let mut ones = 0usize;
+    let mut transitions = [[0usize;2];2];
+    for (i, b) in bits.iter().enumerate() {
+        ones += b;
+        if i + 1 < bits.len() {
+            transitions[*b][bits[i+1]] += 1;
+        }
+    }
+
+    let p1 = ones as f64 / bits.len() as f64;
+    let p0 = 1.0 - p1;
+    let t = |from: usize, to: usize| {
+        let total = transitions[from][0] + transitions[from][1];
+        if total == 0 {0.0} else {transitions[from][to] as f64 / total as f64}
+    };
+    let (p00, p01, p10, p11) = (t(0,0), t(0,1), t(1,0), t(1,1));
+
+    // log2 probabilities of the most likely 128-bit sequences
+    let lg = |x: f64| x.log2();
+    let candidates = [
+        lg(p0) + 127.0 * lg(p00),
+        lg(p0) + 64.0 * lg(p01) + 63.0 * lg(p10),
+        lg(p0) + lg(p01) + 126.0 * lg(p11),
+        lg(p1) + lg(p10) + 126.0 * lg(p00),
+        lg(p1) + 64.0 * lg(p10) + 63.0 * lg(p01),
+        lg(p1) + 127.0 * lg(p11),
+    ];
+    let max = candidates.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+
+    (-max / 128.0).min(1.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::Rng;
+
+    #[test]
+    fn test_random_samples() {
+        let samples = Rng::from_seed([5;32]).generate_bytes(100000);
+        let e = estimate(&samples);
+        assert!(e.most_common_value > 7.5);
+        assert!(e.t_tuple > 7.0);
+        assert!(e.lrs > 7.0);
+        assert!(e.markov > 7.9);
+        assert!(e.min_entropy > 7.0);
+    }
+
+    #[test]
+    fn test_constant_samples() {
+        let e = estimate(&[0x5a;4096]);
+        assert!(e.most_common_value < 0.01);
+        assert!(e.t_tuple < 0.01);
+        assert!(e.min_entropy < 0.01);
+    }
+
+    #[test]
+    fn test_biased_samples() {
+        // 4 equally likely values: 2 bits per sample
+        let samples: Vec<u8> = Rng::from_seed([6;32]).generate_bytes(100000).iter().map(|x| x & 3).collect();
+        let e = estimate(&samples);
+        assert!(e.most_common_value > 1.9 && e.most_common_value <= 2.0);
+        assert!(e.min_entropy <= 2.0);
+
+        // alternating bits have no entropy under the Markov model
+        assert!(markov(&[0x55;1024]) < 0.01);
+    }
+
+    #[test]
+    fn test_estimate_file() {
+        let path = std::env::temp_dir().join(format!("crusto-samples-{}", std::process::id()));
+        std::fs::write(&path, Rng::from_seed([7;32]).generate_bytes(10000)).unwrap();
+        let e = estimate_file(&path).unwrap();
+        assert!(e.min_entropy > 6.0);
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,380 @@
+pub mod entropy;
+pub mod estimate;
+pub mod sts;
+pub mod util;
+
+use num::BigUint;
+use rand::prelude::*;
+use rand_chacha::ChaChaRng;
+use std::convert::TryFrom;
+use std::process;
+use std::time::{Duration,Instant};
+use entropy::{MonitoredSource,NoiseSource,OsNoise};
+
+/// Min-entropy per byte claimed for OS randomness when seeding `Rng::new` and `ReseedingRng`.
+/// Below the ideal 8 bits so that the health tests practically never fail on good output.
+const OS_MIN_ENTROPY: f64 = 6.0;
+
+// 32-byte seed from OS randomness that has passed the continuous health tests
+fn os_seed() -> [u8;32] {
+    let mut seed = [0;32];
+    MonitoredSource::new(OsNoise, OS_MIN_ENTROPY)
+        .and_then(|mut source| source.read(&mut seed))
+        .expect("OS entropy source failed its health tests");
+    seed
+}
+
+/// ChaCha-based generator. Clones continue the same stream; see `ReseedingRng` for a
+/// generator that reseeds itself and is safe across `fork`.
+#[derive(Clone)]
+pub struct Rng {
+    rng: ChaChaRng,
+}
+
+impl Default for Rng {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Rng {
+    /// Seeds from health-tested OS entropy. Panics if the entropy source fails.
+    pub fn new() -> Rng {
+        Rng::from_seed(os_seed())
+    }
+
+    /// Deterministic generator from a 32-byte seed, for reproducible output.
+    pub fn from_seed(seed: [u8;32]) -> Rng {
+        Rng {rng:ChaChaRng::from_seed(seed)}
+    }
+
+    /// Seeds from a health-tested entropy source.
+    pub fn from_source<S: NoiseSource>(source: &mut MonitoredSource<S>) -> Result<Rng,&'static str> {
+        let mut seed = [0;32];
+        source.read(&mut seed)?;
+        Ok(Rng::from_seed(seed))
+    }
+
+    /// Continues an existing ChaCha generator at the given stream and word position.
+    pub fn from_chacha(mut rng: ChaChaRng, stream: u64, word_pos: u128) -> Rng {
+        rng.set_stream(stream);
+        rng.set_word_pos(word_pos);
+        Rng {rng}
+    }
+
+    pub fn fill(&mut self, dest: &mut [u8]) {
+        self.rng.fill_bytes(dest);
+    }
+
+    pub fn generate_bytes(&mut self, byte_size: usize) -> Vec<u8> {
+        let mut data = vec![0; byte_size];
+        self.rng.fill_bytes(&mut data[..]);
+        data
+    }
+
+    pub fn generate_uint(&mut self, byte_size: usize) -> BigUint {
+        let data = self.generate_bytes(byte_size);
+        BigUint::from_bytes_le(&data[..])
+    }
+
+    /// Uniform integer in `[low, high)`.
+    pub fn generate_range(&mut self, low: u64, high: u64) -> u64 {
+        assert!(low < high, "empty range");
+
+        let span = high - low;
+        let mask = u64::MAX.checked_shr((span - 1).leading_zeros()).unwrap_or(0);
+        loop {
+            let x = self.rng.next_u64() & mask;
+            if x < span {
+                return low + x;
+            }
+        }
+    }
+
+    /// Uniform `BigUint` in `[0, bound)`.
+    pub fn generate_uint_below(&mut self, bound: &BigUint) -> BigUint {
+        assert!(*bound > BigUint::from(0u8), "empty range");
+
+        let bits = bound.bits();
+        let bytes = bits.div_ceil(8) as usize;
+        loop {
+            let mut data = self.generate_bytes(bytes);
+            if bits % 8 != 0 {
+                data[bytes-1] &= (1u8 << (bits % 8)) - 1;
+            }
+
+            let x = BigUint::from_bytes_le(&data);
+            if x < *bound {
+                return x;
+            }
+        }
+    }
+}
+
+impl RngCore for Rng {
+    fn next_u32(&mut self) -> u32 {
+        self.rng.next_u32()
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.rng.next_u64()
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        self.rng.fill_bytes(dest)
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+        self.rng.try_fill_bytes(dest)
+    }
+}
+
+impl CryptoRng for Rng {}
+
+impl SeedableRng for Rng {
+    type Seed = [u8;32];
+
+    fn from_seed(seed: Self::Seed) -> Self {
+        Rng::from_seed(seed)
+    }
+}
+
+/// ChaCha generator that reseeds from health-tested OS entropy after a number of output bytes or an
+/// elapsed time, and whenever it detects that the process has forked.
+///
+/// It cannot be cloned, since a clone would repeat the same stream. Use `fork` to get an
+/// independently seeded generator with the same thresholds.
+pub struct ReseedingRng {
+    rng: ChaChaRng,
+    byte_threshold: u64,
+    time_threshold: Duration,
+    bytes_since_reseed: u64,
+    seeded_at: Instant,
+    pid: u32,
+}
+
+impl Default for ReseedingRng {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ReseedingRng {
+    const DEFAULT_BYTE_THRESHOLD: u64 = 1 << 20;
+    const DEFAULT_TIME_THRESHOLD: Duration = Duration::from_secs(3600);
+
+    pub fn new() -> ReseedingRng {
+        ReseedingRng {
+            rng: ChaChaRng::from_seed(os_seed()),
+            byte_threshold: ReseedingRng::DEFAULT_BYTE_THRESHOLD,
+            time_threshold: ReseedingRng::DEFAULT_TIME_THRESHOLD,
+            bytes_since_reseed: 0,
+            seeded_at: Instant::now(),
+            pid: process::id(),
+        }
+    }
+
+    /// Reseed after this many bytes of output.
+    pub fn with_byte_threshold(mut self, bytes: u64) -> Self {
+        self.byte_threshold = bytes;
+        self
+    }
+
+    /// Reseed once this much time has passed since the last reseed.
+    pub fn with_time_threshold(mut self, time: Duration) -> Self {
+        self.time_threshold = time;
+        self
+    }
+
+    /// Independently seeded generator with the same thresholds.
+    pub fn fork(&self) -> ReseedingRng {
+        ReseedingRng::new().with_byte_threshold(self.byte_threshold).with_time_threshold(self.time_threshold)
+    }
+
+    pub fn reseed(&mut self) {
+        self.rng = ChaChaRng::from_seed(os_seed());
+        self.bytes_since_reseed = 0;
+        self.seeded_at = Instant::now();
+        self.pid = process::id();
+    }
+
+    /// Fills `dest`, reseeding part way through whenever the byte threshold is reached.
+    pub fn fill(&mut self, dest: &mut [u8]) {
+        let mut rest = dest;
+        while !rest.is_empty() {
+            self.check_reseed();
+            let room = self.byte_threshold.saturating_sub(self.bytes_since_reseed).max(1);
+            let n = rest.len().min(usize::try_from(room).unwrap_or(usize::MAX));
+            let (head, tail) = rest.split_at_mut(n);
+            self.bytes_since_reseed += n as u64;
+            self.rng.fill_bytes(head);
+            rest = tail;
+        }
+    }
+
+    pub fn generate_bytes(&mut self, byte_size: usize) -> Vec<u8> {
+        let mut data = vec![0; byte_size];
+        self.fill(&mut data);
+        data
+    }
+
+    fn check_reseed(&mut self) {
+        if self.pid != process::id()
+            || self.bytes_since_reseed >= self.byte_threshold
+            || self.seeded_at.elapsed() >= self.time_threshold {
+            self.reseed();
+        }
+    }
+
+    fn before_output(&mut self, len: usize) {
+        self.check_reseed();
+        self.bytes_since_reseed += len as u64;
+    }
+}
+
+impl RngCore for ReseedingRng {
+    fn next_u32(&mut self) -> u32 {
+        self.before_output(4);
+        self.rng.next_u32()
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.before_output(8);
+        self.rng.next_u64()
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        self.fill(dest)
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+        self.fill(dest);
+        Ok(())
+    }
+}
+
+impl CryptoRng for ReseedingRng {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::Rng;
+    use num::bigint::RandBigInt;
+
+    #[test]
+    fn test_seeded() {
+        let mut r1 = Rng::from_seed([7;32]);
+        let mut r2 = <Rng as SeedableRng>::seed_from_u64(0);
+        let mut r3 = <Rng as SeedableRng>::seed_from_u64(0);
+        let a = r2.generate_bytes(32);
+        assert_eq!(a,r3.generate_bytes(32));
+        assert_ne!(a,r1.generate_bytes(32));
+
+        // ChaCha20 with an all-zero key, RFC 7539 appendix A.1 test vector
+        let mut r = Rng::from_seed([0;32]);
+        assert_eq!(r.generate_bytes(8),[0x76,0xb8,0xe0,0xad,0xa0,0xf1,0x3d,0x90]);
+    }
+
+    #[test]
+    fn test_os_seed() {
+        assert_ne!(os_seed(),os_seed());
+        assert_ne!(Rng::new().generate_bytes(32),Rng::new().generate_bytes(32));
+    }
+
+    #[test]
+    fn test_from_source() {
+        let mut source = MonitoredSource::new(entropy::OsNoise, 6.0).unwrap();
+        let mut r1 = Rng::from_source(&mut source).unwrap();
+        let mut r2 = Rng::from_source(&mut source).unwrap();
+        assert_ne!(r1.generate_bytes(32),r2.generate_bytes(32));
+    }
+
+    #[test]
+    fn test_from_chacha() {
+        let mut r1 = Rng::from_seed([1;32]);
+        let mut skipped = [0;40];
+        r1.fill(&mut skipped);
+        let a = r1.generate_bytes(64);
+
+        let mut r2 = Rng::from_chacha(ChaChaRng::from_seed([1;32]), 0, 10);
+        assert_eq!(r2.generate_bytes(64),a);
+
+        let mut r3 = Rng::from_chacha(ChaChaRng::from_seed([1;32]), 1, 10);
+        assert_ne!(r3.generate_bytes(64),a);
+    }
+
+    #[test]
+    fn test_generate_range() {
+        let mut rng = Rng::from_seed([2;32]);
+        let mut seen = [false;6];
+        for _ in 0..1000 {
+            let x = rng.generate_range(10, 16);
+            assert!((10..16).contains(&x));
+            seen[(x-10) as usize] = true;
+        }
+        assert!(seen.iter().all(|x| *x));
+        assert_eq!(rng.generate_range(5, 6),5);
+        rng.generate_range(0, u64::MAX);
+    }
+
+    #[test]
+    fn test_generate_uint_below() {
+        let mut rng = Rng::from_seed([3;32]);
+        let bound = BigUint::from(1000u32) << 70u8;
+        for _ in 0..100 {
+            assert!(rng.generate_uint_below(&bound) < bound);
+        }
+        assert_eq!(rng.generate_uint_below(&BigUint::from(1u8)),BigUint::from(0u8));
+    }
+
+    #[test]
+    fn test_rand_compat() {
+        let mut rng = Rng::from_seed([4;32]);
+        let x = rng.gen_biguint(100);
+        assert!(x.bits() <= 100);
+    }
+
+    #[test]
+    fn test_reseeding_bytes() {
+        let mut rng = ReseedingRng::new().with_byte_threshold(64);
+        rng.generate_bytes(64);
+        assert_eq!(rng.bytes_since_reseed,64);
+        rng.generate_bytes(16);
+        assert_eq!(rng.bytes_since_reseed,16);
+        rng.next_u64();
+        assert_eq!(rng.bytes_since_reseed,24);
+
+        // a large request is split at the threshold rather than drawn from one seed
+        rng.generate_bytes(200);
+        assert_eq!(rng.bytes_since_reseed,32);
+        rng.generate_bytes(30);
+        assert_eq!(rng.bytes_since_reseed,62);
+    }
+
+    #[test]
+    fn test_reseeding_time() {
+        let mut rng = ReseedingRng::new().with_time_threshold(Duration::from_millis(10));
+        let seeded_at = rng.seeded_at;
+        rng.generate_bytes(16);
+        std::thread::sleep(Duration::from_millis(20));
+        rng.generate_bytes(16);
+        assert!(rng.seeded_at > seeded_at);
+        assert_eq!(rng.bytes_since_reseed,16);
+    }
+
+    #[test]
+    fn test_reseeding_fork() {
+        let mut rng = ReseedingRng::new();
+        rng.generate_bytes(16);
+
+        // pretend the generator was inherited from a parent process
+        rng.pid = rng.pid.wrapping_add(1);
+        rng.generate_bytes(16);
+        assert_eq!(rng.pid,process::id());
+        assert_eq!(rng.bytes_since_reseed,16);
+
+        let mut forked = rng.fork();
+        assert_eq!(forked.byte_threshold,rng.byte_threshold);
+        assert_ne!(forked.generate_bytes(32),rng.generate_bytes(32));
+    }
+}
@@ -0,0 +1,549 @@
+// NIST SP 800-22 statistical tests for random bit generators
+use std::f64::consts::{PI, SQRT_2};
+
+/// Significance level used by `TestResult::passed`.
+pub const ALPHA: f64 = 0.01;
+
+/// Bytes requested from a source at a time; within every DRBG request limit.
+const CHUNK: usize = 4096;
+
+#[derive(Debug,Clone)]
+pub struct TestResult {
+    pub name: &'static str,
+    pub p_values: Vec<f64>,
+}
+
+impl TestResult {
+    pub fn passed(&self) -> bool {
+        self.p_values.iter().all(|p| *p >= ALPHA)
+    }
+}
+
+/// Expands bytes into bits, most significant bit first.
+pub fn to_bits(bytes: &[u8]) -> Vec<u8> {
+    bytes.iter().flat_map(|x| (0..8).rev().map(move |i| *x >> i & 1)).collect()
+}
+
+/// Runs every test that applies to the length of `bytes` with the default parameters.
+///
+/// The rank test needs at least 38912 bits and the longest run test 128 bits; shorter
+/// inputs skip them. 10^6 bits (125000 bytes) is the recommended length.
+pub fn run(bytes: &[u8]) -> Vec<TestResult> {
+    let bits = to_bits(bytes);
+    let n = bits.len();
+    assert!(n >= 100, "at least 100 bits are required");
+
+    let log_n = (usize::BITS - 1 - n.leading_zeros()) as usize;
+    let serial_m = 16.min(log_n - 3);
+    let apen_m = 10.min(log_n - 6).max(1);
+
+    let mut results = vec![
+        TestResult {name: "frequency", p_values: vec![frequency(&bits)]},
+        TestResult {name: "block frequency", p_values: vec![block_frequency(&bits, 128.min(n / 4))]},
+        TestResult {name: "runs", p_values: vec![runs(&bits)]},
+    ];
+    if n >= 128 {
+        results.push(TestResult {name: "longest run", p_values: vec![longest_run(&bits)]});
+    }
+    if n >= 38912 {
+        results.push(TestResult {name: "rank", p_values: vec![rank(&bits)]});
+    }
+    let (p1, p2) = serial(&bits, serial_m);
+    let (forward, backward) = cumulative_sums(&bits);
+    results.extend(vec![
+        TestResult {name: "dft", p_values: vec![dft(&bits)]},
+        TestResult {name: "serial", p_values: vec![p1, p2]},
+        TestResult {name: "approximate entropy", p_values: vec![approximate_entropy(&bits, apen_m)]},
+        TestResult {name: "cumulative sums", p_values: vec![forward, backward]},
+    ]);
+    results
+}
+
+/// Runs the suite over `len` bytes pulled from `fill`, e.g. `|b| rng.fill(b)` or
+/// `|b| drbg.generate(b, &[]).unwrap()`.
+pub fn run_source<F: FnMut(&mut [u8])>(len: usize, mut fill: F) -> Vec<TestResult> {
+    let mut bytes = vec![0;len];
+    for chunk in bytes.chunks_mut(CHUNK) {
+        fill(chunk);
+    }
+    run(&bytes)
+}
+
+/// Frequency (monobit) test (2.1)
+pub fn frequency(bits: &[u8]) -> f64 {
+    let n = bits.len() as f64;
+    let s: f64 = bits.iter().map(|b| 2.0 * *b as f64 - 1.0).sum();
+    erfc(s.abs() / n.sqrt() / SQRT_2)
+}
+
+/// Frequency test within blocks of `m` bits (2.2)
+pub fn block_frequency(bits: &[u8], m: usize) -> f64 {
+    let blocks = bits.len() / m;
+    let chi2: f64 = bits.chunks_exact(m).map(|block| {
+        let pi = block.iter().map(|b| *b as usize).sum::<usize>() as f64 / m as f64;
+        (pi - 0.5) * (pi - 0.5)
+    }).sum::<f64>() * 4.0 * m as f64;
+    igamc(blocks as f64 / 2.0, chi2 / 2.0)
+}
+
+/// Runs test (2.3); 0 when the frequency prerequisite fails.
+pub fn runs(bits: &[u8]) -> f64 {
+    let n = bits.len() as f64;
+    let pi = bits.iter().map(|b| *b as usize).sum::<usize>() as f64 / n;
+    if (pi - 0.5).abs() >= 2.0 / n.sqrt() {
+        return 0.0;
+    }
+
+    let v = 1 + bits.windows(2).filter(|w| w[0] != w[1]).count();
+    let x = 2.0 * n * pi * (1.0 - pi);
+    erfc((v as f64 - x).abs() / (2.0 * (2.0 * n).sqrt() * pi * (1.0 - pi)))
+}
+
+/// Test for the longest run of ones in a block (2.4), for at least 128 bits.
+pub fn longest_run(bits: &[u8]) -> f64 {
+    let n = bits.len();
+    assert!(n >= 128, "at least 128 bits are required");
+
+    let (m, lowest, pi): (usize, usize, &[f64]) = if n < 6272 {
+        (8, 1, &[0.21484375, 0.3671875, 0.23046875, 0.1875])
+    } else if n < 750000 {
+        (128, 4, &[0.1174035788, 0.242955959, 0.249363483, 0.17517706, 0.102701071, 0.112398847])
+    } else {
+        (10000, 10, &[0.0882, 0.2092, 0.2483, 0.1933, 0.1208, 0.0675, 0.0727])
+    };
+
+    let k = pi.len() - 1;
+    let mut v = vec![0usize;pi.len()];
+    for block in bits.chunks_exact(m) {
+        let mut longest = 0;
+        let mut run = 0;
+        for b in block.iter() {
+            run = if *b == 1 {run + 1} else {0};
+            longest = longest.max(run);
+        }
+        v[longest.clamp(lowest, lowest + k) - lowest] += 1;
+    }
+
+    let blocks = (n / m) as f64;
+    let chi2: f64 = v.iter().zip(pi.iter()).map(|(v, p)| (*v as f64 - blocks * p).powi(2) / (blocks * p)).sum();
+    igamc(k as f64 / 2.0, chi2 / 2.0)
+}
+
+/// Binary matrix rank test (2.5) over 32x32 matrices, for at least 38912 bits.
+pub fn rank(bits: &[u8]) -> f64 {
+    const M: usize = 32;
+    let count = bits.len() / (M * M);
+    assert!(count >= 38, "at least 38912 bits are required");
+
+    let (mut full, mut minus_one) = (0, 0);
+    for block in bits.chunks_exact(M * M).take(count) {
+        let mut rows: Vec<u32> = block.chunks_exact(M).map(|r| r.iter().fold(0, |acc, b| acc << 1 | *b as u32)).collect();
+        match gf2_rank(&mut rows) {
+            32 => full += 1,
+            31 => minus_one += 1,
+            _ => (),
+        }
+    }
+
+    let p_full = rank_probability(M, M);
+    let p_minus_one = rank_probability(M - 1, M);
+    let p_rest = 1.0 - p_full - p_minus_one;
+    let n = count as f64;
+    let rest = count - full - minus_one;
+    let chi2 = (full as f64 - p_full * n).powi(2) / (p_full * n)
+        + (minus_one as f64 - p_minus_one * n).powi(2) / (p_minus_one * n)
+        + (rest as f64 - p_rest * n).powi(2) / (p_rest * n);
+    (-chi2 / 2.0).exp()
+}
+
+fn gf2_rank(rows: &mut [u32]) -> usize {
+    let mut rank = 0;
+    for col in (0..32).rev() {
+        let pivot = match (rank..rows.len()).find(|i| rows[*i] >> col & 1 == 1) {
+            Some(i) => i,
+            None => continue,
+        };
+        rows.swap(rank, pivot);
+        for i in 0..rows.len() {
+            if i != rank && rows[i] >> col & 1 == 1 {
+                rows[i] ^= rows[rank];
+            }
+        }
+        rank += 1;
+    }
+    rank
+}
+
+// Probability that a random m x m binary matrix has rank r
+fn rank_probability(r: usize, m: usize) -> f64 {
+    let mut p = 2f64.powi((r * (2 * m - r)) as i32 - (m * m) as i32);
+    for i in 0..r {
+        let a = 1.0 - 2f64.powi(i as i32 - m as i32);
+        p *= a * a / (1.0 - 2f64.powi(i as i32 - r as i32));
+    }
+    p
+}
+
+/// Discrete Fourier transform (spectral) test (2.6)
+pub fn dft(bits: &[u8]) -> f64 {
+    let n = bits.len();
+    let x: Vec<Complex> = bits.iter().map(|b| Complex(2.0 * *b as f64 - 1.0, 0.0)).collect();
+    let spectrum = fourier(&x);
+
+    let t = ((1.0f64 / 0.05).ln() * n as f64).sqrt();
+    let n0 = 0.95 * n as f64 / 2.0;
+    let n1 = spectrum[..n / 2].iter().filter(|c| c.abs() < t).count() as f64;
+    let d = (n1 - n0) / (n as f64 * 0.95 * 0.05 / 4.0).sqrt();
+    erfc(d.abs() / SQRT_2)
+}
+
+/// Serial test (2.11) with overlapping `m`-bit patterns; returns both p-values.
+pub fn serial(bits: &[u8], m: usize) -> (f64, f64) {
+    assert!(m >= 3, "block length must be at least 3");
+    let psi = |m: usize| {
+        if m == 0 {
+            return 0.0;
+        }
+        let n = bits.len() as f64;
+        let sum: f64 = pattern_counts(bits, m).iter().map(|c| (*c as f64).powi(2)).sum();
+        sum * 2f64.powi(m as i32) / n - n
+    };
+
+    let (a, b, c) = (psi(m), psi(m - 1), psi(m - 2));
+    let del1 = a - b;
+    let del2 = a - 2.0 * b + c;
+    (igamc(2f64.powi(m as i32 - 2), del1 / 2.0), igamc(2f64.powi(m as i32 - 3), del2 / 2.0))
+}
+
+/// Approximate entropy test (2.12) with block length `m`
+pub fn approximate_entropy(bits: &[u8], m: usize) -> f64 {
+    let n = bits.len() as f64;
+    let phi = |m: usize| -> f64 {
+        pattern_counts(bits, m).iter().filter(|c| **c > 0).map(|c| {
+            let p = *c as f64 / n;
+            p * p.ln()
+        }).sum()
+    };
+
+    let apen = phi(m) - phi(m + 1);
+    let chi2 = 2.0 * n * (2f64.ln() - apen);
+    igamc(2f64.powi(m as i32 - 1), chi2 / 2.0)
+}
+
+// Counts of every overlapping m-bit pattern, wrapping around the end of the sequence
+fn pattern_counts(bits: &[u8], m: usize) -> Vec<usize> {
+    let mut counts = vec![0;1 << m];
+    let mask = (1 << m) - 1;
+    let mut v = bits[..m - 1].iter().fold(0usize, |acc, b| acc << 1 | *b as usize);
+    for b in bits.iter().chain(bits[..m - 1].iter()).skip(m - 1) {
+        v = (v << 1 | *b as usize) & mask;
+        counts[v] += 1;
+    }
+    counts
+}
+
+/// Cumulative sums test (2.13); returns the forward and backward p-values.
+pub fn cumulative_sums(bits: &[u8]) -> (f64, f64) {
+    let max_excursion = |iter: &mut dyn Iterator<Item=&u8>| {
+        let mut s = 0i64;
+        let mut z = 0;
+        for b in iter {
+            s += 2 * *b as i64 - 1;
+            z = z.max(s.abs());
+        }
+        z
+    };
+
+    let n = bits.len();
+    let forward = max_excursion(&mut bits.iter());
+    let backward = max_excursion(&mut bits.iter().rev());
+    (cusum_p_value(n, forward), cusum_p_value(n, backward))
+}
+
+fn cusum_p_value(n: usize, z: i64) -> f64 {
+    let (n, z) = (n as f64, z as f64);
+    let sqrt_n = n.sqrt();
+
+    let mut sum1 = 0.0;
+    for k in ((-n / z + 1.0) / 4.0) as i64..=((n / z - 1.0) / 4.0) as i64 {
+        let k = k as f64;
+        sum1 += normal_cdf((4.0 * k + 1.0) * z / sqrt_n) - normal_cdf((4.0 * k - 1.0) * z / sqrt_n);
+    }
+    let mut sum2 = 0.0;
+    for k in ((-n / z - 3.0) / 4.0) as i64..=((n / z - 1.0) / 4.0) as i64 {
+        let k = k as f64;
+        sum2 += normal_cdf((4.0 * k + 3.0) * z / sqrt_n) - normal_cdf((4.0 * k + 1.0) * z / sqrt_n);
+    }
+    1.0 - sum1 + sum2
+}
+
+#[derive(Debug,Clone,Copy)]
+struct Complex(f64, f64);
+
+impl Complex {
+    fn add(self, o: Complex) -> Complex {
+        Complex(self.0 + o.0, self.1 + o.1)
+    }
+
+    fn sub(self, o: Complex) -> Complex {
+        Complex(self.0 - o.0, self.1 - o.1)
+    }
+
+    fn mul(self, o: Complex) -> Complex {
+        Complex(self.0 * o.0 - self.1 * o.1, self.0 * o.1 + self.1 * o.0)
+    }
+
+    fn conj(self) -> Complex {
+        Complex(self.0, -self.1)
+    }
+
+    fn abs(self) -> f64 {
+        self.0.hypot(self.1)
+    }
+
+    fn expi(theta: f64) -> Complex {
+        Complex(theta.cos(), theta.sin())
+    }
+}
+
+// In-place radix-2 FFT; `a.len()` is a power of two
+fn fft(a: &mut [Complex], inverse: bool) {
+    let n = a.len();
+    let mut j = 0;
+    for i in 1..n {
+        let mut bit = n >> 1;
+        while j & bit != 0 {
+            j ^= bit;
+            bit >>= 1;
+        }
+        j |= bit;
+        if i < j {
+            a.swap(i, j);
+        }
+    }
+
+    let sign = if inverse {1.0} else {-1.0};
+    let mut len = 2;
+    while len <= n {
+        let w = Complex::expi(sign * 2.0 * PI / len as f64);
+        for start in (0..n).step_by(len) {
+            let mut wk = Complex(1.0, 0.0);
+            for k in 0..len / 2 {
+                let u = a[start + k];
+                let v = a[start + k + len / 2].mul(wk);
+                a[start + k] = u.add(v);
+                a[start + k + len / 2] = u.sub(v);
+                wk = wk.mul(w);
+            }
+        }
+        len <<= 1;
+    }
+}
+
+// DFT of any length (Bluestein's algorithm for lengths that are not powers of two)
+fn fourier(x: &[Complex]) -> Vec<Complex> {
+    let n = x.len();
+    if n.is_power_of_two() {
+        let mut a = x.to_vec();
+        fft(&mut a, false);
+        return a;
+    }
+
+    // chirp w_k = exp(-i pi k^2 / n), with k^2 reduced mod 2n to keep the angle accurate
+    let chirp: Vec<Complex> = (0..n as u64)
+        .map(|k| Complex::expi(-PI * ((k * k) % (2 * n as u64)) as f64 / n as f64))
+        .collect();
+
+    let size = (2 * n - 1).next_power_of_two();
+    let mut a = vec![Complex(0.0, 0.0);size];
+    let mut b = vec![Complex(0.0, 0.0);size];
+    for k in 0..n {
+        a[k] = x[k].mul(chirp[k]);
+        b[k] = chirp[k].conj();
+        if k > 0 {
+            b[size - k] = chirp[k].conj();
+        }
+    }
+
+    fft(&mut a, false);
+    fft(&mut b, false);
+    for (u, v) in a.iter_mut().zip(b.iter()) {
+        *u = u.mul(*v);
+    }
+    fft(&mut a, true);
+
+    (0..n).map(|k| {
+        let c = a[k].mul(chirp[k]);
+        Complex(c.0 / size as f64, c.1 / size as f64)
+    }).collect()
+}
+
+fn ln_gamma(x: f64) -> f64 {
+    // Lanczos approximation, g = 7
+    const COEF: [f64;9] = [
+        0.999_999_999_999_809_9, 676.520_368_121_885_1, -1_259.139_216_722_402_8,
+        771.323_428_777_653_1, -176.615_029_162_140_6, 12.507_343_278_686_905,
+        -0.138_571_095_265_720_12, 9.984_369_578_019_572e-6, 1.505_632_735_149_311_6e-7,
+    ];
+    if x < 0.5 {
+        return (PI / (PI * x).sin()).ln() - ln_gamma(1.0 - x);
+    }
+
+    let x = x - 1.0;
+    let mut a = COEF[0];
+    for (i, c) in COEF.iter().enumerate().skip(1) {
+        a += c / (x + i as f64);
+    }
+    let t = x + 7.5;
+    0.5 * (2.0 * PI).ln() + (x + 0.5) * t.ln() - t + a.ln()
+}
+
+/// Regularized upper incomplete gamma function Q(a, x)
+fn igamc(a: f64, x: f64) -> f64 {
+    const EPS: f64 = 1e-15;
+    if x <= 0.0 {
+        return 1.0;
+    }
+    let prefix = (-x + a * x.ln() - ln_gamma(a)).exp();
+
+    if x < a + 1.0 {
+        // series for P(a, x)
+        let mut term = 1.0 / a;
+        let mut sum = term;
+        let mut ap = a;
+        for _ in 0..10000 {
+            ap += 1.0;
+            term *= x / ap;
+            sum += term;
+            if term.abs() < sum.abs() * EPS {
+                break;
+            }
+        }
+        return 1.0 - sum * prefix;
+    }
+
+    // continued fraction for Q(a, x), modified Lentz
+    let tiny = 1e-300;
+    let mut b = x + 1.0 - a;
+    let mut c = 1.0 / tiny;
+    let mut d = 1.0 / b;
+    let mut h = d;
+    for i in 1..10000 {
+        let an = -(i as f64) * (i as f64 - a);
+        b += 2.0;
+        d = an * d + b;
+        if d.abs() < tiny {
+            d = tiny;
+        }
+        c = b + an / c;
+        if c.abs() < tiny {
+            c = tiny;
+        }
+        d = 1.0 / d;
+        let delta = d * c;
+        h *= delta;
+        if (delta - 1.0).abs() < EPS {
+            break;
+        }
+    }
+    h * prefix
+}
+
+fn erfc(x: f64) -> f64 {
+    if x < 0.0 {
+        2.0 - igamc(0.5, x * x)
+    } else {
+        igamc(0.5, x * x)
+    }
+}
+
+fn normal_cdf(x: f64) -> f64 {
+    0.5 * erfc(-x / SQRT_2)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::drbg::{Drbg, HmacDrbg};
+    use crate::rng::Rng;
+
+    fn parse(s: &str) -> Vec<u8> {
+        s.bytes().filter(|c| *c == b'0' || *c == b'1').map(|c| c - b'0').collect()
+    }
+
+    fn assert_p(p: f64, expected: f64) {
+        assert!((p - expected).abs() < 1e-6, "{} != {}", p, expected);
+    }
+
+    // 100 bits of the binary expansion of e used by the SP 800-22 examples
+    const EPSILON: &str = "1100100100001111110110101010001000100001011010001100001000110100110001001100011001100010100010111000";
+
+    #[test]
+    fn test_examples() {
+        let e = parse(EPSILON);
+        assert_p(frequency(&e), 0.109599);
+        assert_p(block_frequency(&e, 10), 0.706438);
+        assert_p(runs(&e), 0.500798);
+        // the spec's worked example predates the revised threshold; N1 = 48 here
+        assert_p(dft(&e), 0.646355);
+        assert_p(approximate_entropy(&e, 2), 0.235301);
+        let (forward, backward) = cumulative_sums(&e);
+        assert_p(forward, 0.219194);
+        assert_p(backward, 0.114866);
+
+        assert_p(longest_run(&parse("11001100000101010110110001001100111000000000001001001101010100010001001111010110100000001101011111001100111001101101100010110010")), 0.180609);
+        let (p1, p2) = serial(&parse("0011011101"), 3);
+        assert_p(p1, 0.808792);
+        assert_p(p2, 0.670320);
+        assert_p(approximate_entropy(&parse("0100110101"), 3), 0.261961);
+    }
+
+    #[test]
+    fn test_rank() {
+        assert!((rank_probability(32, 32) - 0.2888).abs() < 1e-4);
+        assert!((rank_probability(31, 32) - 0.5776).abs() < 1e-4);
+        let mut identity: Vec<u32> = (0..32).map(|i| 1 << i).collect();
+        assert_eq!(gf2_rank(&mut identity),32);
+        let mut rows = vec![0b101, 0b011, 0b110];
+        assert_eq!(gf2_rank(&mut rows),2);
+    }
+
+    #[test]
+    fn test_fourier() {
+        // Bluestein agrees with the naive transform
+        let x: Vec<Complex> = parse(EPSILON).iter().map(|b| Complex(*b as f64, 0.0)).collect();
+        let y = fourier(&x);
+        for (k, yk) in y.iter().enumerate() {
+            let mut sum = Complex(0.0, 0.0);
+            for (j, xj) in x.iter().enumerate() {
+                sum = sum.add(xj.mul(Complex::expi(-2.0 * PI * (j * k) as f64 / x.len() as f64)));
+            }
+            assert!(sum.sub(*yk).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn test_generators() {
+        let mut rng = Rng::from_seed([1;32]);
+        for r in run_source(125000, |b| rng.fill(b)) {
+            assert!(r.passed(), "{}: {:?}", r.name, r.p_values);
+        }
+
+        let mut drbg = HmacDrbg::instantiate(&[2;32], &[3;16], b"sts").unwrap();
+        for r in run_source(125000, |b| drbg.generate(b, &[]).unwrap()) {
+            assert!(r.passed(), "{}: {:?}", r.name, r.p_values);
+        }
+    }
+
+    #[test]
+    fn test_bad_generators() {
+        let counter: Vec<u8> = (0..125000u32).map(|i| i as u8).collect();
+        assert!(run(&counter).iter().any(|r| !r.passed()));
+
+        // unbiased but with a stuck top bit in every byte
+        let mut rng = Rng::from_seed([4;32]);
+        let stuck: Vec<u8> = rng.generate_bytes(125000).iter().map(|x| x & 0x7f).collect();
+        let results = run(&stuck);
+        assert!(!results[0].passed());
+    }
+}
@@ -0,0 +1,305 @@
+// Unbiased random helpers built on Rng::generate_range
+use super::Rng;
+use std::fmt;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+pub const ALPHANUMERIC: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789";
+/// RFC 4648 base64url alphabet
+pub const URL_SAFE: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+
+const LOWERCASE: &[u8] = b"abcdefghijklmnopqrstuvwxyz";
+const UPPERCASE: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ";
+const DIGITS: &[u8] = b"0123456789";
+const SYMBOLS: &[u8] = b"!\"#$%&'()*+,-./:;<=>?@[\\]^_`{|}~";
+
+/// Uniform index in `[0, len)`.
+fn index(rng: &mut Rng, len: usize) -> usize {
+    rng.generate_range(0, len as u64) as usize
+}
+
+/// `len` characters drawn uniformly from an ASCII `alphabet`.
+pub fn token(rng: &mut Rng, len: usize, alphabet: &[u8]) -> String {
+    assert!(!alphabet.is_empty() && alphabet.is_ascii(), "alphabet must be non-empty ASCII");
+    (0..len).map(|_| alphabet[index(rng, alphabet.len())] as char).collect()
+}
+
+pub fn alphanumeric_token(rng: &mut Rng, len: usize) -> String {
+    token(rng, len, ALPHANUMERIC)
+}
+
+pub fn url_safe_token(rng: &mut Rng, len: usize) -> String {
+    token(rng, len, URL_SAFE)
+}
+
+/// `words` words chosen uniformly, with repetition, from `wordlist`.
+pub fn passphrase(rng: &mut Rng, wordlist: &[&str], words: usize, separator: &str) -> String {
+    assert!(!wordlist.is_empty(), "empty wordlist");
+    (0..words).map(|_| wordlist[index(rng, wordlist.len())]).collect::<Vec<_>>().join(separator)
+}
+
+#[derive(Debug,Clone,Copy,PartialEq,Eq)]
+pub enum CharClass {
+    Lowercase,
+    Uppercase,
+    Digit,
+    Symbol,
+}
+
+/// Password length and per-class requirements.
+///
+/// By default every class is allowed and at least one character of each is required.
+#[derive(Debug,Clone)]
+pub struct PasswordPolicy {
+    length: usize,
+    // (characters, minimum count), empty characters disable the class
+    classes: [(Vec<u8>, usize);4],
+}
+
+impl PasswordPolicy {
+    pub fn new(length: usize) -> PasswordPolicy {
+        PasswordPolicy {
+            length,
+            classes: [(LOWERCASE.to_vec(), 1), (UPPERCASE.to_vec(), 1), (DIGITS.to_vec(), 1), (SYMBOLS.to_vec(), 1)],
+        }
+    }
+
+    fn class(&mut self, class: CharClass) -> &mut (Vec<u8>, usize) {
+        &mut self.classes[class as usize]
+    }
+
+    /// Requires at least `count` characters of `class`.
+    pub fn with_minimum(mut self, class: CharClass, count: usize) -> Self {
+        self.class(class).1 = count;
+        self
+    }
+
+    /// Excludes `class` entirely.
+    pub fn without(mut self, class: CharClass) -> Self {
+        *self.class(class) = (Vec::new(), 0);
+        self
+    }
+
+    /// Replaces the symbol set, e.g. with the symbols a site accepts.
+    pub fn with_symbols(mut self, symbols: &str) -> Self {
+        assert!(symbols.is_ascii(), "symbols must be ASCII");
+        self.class(CharClass::Symbol).0 = symbols.as_bytes().to_vec();
+        self
+    }
+
+    pub fn generate(&self, rng: &mut Rng) -> Result<String,&'static str> {
+        let all: Vec<u8> = self.classes.iter().flat_map(|(chars, _)| chars.iter().cloned()).collect();
+        if all.is_empty() {
+            return Err("No character class is allowed");
+        }
+        if self.classes.iter().any(|(chars, min)| chars.is_empty() && *min > 0) {
+            return Err("Required class has no characters");
+        }
+        if self.classes.iter().map(|(_, min)| min).sum::<usize>() > self.length {
+            return Err("Class minimums exceed the password length");
+        }
+
+        let mut password = Vec::with_capacity(self.length);
+        for (chars, min) in self.classes.iter() {
+            for _ in 0..*min {
+                password.push(chars[index(rng, chars.len())]);
+            }
+        }
+        while password.len() < self.length {
+            password.push(all[index(rng, all.len())]);
+        }
+        shuffle(rng, &mut password);
+
+        Ok(String::from_utf8(password).unwrap())
+    }
+}
+
+/// RFC 9562 UUID
+#[derive(Debug,Clone,Copy,PartialEq,Eq,Hash)]
+pub struct Uuid([u8;16]);
+
+impl Uuid {
+    pub fn as_bytes(&self) -> &[u8;16] {
+        &self.0
+    }
+
+    pub fn version(&self) -> u8 {
+        self.0[6] >> 4
+    }
+
+    fn with_version(mut bytes: [u8;16], version: u8) -> Uuid {
+        bytes[6] = bytes[6] & 0x0f | version << 4;
+        bytes[8] = bytes[8] & 0x3f | 0x80;
+        Uuid(bytes)
+    }
+}
+
+impl fmt::Display for Uuid {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (i, b) in self.0.iter().enumerate() {
+            if i == 4 || i == 6 || i == 8 || i == 10 {
+                write!(f, "-")?;
+            }
+            write!(f, "{:02x}", b)?;
+        }
+        Ok(())
+    }
+}
+
+/// Random UUID (version 4)
+pub fn uuid_v4(rng: &mut Rng) -> Uuid {
+    let mut bytes = [0;16];
+    rng.fill(&mut bytes);
+    Uuid::with_version(bytes, 4)
+}
+
+/// Time-ordered UUID (version 7) for the current time
+pub fn uuid_v7(rng: &mut Rng) -> Uuid {
+    let millis = SystemTime::now().duration_since(UNIX_EPOCH).expect("clock before 1970").as_millis();
+    uuid_v7_at(rng, millis as u64)
+}
+
+/// Version 7 UUID with the given Unix timestamp in milliseconds (48 bits).
+pub fn uuid_v7_at(rng: &mut Rng, unix_millis: u64) -> Uuid {
+    assert!(unix_millis < 1 << 48, "timestamp out of range");
+    let mut bytes = [0;16];
+    bytes[..6].copy_from_slice(&unix_millis.to_be_bytes()[2..]);
+    rng.fill(&mut bytes[6..]);
+    Uuid::with_version(bytes, 7)
+}
+
+/// Fisher-Yates shuffle
+pub fn shuffle<T>(rng: &mut Rng, items: &mut [T]) {
+    for i in (1..items.len()).rev() {
+        let j = index(rng, i + 1);
+        items.swap(i, j);
+    }
+}
+
+pub fn choose<'a, T>(rng: &mut Rng, items: &'a [T]) -> Option<&'a T> {
+    if items.is_empty() {
+        None
+    } else {
+        Some(&items[index(rng, items.len())])
+    }
+}
+
+/// `k` distinct elements in random order, without replacement.
+pub fn sample<T: Clone>(rng: &mut Rng, items: &[T], k: usize) -> Vec<T> {
+    assert!(k <= items.len(), "sample larger than population");
+
+    // partial Fisher-Yates over indices
+    let mut indices: Vec<usize> = (0..items.len()).collect();
+    for i in 0..k {
+        let j = i + index(rng, items.len() - i);
+        indices.swap(i, j);
+    }
+    indices[..k].iter().map(|i| items[*i].clone()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tokens() {
+        let mut rng = Rng::from_seed([1;32]);
+        let t = alphanumeric_token(&mut rng, 32);
+        assert_eq!(t.len(),32);
+        assert!(t.bytes().all(|c| ALPHANUMERIC.contains(&c)));
+        assert!(url_safe_token(&mut rng, 64).bytes().all(|c| URL_SAFE.contains(&c)));
+
+        // 3 symbols: no value may be favoured, as `byte % 3` would
+        let mut counts = [0;3];
+        for c in token(&mut rng, 30000, b"abc").bytes() {
+            counts[(c - b'a') as usize] += 1;
+        }
+        assert!(counts.iter().all(|c| (9600..10400).contains(c)));
+
+        let words = ["correct", "horse", "battery", "staple"];
+        let p = passphrase(&mut rng, &words, 6, "-");
+        assert_eq!(p.split('-').count(),6);
+        assert!(p.split('-').all(|w| words.contains(&w)));
+    }
+
+    #[test]
+    fn test_password() {
+        let mut rng = Rng::from_seed([2;32]);
+        let policy = PasswordPolicy::new(16).with_minimum(CharClass::Digit, 4).with_symbols("!@#");
+        for _ in 0..100 {
+            let p = policy.generate(&mut rng).unwrap();
+            assert_eq!(p.len(),16);
+            assert!(p.bytes().filter(|c| c.is_ascii_digit()).count() >= 4);
+            assert!(p.bytes().any(|c| c.is_ascii_lowercase()));
+            assert!(p.bytes().any(|c| c.is_ascii_uppercase()));
+            assert!(p.bytes().any(|c| b"!@#".contains(&c)));
+            assert!(p.bytes().all(|c| c.is_ascii_alphanumeric() || b"!@#".contains(&c)));
+        }
+
+        let p = PasswordPolicy::new(8).without(CharClass::Symbol).without(CharClass::Uppercase).generate(&mut rng).unwrap();
+        assert!(p.bytes().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit()));
+
+        assert!(PasswordPolicy::new(3).generate(&mut rng).is_err());
+        let empty = PasswordPolicy::new(8).without(CharClass::Lowercase).without(CharClass::Uppercase)
+            .without(CharClass::Digit).without(CharClass::Symbol);
+        assert!(empty.generate(&mut rng).is_err());
+        assert!(PasswordPolicy::new(8).with_symbols("").generate(&mut rng).is_err());
+    }
+
+    #[test]
+    fn test_uuid() {
+        let mut rng = Rng::from_seed([3;32]);
+        let u = uuid_v4(&mut rng);
+        assert_eq!(u.version(),4);
+        assert_eq!(u.as_bytes()[8] & 0xc0,0x80);
+        let s = u.to_string();
+        assert_eq!(s.len(),36);
+        assert_eq!(s.as_bytes()[14],b'4');
+        assert_ne!(uuid_v4(&mut rng),u);
+
+        // RFC 9562 appendix A.6 timestamp
+        let u = uuid_v7_at(&mut rng, 0x017F22E279B0);
+        assert_eq!(u.version(),7);
+        assert_eq!(u.as_bytes()[8] & 0xc0,0x80);
+        assert!(u.to_string().starts_with("017f22e2-79b0-7"));
+        assert!(uuid_v7(&mut rng).as_bytes()[..6] > u.as_bytes()[..6]);
+    }
+
+    #[test]
+    fn test_shuffle() {
+        let mut rng = Rng::from_seed([4;32]);
+        // all 6 permutations of 3 elements are equally likely
+        let mut counts = std::collections::HashMap::new();
+        for _ in 0..60000 {
+            let mut v = [0, 1, 2];
+            shuffle(&mut rng, &mut v);
+            *counts.entry(v).or_insert(0) += 1;
+        }
+        assert_eq!(counts.len(),6);
+        assert!(counts.values().all(|c| (9600..10400).contains(c)));
+
+        let mut empty: [u8;0] = [];
+        shuffle(&mut rng, &mut empty);
+    }
+
+    #[test]
+    fn test_sample() {
+        let mut rng = Rng::from_seed([5;32]);
+        let items: Vec<u32> = (0..100).collect();
+        let mut s = sample(&mut rng, &items, 10);
+        assert_eq!(s.len(),10);
+        s.sort();
+        s.dedup();
+        assert_eq!(s.len(),10);
+        assert_eq!(sample(&mut rng, &items, 100).len(),100);
+
+        // each element appears in a 1-of-4 sample a quarter of the time
+        let mut counts = [0;4];
+        for _ in 0..40000 {
+            counts[sample(&mut rng, &[0, 1, 2, 3], 1)[0]] += 1;
+        }
+        assert!(counts.iter().all(|c| (9600..10400).contains(c)));
+
+        assert!(choose(&mut rng, &items).is_some());
+        assert_eq!(choose::<u8>(&mut rng, &[]),None);
+    }
+}
@@ -39,7 +39,7 @@ pub fn sha256(input: &[u8]) -> [u8;32] {
             if t<16 {
                 w[t] = u32::from_be_bytes(m_block[4*t..4*(t+1)].try_into().unwrap());
             } else {
-                w[t] = (Wrapping(sigma_1(w[t - 2])) + Wrapping(w[t - 7]) + Wrapping(sigma_0(w[t - 15])) + Wrapping(w[t - 16])).0 & 0xffffffff;
+                w[t] = (Wrapping(sigma_1(w[t - 2])) + Wrapping(w[t - 7]) + Wrapping(sigma_0(w[t - 15])) + Wrapping(w[t - 16])).0;
             }
         }
 
@@ -53,26 +53,26 @@ pub fn sha256(input: &[u8]) -> [u8;32] {
         h=hash[7];
 
         for t in 0..64 {
-            let t1 = (Wrapping(h) + Wrapping(sum_1(e)) + Wrapping(ch(e, f, g)) + Wrapping(K[t]) + Wrapping(w[t])).0 & 0xffffffff;
-            let t2 = (Wrapping(sum_0(a)) + Wrapping(maj(a, b, c))).0 & 0xffffffff;
+            let t1 = (Wrapping(h) + Wrapping(sum_1(e)) + Wrapping(ch(e, f, g)) + Wrapping(K[t]) + Wrapping(w[t])).0;
+            let t2 = (Wrapping(sum_0(a)) + Wrapping(maj(a, b, c))).0;
             h = g;
             g = f;
             f = e;
-            e = (Wrapping(d) + Wrapping(t1)).0 & 0xffffffff;
+            e = (Wrapping(d) + Wrapping(t1)).0;
             d = c;
             c = b;
             b = a;
-            a = (Wrapping(t1) + Wrapping(t2)).0 & 0xffffffff;
+            a = (Wrapping(t1) + Wrapping(t2)).0;
         }
 
-        hash[0] = (Wrapping(a) + Wrapping(hash[0])).0 & 0xffffffff;
-        hash[1] = (Wrapping(b) + Wrapping(hash[1])).0 & 0xffffffff;
-        hash[2] = (Wrapping(c) + Wrapping(hash[2])).0 & 0xffffffff;
-        hash[3] = (Wrapping(d) + Wrapping(hash[3])).0 & 0xffffffff;
-        hash[4] = (Wrapping(e) + Wrapping(hash[4])).0 & 0xffffffff;
-        hash[5] = (Wrapping(f) + Wrapping(hash[5])).0 & 0xffffffff;
-        hash[6] = (Wrapping(g) + Wrapping(hash[6])).0 & 0xffffffff;
-        hash[7] = (Wrapping(h) + Wrapping(hash[7])).0 & 0xffffffff;
+        hash[0] = (Wrapping(a) + Wrapping(hash[0])).0;
+        hash[1] = (Wrapping(b) + Wrapping(hash[1])).0;
+        hash[2] = (Wrapping(c) + Wrapping(hash[2])).0;
+        hash[3] = (Wrapping(d) + Wrapping(hash[3])).0;
+        hash[4] = (Wrapping(e) + Wrapping(hash[4])).0;
+        hash[5] = (Wrapping(f) + Wrapping(hash[5])).0;
+        hash[6] = (Wrapping(g) + Wrapping(hash[6])).0;
+        hash[7] = (Wrapping(h) + Wrapping(hash[7])).0;
     }
 
     let mut result:Vec<u8>=Vec::new();
@@ -87,7 +87,7 @@ pub fn sha256(input: &[u8]) -> [u8;32] {
 }
 
 fn rotr(x: u32, n: usize) -> u32 {
-    (x >> n) | (x << (32 - n))
+    x.rotate_right(n as u32)
 }
 
 fn ch(x: u32, y: u32, z: u32) -> u32 {
@@ -116,35 +116,13 @@ fn sigma_1(x: u32) -> u32 {
 
 #[test]
 fn test_sha256() {
+    use crate::utility::codec::hex;
+
     let msg = "abcdefghbcdefghicdefghijdefghijkefghijklfghijklmghijklmnhijklmnoijklmnopjklmnopqklmnopqrlmnopqrsmnopqrstnopqrstu".to_string();
 
-    let md = parse_str2bytes("cf5b16a778af8380036ce59e7b0492370b249b11e8f07a51afac45037afee9d1".to_string());
+    let md = hex::decode("cf5b16a778af8380036ce59e7b0492370b249b11e8f07a51afac45037afee9d1").unwrap();
 
     let digest = sha256(msg.as_bytes());
 
-    for (i,v) in digest.iter().enumerate() {
-        assert_eq!(*v,md[i]);
-    }
-}
-
-#[allow(dead_code)]
-fn parse_str2bytes(s: String) -> Vec<u8> {
-    let mut b = Vec::new();
-
-    for (i,c) in s.as_str().chars().enumerate() {
-        let mut n = c as u8;
-        if n >= 0x61 {
-            n = n - 0x61 + 0x0A;
-        } else {
-            n -= 0x30;
-        }
-
-        if i%2==0 {
-            b.push(n << 4);
-        } else {
-            *b.last_mut().unwrap() += n;
-        }
-    }
-
-    b
+    assert_eq!(digest.to_vec(),md);
 }
\ No newline at end of file
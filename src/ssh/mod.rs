@@ -0,0 +1,419 @@
+// OpenSSH formats for RSA keys: `ssh-rsa` public key lines (RFC 4253 section 6.6),
+// `openssh-key-v1` private keys (OpenSSH PROTOCOL.key) and SHA-256 fingerprints.
+//
+// Private keys are written and read unencrypted or with aes256-ctr keyed by
+// bcrypt_pbkdf, the form `ssh-keygen` produces for a passphrase.
+mod bcrypt;
+
+use crate::aes::{CipherMode, KeyLength, AES};
+use crate::rng::Rng;
+use crate::utility::base64::{self, DecodeError};
+use crate::utility::base64::stream::{EncoderWriter, LineEnding, LineWrap};
+use crate::utility::pem;
+use crate::{rsa, sha256};
+use num::BigUint;
+use std::fmt;
+use std::io::Write;
+use std::str::FromStr;
+
+const KEY_TYPE: &str = "ssh-rsa";
+const MAGIC: &[u8] = b"openssh-key-v1\0";
+const LABEL: &str = "OPENSSH PRIVATE KEY";
+/// bcrypt_pbkdf rounds and salt length of new keys, as `ssh-keygen` chooses them
+const ROUNDS: u32 = 16;
+const SALT_LEN: usize = 16;
+/// Most bcrypt_pbkdf rounds accepted from a key file, bounding the work it can demand
+pub const MAX_ROUNDS: u32 = 1000;
+
+#[derive(Debug,Clone,PartialEq,Eq)]
+pub enum Error {
+    Pem(pem::Error),
+    Base64(DecodeError),
+    /// Truncated or inconsistent key data
+    Malformed(&'static str),
+    /// A key type other than `ssh-rsa`
+    UnsupportedKeyType(String),
+    /// A cipher or KDF other than `aes256-ctr` with `bcrypt`
+    UnsupportedCipher(String),
+    /// The key is encrypted and no passphrase was given
+    PassphraseRequired,
+    /// The passphrase is wrong or the data is corrupt
+    DecryptionFailed,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Pem(e) => write!(f, "{}", e),
+            Error::Base64(e) => write!(f, "{}", e),
+            Error::Malformed(what) => write!(f, "malformed key: {}", what),
+            Error::UnsupportedKeyType(name) => write!(f, "unsupported key type {}", name),
+            Error::UnsupportedCipher(name) => write!(f, "unsupported cipher {}", name),
+            Error::PassphraseRequired => write!(f, "key is encrypted"),
+            Error::DecryptionFailed => write!(f, "decryption failed"),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<pem::Error> for Error {
+    fn from(e: pem::Error) -> Self {
+        Error::Pem(e)
+    }
+}
+
+impl From<DecodeError> for Error {
+    fn from(e: DecodeError) -> Self {
+        Error::Base64(e)
+    }
+}
+
+// SSH wire encoding (RFC 4251 section 5)
+struct Reader<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn bytes(&mut self, n: usize) -> Result<&'a [u8],Error> {
+        if n > self.data.len() {
+            return Err(Error::Malformed("truncated"));
+        }
+        let (head, rest) = self.data.split_at(n);
+        self.data = rest;
+        Ok(head)
+    }
+
+    fn u32(&mut self) -> Result<u32,Error> {
+        let b = self.bytes(4)?;
+        Ok(u32::from_be_bytes([b[0], b[1], b[2], b[3]]))
+    }
+
+    fn string(&mut self) -> Result<&'a [u8],Error> {
+        let n = self.u32()? as usize;
+        self.bytes(n)
+    }
+
+    fn mpint(&mut self) -> Result<BigUint,Error> {
+        let b = self.string()?;
+        if b.first().is_some_and(|&x| x & 0x80 != 0) {
+            return Err(Error::Malformed("negative integer"));
+        }
+        Ok(BigUint::from_bytes_be(b))
+    }
+
+    fn finish(&self) -> Result<(),Error> {
+        if self.data.is_empty() {
+            Ok(())
+        } else {
+            Err(Error::Malformed("trailing data"))
+        }
+    }
+}
+
+fn put_u32(out: &mut Vec<u8>, v: u32) {
+    out.extend_from_slice(&v.to_be_bytes());
+}
+
+fn put_string(out: &mut Vec<u8>, s: &[u8]) {
+    put_u32(out, s.len() as u32);
+    out.extend_from_slice(s);
+}
+
+fn put_mpint(out: &mut Vec<u8>, v: &BigUint) {
+    let mut b = if v.bits() == 0 {Vec::new()} else {v.to_bytes_be()};
+    if b.first().is_some_and(|&x| x & 0x80 != 0) {
+        b.insert(0, 0);
+    }
+    put_string(out, &b);
+}
+
+fn read_public(blob: &[u8]) -> Result<rsa::PublicKey,Error> {
+    let mut r = Reader {data: blob};
+    let key_type = r.string()?;
+    if key_type != KEY_TYPE.as_bytes() {
+        return Err(Error::UnsupportedKeyType(String::from_utf8_lossy(key_type).into_owned()));
+    }
+    let e = r.mpint()?;
+    let n = r.mpint()?;
+    r.finish()?;
+    Ok(rsa::PublicKey::new(n, e))
+}
+
+/// An RSA public key and its comment, as in `.pub` and `authorized_keys` files.
+pub struct PublicKey {
+    pub key: rsa::PublicKey,
+    pub comment: String,
+}
+
+impl PublicKey {
+    pub fn new(key: rsa::PublicKey, comment: &str) -> PublicKey {
+        PublicKey {key, comment: comment.to_string()}
+    }
+
+    /// The `ssh-rsa` wire encoding
+    pub fn to_blob(&self) -> Vec<u8> {
+        let mut blob = Vec::new();
+        put_string(&mut blob, KEY_TYPE.as_bytes());
+        put_mpint(&mut blob, self.key.e());
+        put_mpint(&mut blob, self.key.n());
+        blob
+    }
+
+    /// `SHA256:` and the unpadded base64 digest of the blob, as `ssh-keygen -l` prints it
+    pub fn fingerprint(&self) -> String {
+        format!("SHA256:{}", base64::STANDARD_NO_PAD.encode(&sha256::sha256(&self.to_blob())))
+    }
+}
+
+impl fmt::Display for PublicKey {
+    /// `ssh-rsa AAAA... comment`
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} {}", KEY_TYPE, base64::STANDARD.encode(&self.to_blob()))?;
+        if !self.comment.is_empty() {
+            write!(f, " {}", self.comment)?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for PublicKey {
+    type Err = Error;
+
+    /// Parses one key line; the comment is the rest of the line after the key.
+    fn from_str(line: &str) -> Result<PublicKey,Error> {
+        let line = line.trim();
+        let mut fields = line.splitn(2, char::is_whitespace);
+        let key_type = fields.next().unwrap_or("");
+        if key_type != KEY_TYPE {
+            return Err(Error::UnsupportedKeyType(key_type.to_string()));
+        }
+        let rest = fields.next().unwrap_or("").trim_start();
+        let (data, comment) = rest.split_at(rest.find(char::is_whitespace).unwrap_or(rest.len()));
+        let key = read_public(&base64::STANDARD.decode(data)?)?;
+        Ok(PublicKey::new(key, comment.trim()))
+    }
+}
+
+/// An RSA private key and its comment in the `openssh-key-v1` format.
+pub struct PrivateKey {
+    pub key: rsa::PrivateKey,
+    pub comment: String,
+}
+
+// bcrypt_pbkdf output split into the aes256-ctr key and counter
+fn derive_key(passphrase: &[u8], salt: &[u8], rounds: u32) -> (Vec<u8>, [u8;16]) {
+    let mut key = bcrypt::bcrypt_pbkdf(passphrase, salt, rounds, 48);
+    let mut iv = [0u8;16];
+    iv.copy_from_slice(&key[32..]);
+    key.truncate(32);
+    (key, iv)
+}
+
+impl PrivateKey {
+    pub fn new(key: rsa::PrivateKey, comment: &str) -> PrivateKey {
+        PrivateKey {key, comment: comment.to_string()}
+    }
+
+    pub fn public_key(&self) -> PublicKey {
+        PublicKey::new(rsa::PublicKey::from_private_key(&self.key), &self.comment)
+    }
+
+    /// `OPENSSH PRIVATE KEY` text, encrypted with aes256-ctr and bcrypt_pbkdf when
+    /// `passphrase` is given. `rng` supplies the salt and check value.
+    pub fn to_openssh(&self, passphrase: Option<&[u8]>, rng: &mut Rng) -> String {
+        let checkint = rng.generate_range(0, u32::MAX as u64) as u32;
+        let kdf = passphrase.map(|p| (p, rng.generate_bytes(SALT_LEN), ROUNDS));
+        self.encode(kdf.as_ref().map(|(p, salt, rounds)| (*p, salt.as_slice(), *rounds)), checkint)
+    }
+
+    fn encode(&self, kdf: Option<(&[u8], &[u8], u32)>, checkint: u32) -> String {
+        let public = self.public_key();
+        let mut private = Vec::new();
+        put_u32(&mut private, checkint);
+        put_u32(&mut private, checkint);
+        put_string(&mut private, KEY_TYPE.as_bytes());
+        let (_, _, iqmp) = self.key.crt_params();
+        for v in [public.key.n(), public.key.e(), self.key.d(), &iqmp, self.key.p(), self.key.q()].iter() {
+            put_mpint(&mut private, v);
+        }
+        put_string(&mut private, self.comment.as_bytes());
+        let block_size = if kdf.is_some() {16} else {8};
+        for i in 1..=(block_size - private.len() % block_size) % block_size {
+            private.push(i as u8);
+        }
+
+        let mut data = MAGIC.to_vec();
+        match kdf {
+            Some((passphrase, salt, rounds)) => {
+                let (key, iv) = derive_key(passphrase, salt, rounds);
+                private = AES::new(KeyLength::KL256, CipherMode::CTR).encrypt(&key, &iv, &private).unwrap();
+                put_string(&mut data, b"aes256-ctr");
+                put_string(&mut data, b"bcrypt");
+                let mut options = Vec::new();
+                put_string(&mut options, salt);
+                put_u32(&mut options, rounds);
+                put_string(&mut data, &options);
+            },
+            None => {
+                put_string(&mut data, b"none");
+                put_string(&mut data, b"none");
+                put_string(&mut data, b"");
+            },
+        }
+        put_u32(&mut data, 1);
+        put_string(&mut data, &public.to_blob());
+        put_string(&mut data, &private);
+
+        // ssh-keygen wraps at 70 columns
+        let mut encoder = EncoderWriter::new(Vec::new(), base64::STANDARD).with_wrap(LineWrap::new(70, LineEnding::LF));
+        encoder.write_all(&data).unwrap();
+        let body = String::from_utf8(encoder.finish().unwrap()).unwrap();
+        format!("-----BEGIN {}-----\n{}-----END {}-----\n", LABEL, body, LABEL)
+    }
+
+    /// Reads an `OPENSSH PRIVATE KEY` block; `passphrase` is needed only if it is encrypted.
+    pub fn from_openssh(text: &str, passphrase: Option<&[u8]>) -> Result<PrivateKey,Error> {
+        let block = pem::parse_label(text, LABEL)?;
+        let data = block.data.strip_prefix(MAGIC).ok_or(Error::Malformed("not an openssh-key-v1 key"))?;
+        let mut r = Reader {data};
+        let cipher = r.string()?;
+        let kdf = r.string()?;
+        let mut options = Reader {data: r.string()?};
+        if r.u32()? != 1 {
+            return Err(Error::Malformed("expected one key"));
+        }
+        let public = read_public(r.string()?)?;
+        let mut private = r.string()?.to_vec();
+        r.finish()?;
+
+        let encrypted = match (cipher, kdf) {
+            (b"none", b"none") => false,
+            (b"aes256-ctr", b"bcrypt") => true,
+            _ => return Err(Error::UnsupportedCipher(format!("{}/{}",
+                String::from_utf8_lossy(cipher), String::from_utf8_lossy(kdf)))),
+        };
+        if encrypted {
+            let passphrase = passphrase.ok_or(Error::PassphraseRequired)?;
+            let salt = options.string()?;
+            let rounds = options.u32()?;
+            options.finish()?;
+            if rounds == 0 || private.is_empty() || private.len() % 16 != 0 {
+                return Err(Error::Malformed("invalid encryption parameters"));
+            }
+            if rounds > MAX_ROUNDS {
+                return Err(Error::Malformed("too many bcrypt rounds"));
+            }
+            let (key, iv) = derive_key(passphrase, salt, rounds);
+            private = AES::new(KeyLength::KL256, CipherMode::CTR).decrypt(&key, &iv, &private).unwrap();
+        }
+
+        let mut r = Reader {data: &private};
+        if r.u32()? != r.u32()? {
+            return Err(if encrypted {Error::DecryptionFailed} else {Error::Malformed("check values differ")});
+        }
+        if r.string()? != KEY_TYPE.as_bytes() {
+            return Err(Error::Malformed("key types differ"));
+        }
+        let (n, e, d, _iqmp, p, q) = (r.mpint()?, r.mpint()?, r.mpint()?, r.mpint()?, r.mpint()?, r.mpint()?);
+        let comment = String::from_utf8(r.string()?.to_vec()).map_err(|_| Error::Malformed("comment is not UTF-8"))?;
+        if r.data.iter().enumerate().any(|(i, &b)| b as usize != i + 1) {
+            return Err(Error::Malformed("invalid padding"));
+        }
+        if n != *public.n() || e != *public.e() {
+            return Err(Error::Malformed("public and private keys differ"));
+        }
+        let key = rsa::PrivateKey::from_components(&public, &d, &p, &q).ok_or(Error::Malformed("inconsistent RSA key"))?;
+        Ok(PrivateKey::new(key, &comment))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // `ssh-keygen -t rsa -b 1024 -C deploy@crusto`, then `-p` with "secret passphrase"
+    const PUBLIC: &str = include_str!("testdata/id_rsa.pub");
+    const PRIVATE: &str = include_str!("testdata/id_rsa");
+    const ENCRYPTED: &str = include_str!("testdata/id_rsa.enc");
+
+    fn check_value(text: &str) -> u32 {
+        let data = pem::parse_label(text, LABEL).unwrap().data;
+        let mut r = Reader {data: &data[MAGIC.len()..]};
+        for _ in 0..3 {
+            r.string().unwrap();
+        }
+        r.u32().unwrap();
+        r.string().unwrap();
+        let private = r.string().unwrap();
+        u32::from_be_bytes([private[0], private[1], private[2], private[3]])
+    }
+
+    #[test]
+    fn test_public_key() {
+        let key: PublicKey = PUBLIC.parse().unwrap();
+        assert_eq!(key.comment,"deploy@crusto");
+        assert_eq!(key.key.n().bits(),1024);
+        assert_eq!(format!("{}\n", key),PUBLIC);
+        // `ssh-keygen -lf id_rsa.pub`
+        assert_eq!(key.fingerprint(),"SHA256:Nws1Fcj+T27jPuXxk1F5BHHEH7tmJcPkd/kCWJri/kk");
+
+        let bare: PublicKey = PUBLIC.trim_end_matches("deploy@crusto\n").parse().unwrap();
+        assert!(bare.comment.is_empty());
+        assert_eq!(bare.to_string(),PUBLIC.trim_end_matches(" deploy@crusto\n"));
+        let spaced: PublicKey = PUBLIC.replace("deploy@crusto", "two  words").parse().unwrap();
+        assert_eq!(spaced.comment,"two  words");
+
+        assert_eq!(PUBLIC.replace("ssh-rsa", "ssh-ed25519").parse::<PublicKey>().err(),
+            Some(Error::UnsupportedKeyType("ssh-ed25519".to_string())));
+        assert!(matches!("ssh-rsa AAAA!".parse::<PublicKey>().err(), Some(Error::Base64(_))));
+        assert_eq!("ssh-rsa AAAAB3NzaC1yc2E=".parse::<PublicKey>().err(),Some(Error::Malformed("truncated")));
+    }
+
+    #[test]
+    fn test_private_key() {
+        let key = PrivateKey::from_openssh(PRIVATE, None).unwrap();
+        assert_eq!(key.comment,"deploy@crusto");
+        assert_eq!(key.public_key().to_string(),PUBLIC.trim_end());
+        assert_eq!(key.encode(None, check_value(PRIVATE)),PRIVATE);
+
+        assert_eq!(PrivateKey::from_openssh(ENCRYPTED, None).err(),Some(Error::PassphraseRequired));
+        assert_eq!(PrivateKey::from_openssh(ENCRYPTED, Some(b"wrong")).err(),Some(Error::DecryptionFailed));
+        let decrypted = PrivateKey::from_openssh(ENCRYPTED, Some(b"secret passphrase")).unwrap();
+        assert_eq!(decrypted.key.to_der(),key.key.to_der());
+        assert_eq!(decrypted.comment,key.comment);
+
+        // a fresh salt and check value each time
+        let mut rng = Rng::from_seed([3;32]);
+        let a = key.to_openssh(Some(b"pw"), &mut rng);
+        assert_ne!(a,key.to_openssh(Some(b"pw"), &mut rng));
+        assert_eq!(PrivateKey::from_openssh(&a, Some(b"pw")).unwrap().key.to_der(),key.key.to_der());
+        let plain = key.to_openssh(None, &mut rng);
+        assert_eq!(PrivateKey::from_openssh(&plain, Some(b"ignored")).unwrap().key.to_der(),key.key.to_der());
+    }
+
+    #[test]
+    fn test_private_key_invalid() {
+        let mut data = pem::parse_label(PRIVATE, LABEL).unwrap().data;
+        let last = data.len() - 1;
+        data[last] ^= 0xff;
+        let text = pem::encode(&pem::Block::new(LABEL, data));
+        assert_eq!(PrivateKey::from_openssh(&text, None).err(),Some(Error::Malformed("invalid padding")));
+
+        let mut data = pem::parse_label(PRIVATE, LABEL).unwrap().data;
+        let i = MAGIC.len() + 8 + 4;
+        assert_eq!(&data[i..i + 4],b"none");
+        data[i..i + 4].copy_from_slice(b"scry");
+        let text = pem::encode(&pem::Block::new(LABEL, data));
+        assert_eq!(PrivateKey::from_openssh(&text, None).err(),Some(Error::UnsupportedCipher("none/scry".to_string())));
+        assert!(matches!(PrivateKey::from_openssh(PUBLIC, None).err(), Some(Error::Pem(_))));
+
+        // the rounds follow the cipher, the KDF name and the 16-byte salt
+        let mut data = pem::parse_label(ENCRYPTED, LABEL).unwrap().data;
+        let i = MAGIC.len() + 14 + 10 + 4 + 20;
+        assert_eq!(&data[i..i + 4],ROUNDS.to_be_bytes());
+        data[i..i + 4].copy_from_slice(&(MAX_ROUNDS + 1).to_be_bytes());
+        let text = pem::encode(&pem::Block::new(LABEL, data));
+        assert_eq!(PrivateKey::from_openssh(&text, Some(b"secret passphrase")).err(),Some(Error::Malformed("too many bcrypt rounds")));
+    }
+}
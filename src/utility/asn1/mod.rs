@@ -0,0 +1,707 @@
+// ASN.1 Distinguished Encoding Rules (X.690)
+//
+// `Reader` walks a DER buffer one TLV at a time and `Writer` builds one, so a
+// structure is parsed or emitted by code that follows its ASN.1 definition.
+mod oid;
+mod time;
+
+pub use self::oid::Oid;
+pub use self::time::Time;
+
+use num::BigUint;
+use std::fmt;
+
+/// Maximum nesting of constructed values a `Reader` accepts by default
+pub const DEFAULT_MAX_DEPTH: usize = 32;
+/// Maximum content length a `Reader` accepts by default
+pub const DEFAULT_MAX_LENGTH: usize = 1 << 24;
+
+/// Identifier octet; only low tag numbers (below 31) are supported.
+#[derive(Debug,Clone,Copy,PartialEq,Eq,PartialOrd,Ord,Hash)]
+pub struct Tag(pub u8);
+
+impl Tag {
+    pub const BOOLEAN: Tag = Tag(0x01);
+    pub const INTEGER: Tag = Tag(0x02);
+    pub const BIT_STRING: Tag = Tag(0x03);
+    pub const OCTET_STRING: Tag = Tag(0x04);
+    pub const NULL: Tag = Tag(0x05);
+    pub const OID: Tag = Tag(0x06);
+    pub const UTF8_STRING: Tag = Tag(0x0c);
+    pub const PRINTABLE_STRING: Tag = Tag(0x13);
+    pub const IA5_STRING: Tag = Tag(0x16);
+    pub const UTC_TIME: Tag = Tag(0x17);
+    pub const GENERALIZED_TIME: Tag = Tag(0x18);
+    pub const SEQUENCE: Tag = Tag(0x30);
+    pub const SET: Tag = Tag(0x31);
+
+    /// Constructed context-specific tag `[n]`, as used for EXPLICIT tagging
+    pub const fn context(n: u8) -> Tag {
+        assert!(n < 31);
+        Tag(0xa0 | n)
+    }
+
+    /// Primitive context-specific tag `[n]`, as used for IMPLICIT tagging of primitives
+    pub const fn context_primitive(n: u8) -> Tag {
+        assert!(n < 31);
+        Tag(0x80 | n)
+    }
+
+    pub fn is_constructed(&self) -> bool {
+        self.0 & 0x20 != 0
+    }
+}
+
+#[derive(Debug,Clone,PartialEq,Eq)]
+pub enum Error {
+    /// The input ends inside a value
+    Truncated,
+    /// Indefinite or non-minimal length
+    InvalidLength,
+    /// A length over the reader's limit
+    TooLong(usize),
+    /// Nesting over the reader's limit
+    TooDeep,
+    UnsupportedTag(u8),
+    UnexpectedTag { expected: Tag, found: Tag },
+    /// Valid BER that DER forbids
+    NonCanonical(&'static str),
+    InvalidValue(&'static str),
+    /// Bytes left after the last expected value
+    TrailingData,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Truncated => write!(f, "truncated DER value"),
+            Error::InvalidLength => write!(f, "invalid DER length"),
+            Error::TooLong(len) => write!(f, "length {} exceeds the limit", len),
+            Error::TooDeep => write!(f, "nesting exceeds the limit"),
+            Error::UnsupportedTag(t) => write!(f, "unsupported tag 0x{:02x}", t),
+            Error::UnexpectedTag {expected, found} => write!(f, "expected tag 0x{:02x}, found 0x{:02x}", expected.0, found.0),
+            Error::NonCanonical(s) => write!(f, "non-canonical encoding: {}", s),
+            Error::InvalidValue(s) => write!(f, "{}", s),
+            Error::TrailingData => write!(f, "trailing data"),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+/// One encoded value: its tag, content octets and the whole encoding.
+#[derive(Debug,Clone,Copy,PartialEq,Eq)]
+pub struct Tlv<'a> {
+    pub tag: Tag,
+    pub content: &'a [u8],
+    pub raw: &'a [u8],
+}
+
+/// BIT STRING contents; `unused_bits` low bits of the last byte are padding.
+#[derive(Debug,Clone,Copy,PartialEq,Eq)]
+pub struct BitString<'a> {
+    pub data: &'a [u8],
+    pub unused_bits: u8,
+}
+
+impl<'a> BitString<'a> {
+    /// The octets of a string whose length is a multiple of 8, such as a key or signature.
+    pub fn bytes(&self) -> Result<&'a [u8],Error> {
+        match self.unused_bits {
+            0 => Ok(self.data),
+            _ => Err(Error::InvalidValue("BIT STRING is not octet aligned")),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.data.len() * 8 - self.unused_bits as usize
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Bit `i`, counting from the most significant bit of the first byte as in named bit lists.
+    pub fn bit(&self, i: usize) -> bool {
+        i < self.len() && self.data[i / 8] >> (7 - i % 8) & 1 == 1
+    }
+}
+
+fn is_printable(c: u8) -> bool {
+    c.is_ascii_alphanumeric() || b" '()+,-./:=?".contains(&c)
+}
+
+/// Parses `data` with `f`, which must consume all of it.
+pub fn decode<'a, T, F>(data: &'a [u8], f: F) -> Result<T,Error>
+where F: FnOnce(&mut Reader<'a>) -> Result<T,Error> {
+    let mut r = Reader::new(data);
+    let value = f(&mut r)?;
+    r.finish()?;
+    Ok(value)
+}
+
+/// Collects what `f` writes.
+pub fn encode<F: FnOnce(&mut Writer)>(f: F) -> Vec<u8> {
+    let mut w = Writer::new();
+    f(&mut w);
+    w.into_bytes()
+}
+
+/// Cursor over a sequence of DER values.
+#[derive(Debug,Clone)]
+pub struct Reader<'a> {
+    data: &'a [u8],
+    depth: usize,
+    max_depth: usize,
+    max_length: usize,
+}
+
+impl<'a> Reader<'a> {
+    pub fn new(data: &'a [u8]) -> Reader<'a> {
+        Reader {data, depth: 0, max_depth: DEFAULT_MAX_DEPTH, max_length: DEFAULT_MAX_LENGTH}
+    }
+
+    pub fn with_max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = max_depth;
+        self
+    }
+
+    pub fn with_max_length(mut self, max_length: usize) -> Self {
+        self.max_length = max_length;
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    /// Fails unless everything has been read.
+    pub fn finish(&self) -> Result<(),Error> {
+        if self.data.is_empty() {Ok(())} else {Err(Error::TrailingData)}
+    }
+
+    pub fn peek_tag(&self) -> Option<Tag> {
+        self.data.first().map(|t| Tag(*t))
+    }
+
+    pub fn read_tlv(&mut self) -> Result<Tlv<'a>,Error> {
+        let data = self.data;
+        if data.len() < 2 {
+            return Err(Error::Truncated);
+        }
+        let tag = data[0];
+        if tag & 0x1f == 0x1f {
+            return Err(Error::UnsupportedTag(tag));
+        }
+
+        let first = data[1] as usize;
+        let (len, start) = if first < 0x80 {
+            (first, 2)
+        } else {
+            let n = first & 0x7f;
+            if n == 0 {
+                return Err(Error::InvalidLength);
+            }
+            if n > std::mem::size_of::<usize>() {
+                return Err(Error::TooLong(usize::MAX));
+            }
+            if data.len() < 2 + n {
+                return Err(Error::Truncated);
+            }
+            if data[2] == 0 {
+                return Err(Error::InvalidLength);
+            }
+            let len = data[2..2 + n].iter().fold(0usize, |acc, b| acc << 8 | *b as usize);
+            if len < 0x80 {
+                return Err(Error::InvalidLength);
+            }
+            (len, 2 + n)
+        };
+        if len > self.max_length {
+            return Err(Error::TooLong(len));
+        }
+        if data.len() - start < len {
+            return Err(Error::Truncated);
+        }
+
+        self.data = &data[start + len..];
+        Ok(Tlv {tag: Tag(tag), content: &data[start..start + len], raw: &data[..start + len]})
+    }
+
+    /// Content octets of the next value, which must have tag `tag`.
+    pub fn read(&mut self, tag: Tag) -> Result<&'a [u8],Error> {
+        match self.peek_tag() {
+            Some(found) if found != tag => Err(Error::UnexpectedTag {expected: tag, found}),
+            _ => Ok(self.read_tlv()?.content),
+        }
+    }
+
+    /// Content octets of the next value if it has tag `tag`.
+    pub fn read_optional(&mut self, tag: Tag) -> Result<Option<&'a [u8]>,Error> {
+        if self.peek_tag() == Some(tag) {
+            self.read(tag).map(Some)
+        } else {
+            Ok(None)
+        }
+    }
+
+    fn nested(&self, data: &'a [u8]) -> Result<Reader<'a>,Error> {
+        if self.depth >= self.max_depth {
+            return Err(Error::TooDeep);
+        }
+        Ok(Reader {data, depth: self.depth + 1, ..*self})
+    }
+
+    /// Reader over the content of a constructed value with tag `tag`.
+    pub fn read_constructed(&mut self, tag: Tag) -> Result<Reader<'a>,Error> {
+        assert!(tag.is_constructed());
+        let content = self.read(tag)?;
+        self.nested(content)
+    }
+
+    pub fn read_sequence(&mut self) -> Result<Reader<'a>,Error> {
+        self.read_constructed(Tag::SEQUENCE)
+    }
+
+    /// Reader over a SET, whose elements must be in ascending order of their encodings.
+    pub fn read_set(&mut self) -> Result<Reader<'a>,Error> {
+        let r = self.read_constructed(Tag::SET)?;
+        let mut elements = r.clone();
+        let mut prev: Option<&[u8]> = None;
+        while !elements.is_empty() {
+            let raw = elements.read_tlv()?.raw;
+            if prev.is_some_and(|p| p > raw) {
+                return Err(Error::NonCanonical("SET elements out of order"));
+            }
+            prev = Some(raw);
+        }
+        Ok(r)
+    }
+
+    /// Reader over an EXPLICIT `[n]` value.
+    pub fn read_context(&mut self, n: u8) -> Result<Reader<'a>,Error> {
+        self.read_constructed(Tag::context(n))
+    }
+
+    pub fn read_optional_context(&mut self, n: u8) -> Result<Option<Reader<'a>>,Error> {
+        if self.peek_tag() == Some(Tag::context(n)) {
+            self.read_context(n).map(Some)
+        } else {
+            Ok(None)
+        }
+    }
+
+    pub fn read_bool(&mut self) -> Result<bool,Error> {
+        match self.read(Tag::BOOLEAN)? {
+            [0x00] => Ok(false),
+            [0xff] => Ok(true),
+            [_] => Err(Error::NonCanonical("BOOLEAN must be 0x00 or 0xff")),
+            _ => Err(Error::InvalidValue("BOOLEAN must be one byte")),
+        }
+    }
+
+    fn integer_content(&mut self, tag: Tag) -> Result<&'a [u8],Error> {
+        let content = self.read(tag)?;
+        match content {
+            [] => Err(Error::InvalidValue("empty INTEGER")),
+            [0x00, b, ..] if b & 0x80 == 0 => Err(Error::NonCanonical("INTEGER with leading zero")),
+            [0xff, b, ..] if b & 0x80 != 0 => Err(Error::NonCanonical("INTEGER with leading ones")),
+            [b, ..] if b & 0x80 != 0 => Err(Error::InvalidValue("negative INTEGER")),
+            _ => Ok(content),
+        }
+    }
+
+    /// A non-negative INTEGER.
+    pub fn read_integer(&mut self) -> Result<BigUint,Error> {
+        self.integer_content(Tag::INTEGER).map(BigUint::from_bytes_be)
+    }
+
+    /// A non-negative INTEGER with an implicit tag, such as `[1] IMPLICIT INTEGER`.
+    pub fn read_integer_tagged(&mut self, tag: Tag) -> Result<BigUint,Error> {
+        self.integer_content(tag).map(BigUint::from_bytes_be)
+    }
+
+    /// A non-negative INTEGER that fits in a `u64`.
+    pub fn read_u64(&mut self) -> Result<u64,Error> {
+        let content = self.integer_content(Tag::INTEGER)?;
+        let content = if content[0] == 0 {&content[1..]} else {content};
+        if content.len() > 8 {
+            return Err(Error::InvalidValue("INTEGER too large"));
+        }
+        Ok(content.iter().fold(0, |acc, b| acc << 8 | *b as u64))
+    }
+
+    pub fn read_bit_string(&mut self) -> Result<BitString<'a>,Error> {
+        let content = self.read(Tag::BIT_STRING)?;
+        let (unused_bits, data) = match content.split_first() {
+            Some((u, data)) => (*u, data),
+            None => return Err(Error::InvalidValue("empty BIT STRING")),
+        };
+        if unused_bits > 7 || (data.is_empty() && unused_bits != 0) {
+            return Err(Error::InvalidValue("invalid BIT STRING padding"));
+        }
+        if data.last().is_some_and(|b| b & ((1 << unused_bits) - 1) != 0) {
+            return Err(Error::NonCanonical("BIT STRING padding bits must be zero"));
+        }
+        Ok(BitString {data, unused_bits})
+    }
+
+    pub fn read_octet_string(&mut self) -> Result<&'a [u8],Error> {
+        self.read(Tag::OCTET_STRING)
+    }
+
+    pub fn read_null(&mut self) -> Result<(),Error> {
+        match self.read(Tag::NULL)? {
+            [] => Ok(()),
+            _ => Err(Error::InvalidValue("NULL with content")),
+        }
+    }
+
+    pub fn read_oid(&mut self) -> Result<Oid,Error> {
+        Oid::from_der(self.read(Tag::OID)?)
+    }
+
+    pub fn read_utf8_string(&mut self) -> Result<&'a str,Error> {
+        std::str::from_utf8(self.read(Tag::UTF8_STRING)?).map_err(|_| Error::InvalidValue("invalid UTF8String"))
+    }
+
+    pub fn read_printable_string(&mut self) -> Result<&'a str,Error> {
+        let content = self.read(Tag::PRINTABLE_STRING)?;
+        if !content.iter().all(|c| is_printable(*c)) {
+            return Err(Error::InvalidValue("invalid PrintableString"));
+        }
+        Ok(std::str::from_utf8(content).unwrap())
+    }
+
+    pub fn read_ia5_string(&mut self) -> Result<&'a str,Error> {
+        let content = self.read(Tag::IA5_STRING)?;
+        if !content.is_ascii() {
+            return Err(Error::InvalidValue("invalid IA5String"));
+        }
+        Ok(std::str::from_utf8(content).unwrap())
+    }
+
+    /// A UTF8String, PrintableString or IA5String.
+    pub fn read_string(&mut self) -> Result<&'a str,Error> {
+        match self.peek_tag() {
+            Some(Tag::PRINTABLE_STRING) => self.read_printable_string(),
+            Some(Tag::IA5_STRING) => self.read_ia5_string(),
+            _ => self.read_utf8_string(),
+        }
+    }
+
+    pub fn read_utc_time(&mut self) -> Result<Time,Error> {
+        Time::from_utc_time(self.read(Tag::UTC_TIME)?)
+    }
+
+    pub fn read_generalized_time(&mut self) -> Result<Time,Error> {
+        Time::from_generalized_time(self.read(Tag::GENERALIZED_TIME)?)
+    }
+
+    /// A UTCTime or GeneralizedTime.
+    pub fn read_time(&mut self) -> Result<Time,Error> {
+        match self.peek_tag() {
+            Some(Tag::GENERALIZED_TIME) => self.read_generalized_time(),
+            _ => self.read_utc_time(),
+        }
+    }
+}
+
+/// Builds a DER encoding; constructed values are written by nested closures.
+#[derive(Debug,Clone,Default)]
+pub struct Writer {
+    buf: Vec<u8>,
+}
+
+impl Writer {
+    pub fn new() -> Writer {
+        Writer {buf: Vec::new()}
+    }
+
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.buf
+    }
+
+    /// Appends an already encoded value.
+    pub fn write_raw(&mut self, der: &[u8]) {
+        self.buf.extend_from_slice(der);
+    }
+
+    pub fn write_tlv(&mut self, tag: Tag, content: &[u8]) {
+        self.buf.push(tag.0);
+        let len = content.len();
+        if len < 0x80 {
+            self.buf.push(len as u8);
+        } else {
+            let bytes: Vec<u8> = len.to_be_bytes().iter().cloned().skip_while(|b| *b == 0).collect();
+            self.buf.push(0x80 | bytes.len() as u8);
+            self.buf.extend(bytes);
+        }
+        self.buf.extend_from_slice(content);
+    }
+
+    pub fn write_constructed<F: FnOnce(&mut Writer)>(&mut self, tag: Tag, f: F) {
+        assert!(tag.is_constructed());
+        let content = encode(f);
+        self.write_tlv(tag, &content);
+    }
+
+    pub fn write_sequence<F: FnOnce(&mut Writer)>(&mut self, f: F) {
+        self.write_constructed(Tag::SEQUENCE, f);
+    }
+
+    /// Writes a SET, sorting the elements written by `f` into DER order.
+    pub fn write_set<F: FnOnce(&mut Writer)>(&mut self, f: F) {
+        let content = encode(f);
+        let mut r = Reader::new(&content).with_max_length(usize::MAX);
+        let mut elements = Vec::new();
+        while !r.is_empty() {
+            elements.push(r.read_tlv().unwrap().raw);
+        }
+        elements.sort();
+        self.write_tlv(Tag::SET, &elements.concat());
+    }
+
+    /// Writes an EXPLICIT `[n]` value.
+    pub fn write_context<F: FnOnce(&mut Writer)>(&mut self, n: u8, f: F) {
+        self.write_constructed(Tag::context(n), f);
+    }
+
+    pub fn write_bool(&mut self, value: bool) {
+        self.write_tlv(Tag::BOOLEAN, &[if value {0xff} else {0x00}]);
+    }
+
+    pub fn write_integer(&mut self, value: &BigUint) {
+        self.write_integer_tagged(Tag::INTEGER, value);
+    }
+
+    pub fn write_integer_tagged(&mut self, tag: Tag, value: &BigUint) {
+        let mut bytes = value.to_bytes_be();
+        if bytes[0] & 0x80 != 0 {
+            bytes.insert(0, 0);
+        }
+        self.write_tlv(tag, &bytes);
+    }
+
+    pub fn write_u64(&mut self, value: u64) {
+        self.write_integer(&BigUint::from(value));
+    }
+
+    pub fn write_bit_string(&mut self, bits: BitString) {
+        assert!(bits.unused_bits < 8 && (!bits.data.is_empty() || bits.unused_bits == 0));
+        let mut content = vec![bits.unused_bits];
+        content.extend_from_slice(bits.data);
+        if let Some(last) = content.last_mut().filter(|_| !bits.data.is_empty()) {
+            *last &= !((1 << bits.unused_bits) - 1);
+        }
+        self.write_tlv(Tag::BIT_STRING, &content);
+    }
+
+    /// Writes octets as a BIT STRING with no unused bits.
+    pub fn write_bit_string_bytes(&mut self, data: &[u8]) {
+        self.write_bit_string(BitString {data, unused_bits: 0});
+    }
+
+    pub fn write_octet_string(&mut self, data: &[u8]) {
+        self.write_tlv(Tag::OCTET_STRING, data);
+    }
+
+    pub fn write_null(&mut self) {
+        self.write_tlv(Tag::NULL, &[]);
+    }
+
+    pub fn write_oid(&mut self, oid: &Oid) {
+        self.write_tlv(Tag::OID, &oid.to_der());
+    }
+
+    pub fn write_utf8_string(&mut self, s: &str) {
+        self.write_tlv(Tag::UTF8_STRING, s.as_bytes());
+    }
+
+    /// Panics if `s` has characters outside the PrintableString set.
+    pub fn write_printable_string(&mut self, s: &str) {
+        assert!(s.bytes().all(is_printable), "invalid PrintableString");
+        self.write_tlv(Tag::PRINTABLE_STRING, s.as_bytes());
+    }
+
+    /// Panics if `s` is not ASCII.
+    pub fn write_ia5_string(&mut self, s: &str) {
+        assert!(s.is_ascii(), "invalid IA5String");
+        self.write_tlv(Tag::IA5_STRING, s.as_bytes());
+    }
+
+    pub fn write_utc_time(&mut self, t: &Time) {
+        self.write_tlv(Tag::UTC_TIME, t.to_utc_time().as_bytes());
+    }
+
+    pub fn write_generalized_time(&mut self, t: &Time) {
+        self.write_tlv(Tag::GENERALIZED_TIME, t.to_generalized_time().as_bytes());
+    }
+
+    /// UTCTime through 2049 and GeneralizedTime after, as RFC 5280 requires.
+    pub fn write_time(&mut self, t: &Time) {
+        if t.fits_utc_time() {
+            self.write_utc_time(t);
+        } else {
+            self.write_generalized_time(t);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utility::codec::hex;
+
+    fn der(s: &str) -> Vec<u8> {
+        hex::decode(&s.replace(' ', "")).unwrap()
+    }
+
+    #[test]
+    fn test_primitives() {
+        let data = encode(|w| {
+            w.write_bool(true);
+            w.write_u64(0);
+            w.write_u64(127);
+            w.write_u64(128);
+            w.write_u64(256);
+            w.write_null();
+            w.write_octet_string(b"\x01\x02");
+            w.write_bit_string(BitString {data: &[0x6e, 0x5d, 0xc0], unused_bits: 6});
+            w.write_utf8_string("\u{e9}");
+            w.write_printable_string("Test User 1");
+            w.write_ia5_string("test1@rsa.com");
+        });
+        assert_eq!(data,der("0101ff 020100 02017f 0202 0080 02020100 0500 04020102 0304066e5dc0 0c02c3a9 \
+            130b5465737420557365722031 160d7465737431407273612e636f6d"));
+
+        let mut r = Reader::new(&data);
+        assert!(r.read_bool().unwrap());
+        assert_eq!([r.read_u64().unwrap(), r.read_u64().unwrap(), r.read_u64().unwrap()],[0, 127, 128]);
+        assert_eq!(r.read_integer().unwrap(),BigUint::from(256u32));
+        r.read_null().unwrap();
+        assert_eq!(r.read_octet_string().unwrap(),[1, 2]);
+        let bits = r.read_bit_string().unwrap();
+        assert_eq!((bits.len(), bits.bit(1), bits.bit(3), bits.bit(17), bits.bit(18)),(18, true, false, true, false));
+        assert!(bits.bytes().is_err());
+        assert_eq!(r.read_string().unwrap(),"\u{e9}");
+        assert_eq!(r.read_string().unwrap(),"Test User 1");
+        assert_eq!(r.read_string().unwrap(),"test1@rsa.com");
+        r.finish().unwrap();
+    }
+
+    #[test]
+    fn test_big_integer() {
+        let n = BigUint::parse_bytes(b"c9fb501dfb760c8b4395dcaffeb0849b92d5fcb7dc309e58ca3d0cf9397d13d1\
+            ed8e452d0927e9fd909d397be1bf44f64036b31ba52966ff0a9649dbc51fa7a3", 16).unwrap();
+        let data = encode(|w| w.write_integer(&n));
+        assert_eq!(&data[..4],[0x02, 0x41, 0x00, 0xc9]);
+        assert_eq!(decode(&data, |r| r.read_integer()).unwrap(),n);
+        assert!(decode(&data, |r| r.read_u64()).is_err());
+        assert_eq!(decode(&der("0209 00ffffffffffffffff"), |r| r.read_u64()).unwrap(),u64::MAX);
+
+        assert_eq!(decode(&der("0202 0001"), |r| r.read_u64()),Err(Error::NonCanonical("INTEGER with leading zero")));
+        assert_eq!(decode(&der("0202 ff80"), |r| r.read_u64()),Err(Error::NonCanonical("INTEGER with leading ones")));
+        assert_eq!(decode(&der("0201 80"), |r| r.read_u64()),Err(Error::InvalidValue("negative INTEGER")));
+        assert_eq!(decode(&der("0200"), |r| r.read_u64()),Err(Error::InvalidValue("empty INTEGER")));
+    }
+
+    #[test]
+    fn test_constructed() {
+        let oid = Oid::new(&[1, 2, 840, 113549, 1, 1, 11]);
+        let t = Time::new(2024, 2, 29, 12, 0, 0).unwrap();
+        let data = encode(|w| w.write_sequence(|w| {
+            w.write_oid(&oid);
+            w.write_set(|w| {
+                w.write_u64(2);
+                w.write_u64(1);
+            });
+            w.write_context(0, |w| w.write_time(&t));
+            w.write_time(&Time::new(2050, 1, 1, 0, 0, 0).unwrap());
+            w.write_integer_tagged(Tag::context_primitive(1), &BigUint::from(5u8));
+        }));
+        assert_eq!(data,der("3038 06092a864886f70d01010b 3106 020101 020102 a00f 170d3234303232393132303030305a \
+            180f32303530303130313030303030305a 810105"));
+
+        decode(&data, |r| {
+            let mut seq = r.read_sequence()?;
+            assert_eq!(seq.read_oid()?,oid);
+            let mut set = seq.read_set()?;
+            assert_eq!([set.read_u64()?, set.read_u64()?],[1, 2]);
+            set.finish()?;
+            assert!(seq.read_optional_context(1)?.is_none());
+            let mut ctx = seq.read_optional_context(0)?.unwrap();
+            assert_eq!(ctx.read_time()?,t);
+            assert_eq!(seq.read_time()?.year(),2050);
+            assert_eq!(seq.read_optional(Tag::BOOLEAN)?,None);
+            assert_eq!(seq.read_integer_tagged(Tag::context_primitive(1))?,BigUint::from(5u8));
+            seq.finish()
+        }).unwrap();
+
+        let raw = Reader::new(&data).read_sequence().unwrap().read_tlv().unwrap().raw;
+        assert_eq!(raw,&data[2..13]);
+        assert_eq!(decode(&der("3106 020102 020101"), |r| r.read_set().map(|_| ())),
+            Err(Error::NonCanonical("SET elements out of order")));
+        assert_eq!(decode(&der("3000 00"), |r| r.read_sequence().map(|_| ())),Err(Error::TrailingData));
+        assert_eq!(decode(&der("020101"), |r| r.read_sequence().map(|_| ())),
+            Err(Error::UnexpectedTag {expected: Tag::SEQUENCE, found: Tag::INTEGER}));
+    }
+
+    #[test]
+    fn test_lengths() {
+        let long = vec![0xab;300];
+        let data = encode(|w| w.write_octet_string(&long));
+        assert_eq!(&data[..4],[0x04, 0x82, 0x01, 0x2c]);
+        assert_eq!(decode(&data, |r| r.read_octet_string()).unwrap(),&long[..]);
+        assert_eq!(Reader::new(&data).with_max_length(299).read_octet_string(),Err(Error::TooLong(300)));
+        assert_eq!(decode(&data[..100], |r| r.read_octet_string()),Err(Error::Truncated));
+
+        // indefinite, long form for a short length, leading zero length byte, huge length
+        for s in ["0480 0000", "0481 05 0102030405", "0482 0080 00", "0489 010000000000000000"].iter() {
+            assert!(decode(&der(s), |r| r.read_octet_string()).is_err(), "{}", s);
+        }
+        assert_eq!(Reader::new(&der("0480")).read_tlv(),Err(Error::InvalidLength));
+        assert_eq!(Reader::new(&der("1f2200")).read_tlv(),Err(Error::UnsupportedTag(0x1f)));
+        assert_eq!(Reader::new(&der("04")).read_tlv(),Err(Error::Truncated));
+    }
+
+    #[test]
+    fn test_depth() {
+        let mut data = encode(|w| w.write_null());
+        for _ in 0..40 {
+            data = encode(|w| w.write_tlv(Tag::SEQUENCE, &data));
+        }
+        fn walk(r: &mut Reader) -> Result<(),Error> {
+            if r.peek_tag() == Some(Tag::NULL) {
+                return r.read_null();
+            }
+            let mut inner = r.read_sequence()?;
+            walk(&mut inner)?;
+            inner.finish()
+        }
+        assert_eq!(decode(&data, walk),Err(Error::TooDeep));
+        assert!(Reader::new(&data).with_max_depth(40).read_sequence().is_ok());
+        let mut r = Reader::new(&data).with_max_depth(40);
+        assert!(walk(&mut r).is_ok());
+    }
+
+    #[test]
+    fn test_canonical_values() {
+        assert_eq!(decode(&der("010101"), |r| r.read_bool()),Err(Error::NonCanonical("BOOLEAN must be 0x00 or 0xff")));
+        assert!(!decode(&der("010100"), |r| r.read_bool()).unwrap());
+        assert!(decode(&der("0500"), |r| r.read_null()).is_ok());
+        assert!(decode(&der("050100"), |r| r.read_null()).is_err());
+        assert!(decode(&der("030100"), |r| r.read_bit_string()).unwrap().is_empty());
+        assert!(decode(&der("030101"), |r| r.read_bit_string()).is_err());
+        assert!(decode(&der("030208ff"), |r| r.read_bit_string()).is_err());
+        assert_eq!(decode(&der("030201ff"), |r| r.read_bit_string()),Err(Error::NonCanonical("BIT STRING padding bits must be zero")));
+        assert!(decode(&der("1301 2a"), |r| r.read_printable_string()).is_err());
+        assert!(decode(&der("1601 80"), |r| r.read_ia5_string()).is_err());
+        assert!(decode(&der("0c01 ff"), |r| r.read_utf8_string()).is_err());
+        assert!(decode(&der("170d 3234303232393132303030302b"), |r| r.read_time()).is_err());
+
+        // unused bits are cleared on output
+        let data = encode(|w| w.write_bit_string(BitString {data: &[0xff], unused_bits: 4}));
+        assert_eq!(data,[0x03, 0x02, 0x04, 0xf0]);
+    }
+}
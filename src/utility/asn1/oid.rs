@@ -0,0 +1,124 @@
+// OBJECT IDENTIFIER values
+use super::Error;
+use std::fmt;
+use std::str::FromStr;
+
+#[derive(Debug,Clone,PartialEq,Eq,Hash)]
+pub struct Oid(Vec<u64>);
+
+impl Oid {
+    /// Panics unless the first arc is 0, 1 or 2 and, below 2, the second is under 40.
+    pub fn new(arcs: &[u64]) -> Oid {
+        assert!(arcs.len() >= 2 && arcs[0] <= 2 && (arcs[0] == 2 || arcs[1] < 40), "invalid OID");
+        assert!(arcs[0] < 2 || arcs[1] <= u64::MAX - 80, "invalid OID");
+        Oid(arcs.to_vec())
+    }
+
+    pub fn arcs(&self) -> &[u64] {
+        &self.0
+    }
+
+    /// DER content octets
+    pub(crate) fn to_der(&self) -> Vec<u8> {
+        let mut v = Vec::new();
+        let first = self.0[0] * 40 + self.0[1];
+        for arc in std::iter::once(first).chain(self.0[2..].iter().cloned()) {
+            let groups = (64 - (arc | 1).leading_zeros()).div_ceil(7);
+            for i in (0..groups).rev() {
+                let more = if i > 0 {0x80} else {0};
+                v.push(more | (arc >> (7 * i) & 0x7f) as u8);
+            }
+        }
+        v
+    }
+
+    pub(crate) fn from_der(content: &[u8]) -> Result<Oid,Error> {
+        if content.is_empty() || content[content.len() - 1] & 0x80 != 0 {
+            return Err(Error::InvalidValue("truncated OBJECT IDENTIFIER"));
+        }
+        let mut arcs = Vec::new();
+        let mut arc = 0u64;
+        let mut start = true;
+        for b in content.iter() {
+            if start && *b == 0x80 {
+                return Err(Error::NonCanonical("OBJECT IDENTIFIER arc with leading zero"));
+            }
+            if arc >> 57 != 0 {
+                return Err(Error::InvalidValue("OBJECT IDENTIFIER arc too large"));
+            }
+            arc = arc << 7 | (b & 0x7f) as u64;
+            start = b & 0x80 == 0;
+            if start {
+                if arcs.is_empty() {
+                    let x = (arc / 40).min(2);
+                    arcs.push(x);
+                    arcs.push(arc - 40 * x);
+                } else {
+                    arcs.push(arc);
+                }
+                arc = 0;
+            }
+        }
+        Ok(Oid(arcs))
+    }
+}
+
+impl fmt::Display for Oid {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let arcs: Vec<String> = self.0.iter().map(|a| a.to_string()).collect();
+        write!(f, "{}", arcs.join("."))
+    }
+}
+
+impl FromStr for Oid {
+    type Err = Error;
+
+    /// Parses dotted decimal notation, e.g. `1.2.840.113549`.
+    fn from_str(s: &str) -> Result<Oid,Error> {
+        const ERR: Error = Error::InvalidValue("invalid OBJECT IDENTIFIER string");
+        let mut arcs = Vec::new();
+        for part in s.split('.') {
+            if part.is_empty() || !part.bytes().all(|c| c.is_ascii_digit()) || (part.len() > 1 && part.starts_with('0')) {
+                return Err(ERR);
+            }
+            arcs.push(part.parse::<u64>().map_err(|_| ERR)?);
+        }
+        if arcs.len() < 2 || arcs[0] > 2 || (arcs[0] < 2 && arcs[1] >= 40) || (arcs[0] == 2 && arcs[1] > u64::MAX - 80) {
+            return Err(ERR);
+        }
+        Ok(Oid(arcs))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_oid() {
+        let vectors: [(&str, &[u8]);4] = [
+            ("1.2.840.113549.1.1.11", &[0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x01, 0x0b]),
+            ("2.5.4.3", &[0x55, 0x04, 0x03]),
+            ("2.999.3", &[0x88, 0x37, 0x03]),
+            ("0.0", &[0x00]),
+        ];
+        for (s, der) in vectors.iter() {
+            let oid: Oid = s.parse().unwrap();
+            assert_eq!(oid.to_der(),*der);
+            assert_eq!(Oid::from_der(der).unwrap(),oid);
+            assert_eq!(oid.to_string(),*s);
+        }
+        assert_eq!(Oid::new(&[2, 5, 4, 3]),"2.5.4.3".parse().unwrap());
+        assert_eq!(Oid::new(&[1, 2, u64::MAX]).to_string(),format!("1.2.{}", u64::MAX));
+        let big = Oid::new(&[1, 2, u64::MAX]);
+        assert_eq!(Oid::from_der(&big.to_der()).unwrap(),big);
+
+        assert!(Oid::from_der(&[]).is_err());
+        assert!(Oid::from_der(&[0x2a, 0x86]).is_err());
+        assert_eq!(Oid::from_der(&[0x2a, 0x80, 0x01]),Err(Error::NonCanonical("OBJECT IDENTIFIER arc with leading zero")));
+        assert!(Oid::from_der(&[0x2a, 0x82, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x00]).is_err());
+        for s in ["", "1", "3.1", "1.40", "1..2", "1.02", "1.2.x", "1.2."].iter() {
+            assert!(s.parse::<Oid>().is_err(), "{}", s);
+        }
+    }
+}
@@ -0,0 +1,179 @@
+// UTCTime and GeneralizedTime in their DER forms (X.690 11.7, 11.8)
+use super::Error;
+use std::fmt;
+
+/// A UTC date and time to the second. Fields are ordered so that comparison is
+/// chronological.
+#[derive(Debug,Clone,Copy,PartialEq,Eq,PartialOrd,Ord,Hash)]
+pub struct Time {
+    year: u16,
+    month: u8,
+    day: u8,
+    hour: u8,
+    minute: u8,
+    second: u8,
+}
+
+fn days_in_month(year: u16, month: u8) -> u8 {
+    match month {
+        2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+// days since 1970-01-01 of a proleptic Gregorian date
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let y = if month <= 2 {year - 1} else {year};
+    let era = y.div_euclid(400);
+    let yoe = y - era * 400;
+    let doy = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146097 + doe - 719468
+}
+
+fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 {mp + 3} else {mp - 9};
+    let year = yoe + era * 400 + if month <= 2 {1} else {0};
+    (year, month, day)
+}
+
+fn digits(s: &[u8]) -> Result<u16,Error> {
+    s.iter().try_fold(0u16, |acc, c| {
+        if c.is_ascii_digit() {
+            Ok(acc * 10 + (c - b'0') as u16)
+        } else {
+            Err(Error::InvalidValue("invalid time"))
+        }
+    })
+}
+
+impl Time {
+    pub fn new(year: u16, month: u8, day: u8, hour: u8, minute: u8, second: u8) -> Result<Time,Error> {
+        if year > 9999 || month == 0 || month > 12 || day == 0 || day > days_in_month(year, month)
+            || hour > 23 || minute > 59 || second > 59 {
+            return Err(Error::InvalidValue("invalid time"));
+        }
+        Ok(Time {year, month, day, hour, minute, second})
+    }
+
+    /// Panics outside years 0 to 9999.
+    pub fn from_unix(secs: i64) -> Time {
+        let (year, month, day) = civil_from_days(secs.div_euclid(86400));
+        assert!((0..=9999).contains(&year), "time out of range");
+        let t = secs.rem_euclid(86400);
+        Time {
+            year: year as u16, month: month as u8, day: day as u8,
+            hour: (t / 3600) as u8, minute: (t / 60 % 60) as u8, second: (t % 60) as u8,
+        }
+    }
+
+    /// Seconds since 1970-01-01T00:00:00Z, ignoring leap seconds.
+    pub fn unix(&self) -> i64 {
+        let days = days_from_civil(self.year as i64, self.month as i64, self.day as i64);
+        days * 86400 + self.hour as i64 * 3600 + self.minute as i64 * 60 + self.second as i64
+    }
+
+    pub fn year(&self) -> u16 { self.year }
+    pub fn month(&self) -> u8 { self.month }
+    pub fn day(&self) -> u8 { self.day }
+    pub fn hour(&self) -> u8 { self.hour }
+    pub fn minute(&self) -> u8 { self.minute }
+    pub fn second(&self) -> u8 { self.second }
+
+    // YYMMDDHHMMSSZ; two-digit years are 1950 to 2049 as in RFC 5280
+    pub(crate) fn from_utc_time(s: &[u8]) -> Result<Time,Error> {
+        if s.len() != 13 || s[12] != b'Z' {
+            return Err(Error::InvalidValue("UTCTime must be YYMMDDHHMMSSZ"));
+        }
+        let yy = digits(&s[0..2])?;
+        Time::from_fields(if yy >= 50 {1900 + yy} else {2000 + yy}, &s[2..12])
+    }
+
+    // YYYYMMDDHHMMSSZ without fractional seconds
+    pub(crate) fn from_generalized_time(s: &[u8]) -> Result<Time,Error> {
+        if s.len() != 15 || s[14] != b'Z' {
+            return Err(Error::InvalidValue("GeneralizedTime must be YYYYMMDDHHMMSSZ"));
+        }
+        Time::from_fields(digits(&s[0..4])?, &s[4..14])
+    }
+
+    fn from_fields(year: u16, s: &[u8]) -> Result<Time,Error> {
+        let f = |i: usize| digits(&s[i..i + 2]).map(|x| x as u8);
+        Time::new(year, f(0)?, f(2)?, f(4)?, f(6)?, f(8)?)
+    }
+
+    /// Whether the time can be written as UTCTime.
+    pub fn fits_utc_time(&self) -> bool {
+        (1950..2050).contains(&self.year)
+    }
+
+    pub(crate) fn to_utc_time(self) -> String {
+        assert!(self.fits_utc_time(), "year out of UTCTime range");
+        format!("{:02}{:02}{:02}{:02}{:02}{:02}Z", self.year % 100, self.month, self.day, self.hour, self.minute, self.second)
+    }
+
+    pub(crate) fn to_generalized_time(self) -> String {
+        format!("{:04}{:02}{:02}{:02}{:02}{:02}Z", self.year, self.month, self.day, self.hour, self.minute, self.second)
+    }
+}
+
+impl fmt::Display for Time {
+    /// RFC 3339, e.g. `2024-02-29T12:00:00Z`
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z", self.year, self.month, self.day, self.hour, self.minute, self.second)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unix() {
+        let vectors = [
+            (0, "1970-01-01T00:00:00Z"),
+            (951782400, "2000-02-29T00:00:00Z"),
+            (1709208000, "2024-02-29T12:00:00Z"),
+            (2147483647, "2038-01-19T03:14:07Z"),
+            (-1, "1969-12-31T23:59:59Z"),
+            (253402300799, "9999-12-31T23:59:59Z"),
+            (-62167219200, "0000-01-01T00:00:00Z"),
+        ];
+        for (secs, s) in vectors.iter() {
+            let t = Time::from_unix(*secs);
+            assert_eq!(t.to_string(),*s);
+            assert_eq!(t.unix(),*secs);
+        }
+        assert!(Time::from_unix(0) < Time::from_unix(1));
+        assert!(Time::from_unix(86399) < Time::from_unix(86400));
+    }
+
+    #[test]
+    fn test_der_forms() {
+        let t = Time::from_utc_time(b"491231235959Z").unwrap();
+        assert_eq!(t.to_string(),"2049-12-31T23:59:59Z");
+        assert_eq!(t.to_utc_time(),"491231235959Z");
+        assert_eq!(Time::from_utc_time(b"500101000000Z").unwrap().year(),1950);
+        let t = Time::from_generalized_time(b"20500101000000Z").unwrap();
+        assert!(!t.fits_utc_time());
+        assert_eq!(t.to_generalized_time(),"20500101000000Z");
+
+        for s in ["4912312359Z", "491231235959", "491231235959+0000", "491232235959Z", "490229000000Z", "491231240000Z", "4912312359-9Z"].iter() {
+            assert!(Time::from_utc_time(s.as_bytes()).is_err(), "{}", s);
+        }
+        for s in ["20500101000000.5Z", "20500101000000", "205001010000Z", "20000230000000Z"].iter() {
+            assert!(Time::from_generalized_time(s.as_bytes()).is_err(), "{}", s);
+        }
+        assert!(Time::from_generalized_time(b"20000229000000Z").is_ok());
+        assert!(Time::from_generalized_time(b"21000229000000Z").is_err());
+    }
+}
@@ -0,0 +1,399 @@
+// Base64 (RFC 4648) with configurable alphabets and padding
+pub mod stream;
+
+use super::ct;
+use std::fmt;
+
+/// 64 distinct ASCII symbols, in value order.
+#[derive(Debug,Clone,Copy,PartialEq,Eq)]
+pub struct Alphabet {
+    symbols: [u8;64],
+}
+
+impl Alphabet {
+    pub const STANDARD: Alphabet = Alphabet {symbols: *b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/"};
+    /// RFC 4648 section 5, "base64url"
+    pub const URL_SAFE: Alphabet = Alphabet {symbols: *b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_"};
+    pub const BCRYPT: Alphabet = Alphabet {symbols: *b"./ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789"};
+    /// crypt(3) symbol order; the byte grouping is still the RFC 4648 one
+    pub const CRYPT: Alphabet = Alphabet {symbols: *b"./0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz"};
+
+    /// Custom alphabet of 64 distinct printable ASCII symbols other than `=`.
+    pub fn new(symbols: &str) -> Result<Alphabet,&'static str> {
+        let bytes = symbols.as_bytes();
+        if bytes.len() != 64 {
+            return Err("Alphabet must have 64 symbols");
+        }
+        if bytes.iter().any(|c| !c.is_ascii_graphic() || *c == b'=') {
+            return Err("Alphabet symbols must be printable ASCII other than '='");
+        }
+        for (i, c) in bytes.iter().enumerate() {
+            if bytes[i+1..].contains(c) {
+                return Err("Alphabet symbols must be distinct");
+            }
+        }
+
+        let mut alphabet = Alphabet {symbols: [0;64]};
+        alphabet.symbols.copy_from_slice(bytes);
+        Ok(alphabet)
+    }
+
+    // symbol -> value, 0xff for symbols outside the alphabet
+    fn decode_table(&self) -> [u8;256] {
+        let mut table = [0xff;256];
+        for (i, c) in self.symbols.iter().enumerate() {
+            table[*c as usize] = i as u8;
+        }
+        table
+    }
+}
+
+#[derive(Debug,Clone,Copy,PartialEq,Eq)]
+pub enum Padding {
+    /// Encode with `=` padding and require it when decoding
+    Padded,
+    /// Encode without padding and reject it when decoding
+    Unpadded,
+    /// Encode with padding, accept input with or without it
+    Either,
+}
+
+/// Base64 codec for one alphabet and padding mode.
+#[derive(Debug,Clone,Copy,PartialEq,Eq)]
+pub struct Engine {
+    alphabet: Alphabet,
+    padding: Padding,
+    whitespace: bool,
+}
+
+pub const STANDARD: Engine = Engine::new(Alphabet::STANDARD, Padding::Padded);
+pub const STANDARD_NO_PAD: Engine = Engine::new(Alphabet::STANDARD, Padding::Unpadded);
+pub const URL_SAFE: Engine = Engine::new(Alphabet::URL_SAFE, Padding::Padded);
+pub const URL_SAFE_NO_PAD: Engine = Engine::new(Alphabet::URL_SAFE, Padding::Unpadded);
+
+impl Engine {
+    pub const fn new(alphabet: Alphabet, padding: Padding) -> Engine {
+        Engine {alphabet, padding, whitespace: false}
+    }
+
+    /// Skip spaces, tabs and line breaks anywhere in the input when decoding.
+    pub const fn with_whitespace(mut self, allowed: bool) -> Self {
+        self.whitespace = allowed;
+        self
+    }
+
+    pub fn encode(&self, data: &[u8]) -> String {
+        let sym = |x: u32| self.alphabet.symbols[(x & 0x3f) as usize] as char;
+        let mut s = String::with_capacity(data.len().div_ceil(3) * 4);
+
+        for chunk in data.chunks(3) {
+            let mut block = [0;3];
+            block[..chunk.len()].copy_from_slice(chunk);
+            let x = (block[0] as u32) << 16 | (block[1] as u32) << 8 | block[2] as u32;
+
+            for i in 0..=chunk.len() {
+                s.push(sym(x >> (18 - 6 * i)));
+            }
+            if self.padding != Padding::Unpadded {
+                for _ in chunk.len()..3 {
+                    s.push('=');
+                }
+            }
+        }
+
+        s
+    }
+
+    /// Decodes strictly: every byte must be a symbol of the alphabet (or whitespace when
+    /// allowed), padding may only end the input, and the unused bits of the last symbol
+    /// must be zero, so every byte string has exactly one accepted encoding.
+    pub fn decode(&self, data: &str) -> Result<Vec<u8>,DecodeError> {
+        self.decode_bytes(data.as_bytes(), false)
+    }
+
+    /// Same as `decode`, but for secrets: the time taken does not depend on the symbol
+    /// values, only on the length and the positions of padding and whitespace.
+    ///
+    /// Symbols are mapped with branchless arithmetic instead of a table lookup. Invalid
+    /// input is decoded again with `decode` to locate the error.
+    pub fn decode_ct(&self, data: &str) -> Result<Vec<u8>,DecodeError> {
+        self.decode_bytes(data.as_bytes(), true)
+    }
+
+    // value + 1 of symbol `c`, 0 if it is not in the alphabet
+    fn symbol_ct(&self, c: u8, arithmetic: bool) -> i32 {
+        let c = c as i32;
+        let s = &self.alphabet.symbols;
+        if arithmetic {
+            (ct::range_mask(c, 0x41, 0x5a) & (c - 0x41 + 1))
+                | (ct::range_mask(c, 0x61, 0x7a) & (c - 0x61 + 27))
+                | (ct::range_mask(c, 0x30, 0x39) & (c - 0x30 + 53))
+                | (ct::eq_mask(c, s[62] as i32) & 63)
+                | (ct::eq_mask(c, s[63] as i32) & 64)
+        } else {
+            s.iter().enumerate().fold(0, |acc, (i, x)| acc | (ct::eq_mask(c, *x as i32) & (i as i32 + 1)))
+        }
+    }
+
+    fn decode_bytes(&self, data: &[u8], ct: bool) -> Result<Vec<u8>,DecodeError> {
+        let table = self.alphabet.decode_table();
+        // arithmetic mapping covers alphabets sharing the standard first 62 symbols
+        let arithmetic = self.alphabet.symbols[..62] == Alphabet::STANDARD.symbols[..62];
+        let mut bad = 0i32;
+        let mut v = Vec::with_capacity(data.len() / 4 * 3 + 2);
+        let mut x = 0u32;
+        let mut symbols = 0;
+        // offset and byte of the last symbol, offset of the first '='
+        let mut last = (0, 0);
+        let mut pad_start = None;
+        let mut pads = 0;
+
+        for (i, c) in data.iter().cloned().enumerate() {
+            if self.whitespace && matches!(c, b' ' | b'\t' | b'\r' | b'\n') {
+                continue;
+            }
+            if c == b'=' {
+                pad_start.get_or_insert(i);
+                pads += 1;
+                continue;
+            }
+
+            let d = if ct {
+                let d = self.symbol_ct(c, arithmetic);
+                bad |= d - 1;
+                ((d - 1) & 0x3f) as u8
+            } else {
+                table[c as usize]
+            };
+            if d == 0xff || pad_start.is_some() {
+                return Err(DecodeError::InvalidByte(i, c));
+            }
+            x = x << 6 | d as u32;
+            symbols += 1;
+            last = (i, c);
+            if symbols % 4 == 0 {
+                v.extend_from_slice(&x.to_be_bytes()[1..]);
+                x = 0;
+            }
+        }
+
+        if bad < 0 {
+            return self.decode_bytes(data, false);
+        }
+
+        let rem = symbols % 4;
+        if rem == 1 {
+            return Err(DecodeError::InvalidLength(data.len()));
+        }
+        match pad_start {
+            Some(i) if self.padding == Padding::Unpadded || rem == 0 || pads != 4 - rem => {
+                return Err(DecodeError::InvalidPadding(i));
+            },
+            None if self.padding == Padding::Padded && rem != 0 => {
+                return Err(DecodeError::InvalidPadding(data.len()));
+            },
+            _ => (),
+        }
+
+        if rem > 0 {
+            // 2 symbols carry 1 byte and 4 spare bits, 3 symbols carry 2 bytes and 2 spare bits
+            let spare = 6 * rem - 8 * (rem - 1);
+            if x & ((1 << spare) - 1) != 0 {
+                return Err(DecodeError::InvalidLastSymbol(last.0, last.1));
+            }
+            x >>= spare;
+            v.extend_from_slice(&x.to_be_bytes()[5 - rem..]);
+        }
+
+        Ok(v)
+    }
+}
+
+/// Decoding error with the byte offset into the input where it was detected.
+#[derive(Debug,Clone,Copy,PartialEq,Eq)]
+pub enum DecodeError {
+    /// Byte outside the alphabet, or a symbol after padding
+    InvalidByte(usize, u8),
+    /// Symbol count leaves a lone 6-bit group; the offset is the input length
+    InvalidLength(usize),
+    /// Padding missing, unexpected, or of the wrong length
+    InvalidPadding(usize),
+    /// Last symbol has non-zero bits past the end of the data
+    InvalidLastSymbol(usize, u8),
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DecodeError::InvalidByte(i, c) => write!(f, "invalid byte 0x{:02x} at offset {}", c, i),
+            DecodeError::InvalidLength(i) => write!(f, "invalid length {}", i),
+            DecodeError::InvalidPadding(i) => write!(f, "invalid padding at offset {}", i),
+            DecodeError::InvalidLastSymbol(i, c) => write!(f, "non-canonical last symbol {:?} at offset {}", *c as char, i),
+        }
+    }
+}
+
+impl std::error::Error for DecodeError {}
+
+/// Standard alphabet with padding
+pub fn encode(data: &[u8]) -> String {
+    STANDARD.encode(data)
+}
+
+/// Standard alphabet with padding
+pub fn decode(data: &str) -> Result<Vec<u8>,DecodeError> {
+    STANDARD.decode(data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::Rng;
+
+    // RFC 4648 section 10
+    const VECTORS: [(&str, &str);7] = [
+        ("", ""), ("f", "Zg=="), ("fo", "Zm8="), ("foo", "Zm9v"),
+        ("foob", "Zm9vYg=="), ("fooba", "Zm9vYmE="), ("foobar", "Zm9vYmFy"),
+    ];
+
+    #[test]
+    fn test_encode() {
+        for (plain, encoded) in VECTORS.iter() {
+            assert_eq!(encode(plain.as_bytes()),*encoded);
+            assert_eq!(STANDARD_NO_PAD.encode(plain.as_bytes()),encoded.trim_end_matches('='));
+        }
+        assert_eq!(encode("ABCDEFG".as_bytes()),"QUJDREVGRw==");
+    }
+
+    #[test]
+    fn test_decode() {
+        for (plain, encoded) in VECTORS.iter() {
+            assert_eq!(decode(encoded).unwrap(),plain.as_bytes());
+            assert_eq!(STANDARD_NO_PAD.decode(encoded.trim_end_matches('=')).unwrap(),plain.as_bytes());
+        }
+        assert_eq!(decode("QUJDREVGRw==").unwrap(),b"ABCDEFG");
+    }
+
+    #[test]
+    fn test_url_safe() {
+        let data = [0xfb, 0xff, 0xbf, 0xfe];
+        assert_eq!(encode(&data),"+/+//g==");
+        assert_eq!(URL_SAFE.encode(&data),"-_-__g==");
+        assert_eq!(URL_SAFE_NO_PAD.encode(&data),"-_-__g");
+        assert_eq!(URL_SAFE_NO_PAD.decode("-_-__g").unwrap(),data);
+        assert!(URL_SAFE_NO_PAD.decode("+/+//g").is_err());
+        assert!(STANDARD.decode("-_-__g==").is_err());
+    }
+
+    #[test]
+    fn test_padding_modes() {
+        assert!(STANDARD.decode("Zm8").is_err());
+        assert!(STANDARD_NO_PAD.decode("Zm8=").is_err());
+
+        let either = Engine::new(Alphabet::STANDARD, Padding::Either);
+        assert_eq!(either.encode(b"fo"),"Zm8=");
+        assert_eq!(either.decode("Zm8=").unwrap(),b"fo");
+        assert_eq!(either.decode("Zm8").unwrap(),b"fo");
+        assert!(either.decode("Zg=").is_err());
+        assert!(either.decode("Z").is_err());
+    }
+
+    #[test]
+    fn test_custom_alphabets() {
+        let bcrypt = Engine::new(Alphabet::BCRYPT, Padding::Unpadded);
+        // bcrypt salt from the OpenBSD test vectors
+        let salt = bcrypt.decode("CCCCCCCCCCCCCCCCCCCCC.").unwrap();
+        assert_eq!(salt.len(),16);
+        assert_eq!(bcrypt.encode(&salt),"CCCCCCCCCCCCCCCCCCCCC.");
+        assert_eq!(bcrypt.encode(&[0]),"..");
+
+        let crypt = Engine::new(Alphabet::CRYPT, Padding::Unpadded);
+        assert_eq!(crypt.encode(&[0xff, 0xff, 0xff]),"zzzz");
+        assert_eq!(crypt.encode(&[0, 0, 0x01]),".../");
+
+        let reversed: String = Alphabet::STANDARD.symbols.iter().rev().map(|c| *c as char).collect();
+        let custom = Engine::new(Alphabet::new(&reversed).unwrap(), Padding::Padded);
+        assert_eq!(custom.encode(b"foobar"),"mZCQnZ6N");
+        assert_eq!(custom.decode("mZCQnZ6N").unwrap(),b"foobar");
+
+        assert!(Alphabet::new("abc").is_err());
+        assert!(Alphabet::new(&"A".repeat(64)).is_err());
+        assert!(Alphabet::new(&reversed.replace('A', "=")).is_err());
+    }
+
+    #[test]
+    fn test_strict() {
+        use DecodeError::*;
+        assert_eq!(decode("Zm9v\nYmFy"),Err(InvalidByte(4, b'\n')));
+        assert_eq!(decode("Zm9v*mFy"),Err(InvalidByte(4, b'*')));
+        assert_eq!(decode("Zm8=Zm8="),Err(InvalidByte(4, b'Z')));
+        assert_eq!(decode("Zg==Zg=="),Err(InvalidByte(4, b'Z')));
+        assert_eq!(decode("Zg==="),Err(InvalidPadding(2)));
+        assert_eq!(decode("Zm9v===="),Err(InvalidPadding(4)));
+        assert_eq!(decode("Zm9=v"),Err(InvalidByte(4, b'v')));
+        assert_eq!(decode("Zm9vY"),Err(InvalidLength(5)));
+        assert_eq!(decode("Zm9vY==="),Err(InvalidLength(8)));
+        assert_eq!(decode("Zm9vYg"),Err(InvalidPadding(6)));
+        assert_eq!(STANDARD_NO_PAD.decode("Zm9vYg=="),Err(InvalidPadding(6)));
+
+        // non-zero bits after the data
+        assert_eq!(decode("Zh=="),Err(InvalidLastSymbol(1, b'h')));
+        assert_eq!(decode("Zm9="),Err(InvalidLastSymbol(2, b'9')));
+        assert_eq!(decode("Zm8=").unwrap(),b"fo");
+
+        // the old decoder indexed by byte offset, which broke on multi-byte characters
+        assert_eq!(decode("Zé=="),Err(InvalidByte(1, 0xc3)));
+
+        assert_eq!(InvalidByte(3, b'*').to_string(),"invalid byte 0x2a at offset 3");
+    }
+
+    #[test]
+    fn test_whitespace() {
+        let lenient = STANDARD.with_whitespace(true);
+        assert_eq!(lenient.decode("Zm9v\r\nYmFy\n").unwrap(),b"foobar");
+        assert_eq!(lenient.decode(" Zm9v Yg = = ").unwrap(),b"foob");
+        assert_eq!(lenient.decode("Zm9v\n*").err(),Some(DecodeError::InvalidByte(5, b'*')));
+        assert_eq!(lenient.decode("Zm\n9vY\n"),Err(DecodeError::InvalidLength(7)));
+        assert!(STANDARD.decode("Zm9v\r\nYmFy").is_err());
+    }
+
+    #[test]
+    fn test_decode_ct() {
+        let mut rng = Rng::from_seed([1;32]);
+        let engines = [
+            STANDARD, URL_SAFE_NO_PAD, STANDARD.with_whitespace(true),
+            Engine::new(Alphabet::BCRYPT, Padding::Unpadded), Engine::new(Alphabet::CRYPT, Padding::Either),
+        ];
+        for engine in engines.iter() {
+            for len in 0..40 {
+                let data = rng.generate_bytes(len);
+                let encoded = engine.encode(&data);
+                assert_eq!(engine.decode_ct(&encoded).unwrap(),data);
+
+                // corrupt one position
+                if !encoded.is_empty() {
+                    let mut bytes = encoded.into_bytes();
+                    let i = rng.generate_range(0, bytes.len() as u64) as usize;
+                    bytes[i] = b"*=A/_.\n"[rng.generate_range(0, 7) as usize];
+                    let corrupted = String::from_utf8(bytes).unwrap();
+                    assert_eq!(engine.decode_ct(&corrupted),engine.decode(&corrupted));
+                }
+            }
+        }
+        assert_eq!(decode("Zh=="),Err(DecodeError::InvalidLastSymbol(1, b'h')));
+        assert_eq!(STANDARD.decode_ct("Zh=="),Err(DecodeError::InvalidLastSymbol(1, b'h')));
+        assert_eq!(STANDARD.decode_ct("Zm9v*mFy"),Err(DecodeError::InvalidByte(4, b'*')));
+    }
+
+    #[test]
+    #[ignore = "wall-clock timing, run with --ignored on a quiet machine"]
+    fn test_decode_ct_timing() {
+        // all-zero key material versus random symbols of the same length
+        let zeros = STANDARD.encode(&[0;768]);
+        let random = STANDARD.encode(&Rng::from_seed([2;32]).generate_bytes(768));
+        let t = ct::timing::welch_t(zeros.as_bytes(), random.as_bytes(), 20000, |s| {
+            std::hint::black_box(STANDARD.decode_ct(std::str::from_utf8(s).unwrap()).unwrap());
+        });
+        assert!(t.abs() < 10.0, "t = {}", t);
+    }
+}
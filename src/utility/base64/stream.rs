@@ -0,0 +1,357 @@
+// Streaming base64 over std::io with optional line wrapping
+use super::{DecodeError, Engine};
+use std::io::{self, Read, Write};
+
+const CHUNK: usize = 3 * 1024;
+
+#[derive(Debug,Clone,Copy,PartialEq,Eq)]
+pub enum LineEnding {
+    LF,
+    CRLF,
+}
+
+impl LineEnding {
+    fn as_bytes(&self) -> &'static [u8] {
+        match self {
+            LineEnding::LF => b"\n",
+            LineEnding::CRLF => b"\r\n",
+        }
+    }
+}
+
+/// Line width in symbols and the line ending inserted between lines.
+#[derive(Debug,Clone,Copy,PartialEq,Eq)]
+pub struct LineWrap {
+    width: usize,
+    ending: LineEnding,
+}
+
+impl LineWrap {
+    /// RFC 7468
+    pub const PEM: LineWrap = LineWrap {width: 64, ending: LineEnding::LF};
+    /// RFC 2045
+    pub const MIME: LineWrap = LineWrap {width: 76, ending: LineEnding::CRLF};
+
+    pub fn new(width: usize, ending: LineEnding) -> LineWrap {
+        assert!(width > 0, "line width must be positive");
+        LineWrap {width, ending}
+    }
+}
+
+/// Encodes everything written to it into `inner`.
+///
+/// Call `finish` to write the final group and get `inner` back; dropping the encoder
+/// finishes it too but loses any error. With wrapping, every line including the last
+/// ends with the line ending.
+pub struct EncoderWriter<W: Write> {
+    inner: Option<W>,
+    engine: Engine,
+    wrap: Option<LineWrap>,
+    pending: [u8;3],
+    pending_len: usize,
+    column: usize,
+}
+
+impl<W: Write> EncoderWriter<W> {
+    pub fn new(inner: W, engine: Engine) -> EncoderWriter<W> {
+        EncoderWriter {inner: Some(inner), engine, wrap: None, pending: [0;3], pending_len: 0, column: 0}
+    }
+
+    pub fn with_wrap(mut self, wrap: LineWrap) -> Self {
+        self.wrap = Some(wrap);
+        self
+    }
+
+    pub fn finish(mut self) -> io::Result<W> {
+        self.write_final()?;
+        Ok(self.inner.take().unwrap())
+    }
+
+    fn write_final(&mut self) -> io::Result<()> {
+        let tail = self.engine.encode(&self.pending[..self.pending_len]);
+        self.pending_len = 0;
+        self.write_symbols(tail.as_bytes())?;
+
+        if let Some(wrap) = self.wrap {
+            if self.column > 0 {
+                self.inner.as_mut().unwrap().write_all(wrap.ending.as_bytes())?;
+                self.column = 0;
+            }
+        }
+        self.inner.as_mut().unwrap().flush()
+    }
+
+    fn write_symbols(&mut self, mut symbols: &[u8]) -> io::Result<()> {
+        let inner = self.inner.as_mut().unwrap();
+        let wrap = match self.wrap {
+            Some(wrap) => wrap,
+            None => return inner.write_all(symbols),
+        };
+
+        while !symbols.is_empty() {
+            if self.column == wrap.width {
+                inner.write_all(wrap.ending.as_bytes())?;
+                self.column = 0;
+            }
+            let n = symbols.len().min(wrap.width - self.column);
+            inner.write_all(&symbols[..n])?;
+            self.column += n;
+            symbols = &symbols[n..];
+        }
+        Ok(())
+    }
+}
+
+impl<W: Write> Write for EncoderWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let mut consumed = 0;
+
+        if self.pending_len > 0 {
+            consumed = buf.len().min(3 - self.pending_len);
+            self.pending[self.pending_len..self.pending_len + consumed].copy_from_slice(&buf[..consumed]);
+            self.pending_len += consumed;
+            if self.pending_len < 3 {
+                return Ok(consumed);
+            }
+            let group = self.engine.encode(&self.pending);
+            self.pending_len = 0;
+            self.write_symbols(group.as_bytes())?;
+        }
+
+        let rest = &buf[consumed..];
+        let whole = (rest.len() / 3 * 3).min(CHUNK);
+        if whole > 0 {
+            let encoded = self.engine.encode(&rest[..whole]);
+            self.write_symbols(encoded.as_bytes())?;
+            consumed += whole;
+        } else {
+            self.pending[..rest.len()].copy_from_slice(rest);
+            self.pending_len = rest.len();
+            consumed += rest.len();
+        }
+
+        Ok(consumed)
+    }
+
+    /// Flushes `inner`; a partial 3-byte group stays buffered until `finish`.
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.as_mut().unwrap().flush()
+    }
+}
+
+impl<W: Write> Drop for EncoderWriter<W> {
+    fn drop(&mut self) {
+        if self.inner.is_some() {
+            let _ = self.write_final();
+        }
+    }
+}
+
+/// Decodes base64 read from `inner`, skipping line breaks (and spaces and tabs if the
+/// engine allows whitespace).
+///
+/// Decoding is as strict as `Engine::decode`; errors are `io::ErrorKind::InvalidData`
+/// wrapping a `DecodeError` whose offset counts from the start of the stream.
+pub struct DecoderReader<R: Read> {
+    inner: R,
+    engine: Engine,
+    // buffered symbols and their stream offsets
+    symbols: Vec<u8>,
+    offsets: Vec<usize>,
+    consumed: usize,
+    out: Vec<u8>,
+    pos: usize,
+    done: bool,
+}
+
+impl<R: Read> DecoderReader<R> {
+    pub fn new(inner: R, engine: Engine) -> DecoderReader<R> {
+        DecoderReader {
+            inner,
+            engine,
+            symbols: Vec::new(),
+            offsets: Vec::new(),
+            consumed: 0,
+            out: Vec::new(),
+            pos: 0,
+            done: false,
+        }
+    }
+
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+
+    fn fill(&mut self) -> io::Result<()> {
+        let mut chunk = [0;CHUNK];
+        let n = self.inner.read(&mut chunk)?;
+        for (i, c) in chunk[..n].iter().enumerate() {
+            let skip = matches!(*c, b'\r' | b'\n') || self.engine.whitespace && matches!(*c, b' ' | b'\t');
+            if !skip {
+                self.symbols.push(*c);
+                self.offsets.push(self.consumed + i);
+            }
+        }
+        self.consumed += n;
+
+        // the last group may hold padding, so it waits for the end of the stream
+        let ready = if n == 0 {
+            self.done = true;
+            self.symbols.len()
+        } else {
+            let whole = self.symbols.len().saturating_sub(4) / 4 * 4;
+            if self.symbols[..whole].contains(&b'=') {
+                // padding with more input after it: decoding everything reports where
+                self.symbols.len()
+            } else {
+                whole
+            }
+        };
+
+        let decoded = self.engine.decode_bytes(&self.symbols[..ready], false).map_err(|e| {
+            let at = |i: usize| *self.offsets.get(i).unwrap_or(&self.consumed);
+            let e = match e {
+                DecodeError::InvalidByte(i, c) => DecodeError::InvalidByte(at(i), c),
+                DecodeError::InvalidLength(_) => DecodeError::InvalidLength(self.consumed),
+                DecodeError::InvalidPadding(i) => DecodeError::InvalidPadding(at(i)),
+                DecodeError::InvalidLastSymbol(i, c) => DecodeError::InvalidLastSymbol(at(i), c),
+            };
+            io::Error::new(io::ErrorKind::InvalidData, e)
+        })?;
+
+        self.symbols.drain(..ready);
+        self.offsets.drain(..ready);
+        self.out = decoded;
+        self.pos = 0;
+        Ok(())
+    }
+}
+
+impl<R: Read> Read for DecoderReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        while self.pos == self.out.len() {
+            if self.done || buf.is_empty() {
+                return Ok(0);
+            }
+            self.fill()?;
+        }
+
+        let n = buf.len().min(self.out.len() - self.pos);
+        buf[..n].copy_from_slice(&self.out[self.pos..self.pos + n]);
+        self.pos += n;
+        Ok(n)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::{STANDARD, STANDARD_NO_PAD};
+    use crate::rng::Rng;
+
+    // hands out at most `step` bytes per read
+    struct Trickle<'a>(&'a [u8], usize);
+
+    impl Read for Trickle<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            let n = self.0.len().min(self.1).min(buf.len());
+            buf[..n].copy_from_slice(&self.0[..n]);
+            self.0 = &self.0[n..];
+            Ok(n)
+        }
+    }
+
+    fn encode_wrapped(data: &[u8], wrap: Option<LineWrap>, step: usize) -> String {
+        let mut encoder = EncoderWriter::new(Vec::new(), STANDARD);
+        if let Some(wrap) = wrap {
+            encoder = encoder.with_wrap(wrap);
+        }
+        for chunk in data.chunks(step) {
+            encoder.write_all(chunk).unwrap();
+        }
+        String::from_utf8(encoder.finish().unwrap()).unwrap()
+    }
+
+    fn decode_stream(data: &[u8], step: usize) -> io::Result<Vec<u8>> {
+        let mut out = Vec::new();
+        DecoderReader::new(Trickle(data, step), STANDARD).read_to_end(&mut out)?;
+        Ok(out)
+    }
+
+    #[test]
+    fn test_encode() {
+        let data = Rng::from_seed([1;32]).generate_bytes(10000);
+        let plain = STANDARD.encode(&data);
+        for step in [1, 2, 5, 4096, 10000].iter() {
+            assert_eq!(encode_wrapped(&data, None, *step),plain);
+        }
+
+        let pem = encode_wrapped(&data, Some(LineWrap::PEM), 7);
+        assert!(pem.ends_with('\n') && !pem.ends_with("\n\n"));
+        let lines: Vec<&str> = pem.lines().collect();
+        assert!(lines[..lines.len()-1].iter().all(|l| l.len() == 64));
+        assert_eq!(lines.concat(),plain);
+
+        let mime = encode_wrapped(&data, Some(LineWrap::MIME), 100);
+        assert!(mime.ends_with("\r\n"));
+        let lines: Vec<&str> = mime.split_terminator("\r\n").collect();
+        assert!(lines[..lines.len()-1].iter().all(|l| l.len() == 76));
+        assert_eq!(mime.replace("\r\n", ""),plain);
+    }
+
+    #[test]
+    fn test_encode_edges() {
+        assert_eq!(encode_wrapped(b"", Some(LineWrap::PEM), 1),"");
+        // a full last line gets exactly one line ending
+        let data = [0;48];
+        assert_eq!(encode_wrapped(&data, Some(LineWrap::PEM), 48),"A".repeat(64) + "\n");
+        assert_eq!(encode_wrapped(b"fo", Some(LineWrap::new(2, LineEnding::CRLF)), 1),"Zm\r\n8=\r\n");
+
+        let mut out = Vec::new();
+        {
+            let mut encoder = EncoderWriter::new(&mut out, STANDARD_NO_PAD);
+            encoder.write_all(b"foob").unwrap();
+        }
+        assert_eq!(out,b"Zm9vYg");
+    }
+
+    #[test]
+    fn test_decode() {
+        let data = Rng::from_seed([2;32]).generate_bytes(10000);
+        let pem = encode_wrapped(&data, Some(LineWrap::PEM), 10000);
+        let mime = encode_wrapped(&data, Some(LineWrap::MIME), 10000);
+        for step in [1, 3, 77, 4096, 20000].iter() {
+            assert_eq!(decode_stream(pem.as_bytes(), *step).unwrap(),data);
+            assert_eq!(decode_stream(mime.as_bytes(), *step).unwrap(),data);
+        }
+        assert_eq!(decode_stream(b"", 1).unwrap(),b"");
+        assert_eq!(decode_stream(b"Zm9v\nYg==\n", 1).unwrap(),b"foob");
+    }
+
+    #[test]
+    fn test_decode_errors() {
+        let error = |data: &[u8], step| {
+            let e = decode_stream(data, step).unwrap_err();
+            assert_eq!(e.kind(),io::ErrorKind::InvalidData);
+            *e.into_inner().unwrap().downcast::<DecodeError>().unwrap()
+        };
+
+        let mut data = encode_wrapped(&[0;3000], Some(LineWrap::PEM), 3000).into_bytes();
+        data[3500] = b'*';
+        assert_eq!(error(&data, 100),DecodeError::InvalidByte(3500, b'*'));
+
+        // padding in the middle of the stream
+        for step in [1, 4, 4096].iter() {
+            assert_eq!(error(b"Zg==\nZm9vZm9vZm9v\n", *step),DecodeError::InvalidByte(5, b'Z'));
+        }
+        assert_eq!(error(b"Zm9v\nY\n", 1),DecodeError::InvalidLength(7));
+        assert_eq!(error(b"Zm9v\nYg\n", 2),DecodeError::InvalidPadding(8));
+        assert_eq!(error(b"Zm9v\nZh==", 3),DecodeError::InvalidLastSymbol(6, b'h'));
+        assert_eq!(error(b"Zm9v Zm9v", 3),DecodeError::InvalidByte(4, b' '));
+
+        let mut out = Vec::new();
+        let lenient = STANDARD.with_whitespace(true);
+        DecoderReader::new(Trickle(b"Zm 9v\tYg\n==", 1), lenient).read_to_end(&mut out).unwrap();
+        assert_eq!(out,b"foob");
+    }
+}
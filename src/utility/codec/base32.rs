@@ -0,0 +1,155 @@
+// Base32 and Base32hex (RFC 4648 sections 6 and 7)
+use super::Error;
+
+/// Base32 codec for one alphabet, with or without `=` padding.
+///
+/// Decoding is case-sensitive and strict like `base64::Engine::decode`; convert
+/// hand-typed input such as TOTP secrets with `to_ascii_uppercase` first.
+#[derive(Debug,Clone,Copy,PartialEq,Eq)]
+pub struct Engine {
+    alphabet: &'static [u8;32],
+    padding: bool,
+}
+
+pub const STANDARD: Engine = Engine {alphabet: b"ABCDEFGHIJKLMNOPQRSTUVWXYZ234567", padding: true};
+pub const STANDARD_NO_PAD: Engine = Engine {alphabet: b"ABCDEFGHIJKLMNOPQRSTUVWXYZ234567", padding: false};
+/// Extended hex alphabet, which preserves sort order
+pub const HEX: Engine = Engine {alphabet: b"0123456789ABCDEFGHIJKLMNOPQRSTUV", padding: true};
+pub const HEX_NO_PAD: Engine = Engine {alphabet: b"0123456789ABCDEFGHIJKLMNOPQRSTUV", padding: false};
+
+// symbols used by a final group of 1 to 4 bytes
+const TAIL_SYMBOLS: [usize;5] = [0, 2, 4, 5, 7];
+
+impl Engine {
+    pub fn encode(&self, data: &[u8]) -> String {
+        let mut s = String::with_capacity(data.len().div_ceil(5) * 8);
+
+        for chunk in data.chunks(5) {
+            let mut block = [0;8];
+            block[3..3 + chunk.len()].copy_from_slice(chunk);
+            let x = u64::from_be_bytes(block);
+
+            let symbols = if chunk.len() == 5 {8} else {TAIL_SYMBOLS[chunk.len()]};
+            for i in 0..symbols {
+                s.push(self.alphabet[(x >> (35 - 5 * i) & 0x1f) as usize] as char);
+            }
+            if self.padding {
+                for _ in symbols..8 {
+                    s.push('=');
+                }
+            }
+        }
+
+        s
+    }
+
+    pub fn decode(&self, data: &str) -> Result<Vec<u8>,Error> {
+        let mut table = [0xff;256];
+        for (i, c) in self.alphabet.iter().enumerate() {
+            table[*c as usize] = i as u8;
+        }
+
+        let bytes = data.as_bytes();
+        let mut v = Vec::with_capacity(bytes.len() / 8 * 5 + 4);
+        let mut x = 0u64;
+        let mut symbols = 0;
+        let mut last = (0, 0);
+        let mut pad_start = None;
+
+        for (i, c) in bytes.iter().cloned().enumerate() {
+            if c == b'=' {
+                pad_start.get_or_insert(i);
+                continue;
+            }
+            let d = table[c as usize];
+            if d == 0xff || pad_start.is_some() {
+                return Err(Error::InvalidByte(i, c));
+            }
+            x = x << 5 | d as u64;
+            symbols += 1;
+            last = (i, c);
+            if symbols % 8 == 0 {
+                v.extend_from_slice(&x.to_be_bytes()[3..]);
+                x = 0;
+            }
+        }
+
+        let rem = symbols % 8;
+        let tail = match TAIL_SYMBOLS.iter().position(|n| *n == rem) {
+            Some(tail) => tail,
+            None if rem == 0 => 0,
+            None => return Err(Error::InvalidLength(bytes.len())),
+        };
+        match pad_start {
+            Some(i) if !self.padding || rem == 0 || bytes.len() - i != 8 - rem => return Err(Error::InvalidPadding(i)),
+            None if self.padding && rem != 0 => return Err(Error::InvalidPadding(bytes.len())),
+            _ => (),
+        }
+
+        if rem > 0 {
+            let spare = 5 * rem - 8 * tail;
+            if x & ((1 << spare) - 1) != 0 {
+                return Err(Error::InvalidLastSymbol(last.0, last.1));
+            }
+            x >>= spare;
+            v.extend_from_slice(&x.to_be_bytes()[8 - tail..]);
+        }
+
+        Ok(v)
+    }
+}
+
+/// Standard alphabet with padding
+pub fn encode(data: &[u8]) -> String {
+    STANDARD.encode(data)
+}
+
+/// Standard alphabet with padding
+pub fn decode(data: &str) -> Result<Vec<u8>,Error> {
+    STANDARD.decode(data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // RFC 4648 section 10
+    const VECTORS: [(&str, &str, &str);7] = [
+        ("", "", ""),
+        ("f", "MY======", "CO======"),
+        ("fo", "MZXQ====", "CPNG===="),
+        ("foo", "MZXW6===", "CPNMU==="),
+        ("foob", "MZXW6YQ=", "CPNMUOG="),
+        ("fooba", "MZXW6YTB", "CPNMUOJ1"),
+        ("foobar", "MZXW6YTBOI======", "CPNMUOJ1E8======"),
+    ];
+
+    #[test]
+    fn test_vectors() {
+        for (plain, b32, b32hex) in VECTORS.iter() {
+            assert_eq!(encode(plain.as_bytes()),*b32);
+            assert_eq!(HEX.encode(plain.as_bytes()),*b32hex);
+            assert_eq!(decode(b32).unwrap(),plain.as_bytes());
+            assert_eq!(HEX.decode(b32hex).unwrap(),plain.as_bytes());
+            assert_eq!(STANDARD_NO_PAD.encode(plain.as_bytes()),b32.trim_end_matches('='));
+            assert_eq!(STANDARD_NO_PAD.decode(b32.trim_end_matches('=')).unwrap(),plain.as_bytes());
+        }
+
+        // RFC 6238 TOTP secret "12345678901234567890"
+        assert_eq!(STANDARD_NO_PAD.decode("GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ").unwrap(),b"12345678901234567890");
+    }
+
+    #[test]
+    fn test_errors() {
+        assert_eq!(decode("MY"),Err(Error::InvalidPadding(2)));
+        assert_eq!(decode("MY====="),Err(Error::InvalidPadding(2)));
+        assert_eq!(decode("MZXW6=="),Err(Error::InvalidPadding(5)));
+        assert_eq!(STANDARD_NO_PAD.decode("MY======"),Err(Error::InvalidPadding(2)));
+        assert_eq!(decode("M======="),Err(Error::InvalidLength(8)));
+        assert_eq!(decode("MZX====="),Err(Error::InvalidLength(8)));
+        assert_eq!(decode("MY======MY======"),Err(Error::InvalidByte(8, b'M')));
+        assert_eq!(decode("my======"),Err(Error::InvalidByte(0, b'm')));
+        assert_eq!(decode("MZ======"),Err(Error::InvalidLastSymbol(1, b'Z')));
+        assert_eq!(HEX.decode("W0======"),Err(Error::InvalidByte(0, b'W')));
+    }
+}
@@ -0,0 +1,129 @@
+// Base58 with the Bitcoin alphabet, and Base58Check
+use super::Error;
+use crate::sha256::sha256;
+
+const ALPHABET: &[u8;58] = b"123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
+
+/// Leading zero bytes become leading `1`s; the rest is the big-endian number in base 58.
+pub fn encode(data: &[u8]) -> String {
+    let zeros = data.iter().take_while(|x| **x == 0).count();
+
+    // little-endian base 58 digits
+    let mut digits: Vec<u8> = Vec::with_capacity(data.len() * 138 / 100 + 1);
+    for x in data[zeros..].iter() {
+        let mut carry = *x as u32;
+        for d in digits.iter_mut() {
+            carry += (*d as u32) << 8;
+            *d = (carry % 58) as u8;
+            carry /= 58;
+        }
+        while carry > 0 {
+            digits.push((carry % 58) as u8);
+            carry /= 58;
+        }
+    }
+
+    let mut s = "1".repeat(zeros);
+    s.extend(digits.iter().rev().map(|d| ALPHABET[*d as usize] as char));
+    s
+}
+
+pub fn decode(data: &str) -> Result<Vec<u8>,Error> {
+    let mut table = [0xff;256];
+    for (i, c) in ALPHABET.iter().enumerate() {
+        table[*c as usize] = i as u8;
+    }
+
+    let bytes = data.as_bytes();
+    let ones = bytes.iter().take_while(|c| **c == b'1').count();
+
+    // little-endian base 256 digits
+    let mut out: Vec<u8> = Vec::with_capacity(bytes.len() * 733 / 1000 + 1);
+    for (i, c) in bytes.iter().enumerate().skip(ones) {
+        let d = table[*c as usize];
+        if d == 0xff {
+            return Err(Error::InvalidByte(i, *c));
+        }
+        let mut carry = d as u32;
+        for x in out.iter_mut() {
+            carry += *x as u32 * 58;
+            *x = carry as u8;
+            carry >>= 8;
+        }
+        while carry > 0 {
+            out.push(carry as u8);
+            carry >>= 8;
+        }
+    }
+
+    let mut v = vec![0;ones];
+    v.extend(out.iter().rev());
+    Ok(v)
+}
+
+fn checksum(payload: &[u8]) -> [u8;4] {
+    let mut c = [0;4];
+    c.copy_from_slice(&sha256(&sha256(payload))[..4]);
+    c
+}
+
+/// Base58 of `payload` followed by the first 4 bytes of its double SHA-256.
+///
+/// Version bytes, as in Bitcoin addresses, are part of `payload`.
+pub fn encode_check(payload: &[u8]) -> String {
+    let mut data = payload.to_vec();
+    data.extend_from_slice(&checksum(payload));
+    encode(&data)
+}
+
+/// Decodes and verifies Base58Check, returning the payload without the checksum.
+pub fn decode_check(data: &str) -> Result<Vec<u8>,Error> {
+    let mut v = decode(data)?;
+    if v.len() < 4 {
+        return Err(Error::InvalidLength(data.len()));
+    }
+
+    let payload_len = v.len() - 4;
+    if checksum(&v[..payload_len]) != v[payload_len..] {
+        return Err(Error::InvalidChecksum);
+    }
+    v.truncate(payload_len);
+    Ok(v)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utility::codec::hex;
+
+    #[test]
+    fn test_base58() {
+        let vectors: [(&[u8], &str);6] = [
+            (b"", ""),
+            (&[0], "1"),
+            (&[0, 0, 0x28, 0x7f, 0xb4, 0xcd], "11233QC4"),
+            (b"Hello World!", "2NEpo7TZRRrLZSi2U"),
+            (b"The quick brown fox jumps over the lazy dog.", "USm3fpXnKG5EUBx2ndxBDMPVciP5hGey2Jh4NDv6gmeo1LkMeiKrLJUUBk6Z"),
+            (&[0xff;4], "7YXq9G"),
+        ];
+        for (plain, encoded) in vectors.iter() {
+            assert_eq!(encode(plain),*encoded);
+            assert_eq!(decode(encoded).unwrap(),*plain);
+        }
+
+        assert_eq!(decode("0OIl"),Err(Error::InvalidByte(0, b'0')));
+        assert_eq!(decode("11l"),Err(Error::InvalidByte(2, b'l')));
+    }
+
+    #[test]
+    fn test_base58check() {
+        // version 0 address of a HASH160
+        let payload = hex::decode("00010966776006953d5567439e5e39f86a0d273bee").unwrap();
+        assert_eq!(encode_check(&payload),"16UwLL9Risc3QfPqBUvKofHmBQ7wMtjvM");
+        assert_eq!(decode_check("16UwLL9Risc3QfPqBUvKofHmBQ7wMtjvM").unwrap(),payload);
+
+        assert_eq!(decode_check("16UwLL9Risc3QfPqBUvKofHmBQ7wMtjvN"),Err(Error::InvalidChecksum));
+        assert_eq!(decode_check("111"),Err(Error::InvalidLength(3)));
+        assert_eq!(decode_check(&encode_check(b"")).unwrap(),b"");
+    }
+}
@@ -0,0 +1,217 @@
+// Bech32 (BIP 173) and Bech32m (BIP 350)
+use super::Error;
+
+const CHARSET: &[u8;32] = b"qpzry9x8gf2tvdw0s3jn54khce6mua7l";
+const GENERATOR: [u32;5] = [0x3b6a57b2, 0x26508e6d, 0x1ea119fa, 0x3d4233dd, 0x2a1462b3];
+const MAX_LENGTH: usize = 90;
+
+#[derive(Debug,Clone,Copy,PartialEq,Eq)]
+pub enum Variant {
+    Bech32,
+    Bech32m,
+}
+
+impl Variant {
+    fn constant(&self) -> u32 {
+        match self {
+            Variant::Bech32 => 1,
+            Variant::Bech32m => 0x2bc830a3,
+        }
+    }
+}
+
+fn polymod(values: impl Iterator<Item=u8>) -> u32 {
+    let mut chk = 1u32;
+    for v in values {
+        let top = chk >> 25;
+        chk = (chk & 0x1ffffff) << 5 ^ v as u32;
+        for (i, g) in GENERATOR.iter().enumerate() {
+            if top >> i & 1 == 1 {
+                chk ^= g;
+            }
+        }
+    }
+    chk
+}
+
+fn hrp_expand(hrp: &[u8]) -> impl Iterator<Item=u8> + '_ {
+    hrp.iter().map(|c| c >> 5).chain(std::iter::once(0)).chain(hrp.iter().map(|c| c & 0x1f))
+}
+
+/// Encodes 5-bit `data` values under the human-readable part `hrp`, in lower case.
+pub fn encode(hrp: &str, data: &[u8], variant: Variant) -> Result<String,Error> {
+    let hrp = hrp.to_ascii_lowercase();
+    let h = hrp.as_bytes();
+    if h.is_empty() || h.iter().any(|c| !(33..=126).contains(c)) {
+        return Err(Error::InvalidPrefix);
+    }
+    if let Some(i) = data.iter().position(|v| *v > 31) {
+        return Err(Error::InvalidByte(i, data[i]));
+    }
+    let len = h.len() + 1 + data.len() + 6;
+    if len > MAX_LENGTH {
+        return Err(Error::InvalidLength(len));
+    }
+
+    let chk = polymod(hrp_expand(h).chain(data.iter().cloned()).chain([0;6].iter().cloned())) ^ variant.constant();
+
+    let mut s = hrp.clone();
+    s.push('1');
+    s.extend(data.iter().map(|v| CHARSET[*v as usize] as char));
+    s.extend((0..6).map(|i| CHARSET[(chk >> (5 * (5 - i)) & 0x1f) as usize] as char));
+    Ok(s)
+}
+
+/// Returns the lower-case human-readable part, the 5-bit data values and the variant
+/// whose checksum matched.
+pub fn decode(s: &str) -> Result<(String, Vec<u8>, Variant),Error> {
+    let bytes = s.as_bytes();
+    if bytes.len() > MAX_LENGTH {
+        return Err(Error::InvalidLength(bytes.len()));
+    }
+    if let Some(i) = bytes.iter().position(|c| !(33..=126).contains(c)) {
+        return Err(Error::InvalidByte(i, bytes[i]));
+    }
+    let lower = bytes.iter().position(|c| c.is_ascii_lowercase());
+    let upper = bytes.iter().position(|c| c.is_ascii_uppercase());
+    if let (Some(l), Some(u)) = (lower, upper) {
+        return Err(Error::MixedCase(l.max(u)));
+    }
+
+    let s = s.to_ascii_lowercase();
+    let bytes = s.as_bytes();
+    let sep = match bytes.iter().rposition(|c| *c == b'1') {
+        Some(0) | None => return Err(Error::InvalidPrefix),
+        Some(sep) => sep,
+    };
+    if bytes.len() - sep - 1 < 6 {
+        return Err(Error::InvalidLength(bytes.len()));
+    }
+
+    let mut data = Vec::with_capacity(bytes.len() - sep - 1);
+    for (i, c) in bytes.iter().enumerate().skip(sep + 1) {
+        match CHARSET.iter().position(|x| x == c) {
+            Some(v) => data.push(v as u8),
+            None => return Err(Error::InvalidByte(i, *c)),
+        }
+    }
+
+    let hrp = &bytes[..sep];
+    let chk = polymod(hrp_expand(hrp).chain(data.iter().cloned()));
+    let variant = if chk == Variant::Bech32.constant() {
+        Variant::Bech32
+    } else if chk == Variant::Bech32m.constant() {
+        Variant::Bech32m
+    } else {
+        return Err(Error::InvalidChecksum);
+    };
+
+    data.truncate(data.len() - 6);
+    Ok((s[..sep].to_string(), data, variant))
+}
+
+/// Regroups bytes into 5-bit values, zero-padding the last one.
+pub fn to_base32(data: &[u8]) -> Vec<u8> {
+    let mut v = Vec::with_capacity((data.len() * 8).div_ceil(5));
+    let mut acc = 0u32;
+    let mut bits = 0;
+    for x in data.iter() {
+        acc = acc << 8 | *x as u32;
+        bits += 8;
+        while bits >= 5 {
+            bits -= 5;
+            v.push((acc >> bits & 0x1f) as u8);
+        }
+    }
+    if bits > 0 {
+        v.push((acc << (5 - bits) & 0x1f) as u8);
+    }
+    v
+}
+
+/// Regroups 5-bit values into bytes; leftover bits must be fewer than 5 and zero.
+pub fn from_base32(data: &[u8]) -> Result<Vec<u8>,Error> {
+    let mut v = Vec::with_capacity(data.len() * 5 / 8);
+    let mut acc = 0u32;
+    let mut bits = 0;
+    for (i, x) in data.iter().enumerate() {
+        if *x > 31 {
+            return Err(Error::InvalidByte(i, *x));
+        }
+        acc = (acc << 5 | *x as u32) & 0xfff;
+        bits += 5;
+        if bits >= 8 {
+            bits -= 8;
+            v.push((acc >> bits) as u8);
+        }
+    }
+    if bits >= 5 {
+        return Err(Error::InvalidLength(data.len()));
+    }
+    if acc & ((1 << bits) - 1) != 0 {
+        return Err(Error::InvalidLastSymbol(data.len() - 1, data[data.len() - 1]));
+    }
+    Ok(v)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utility::codec::hex;
+
+    #[test]
+    fn test_valid() {
+        // BIP 173 and BIP 350 valid test vectors
+        let vectors = [
+            ("A12UEL5L", Variant::Bech32),
+            ("a12uel5l", Variant::Bech32),
+            ("an83characterlonghumanreadablepartthatcontainsthenumber1andtheexcludedcharactersbio1tt5tgs", Variant::Bech32),
+            ("abcdef1qpzry9x8gf2tvdw0s3jn54khce6mua7lmqqqxw", Variant::Bech32),
+            ("split1checkupstagehandshakeupstreamerranterredcaperred2y9e3w", Variant::Bech32),
+            ("A1LQFN3A", Variant::Bech32m),
+            ("abcdef1l7aum6echk45nj3s0wdvt2fg8x9yrzpqzd3ryx", Variant::Bech32m),
+            ("split1checkupstagehandshakeupstreamerranterredcaperredlc445v", Variant::Bech32m),
+        ];
+        for (s, variant) in vectors.iter() {
+            let (hrp, data, v) = decode(s).unwrap();
+            assert_eq!(v,*variant);
+            assert_eq!(encode(&hrp, &data, v).unwrap(),s.to_ascii_lowercase());
+        }
+
+        let (hrp, data, _) = decode("abcdef1qpzry9x8gf2tvdw0s3jn54khce6mua7lmqqqxw").unwrap();
+        assert_eq!(hrp,"abcdef");
+        assert_eq!(data,(0..32).collect::<Vec<u8>>());
+    }
+
+    #[test]
+    fn test_invalid() {
+        assert_eq!(decode("\x201nwldj5"),Err(Error::InvalidByte(0, 0x20)));
+        assert_eq!(decode("an84characterslonghumanreadablepartthatcontainsthenumber1andtheexcludedcharactersbio1569pvx"),Err(Error::InvalidLength(91)));
+        assert_eq!(decode("pzry9x0s0muk"),Err(Error::InvalidPrefix));
+        assert_eq!(decode("1pzry9x0s0muk"),Err(Error::InvalidPrefix));
+        assert_eq!(decode("x1b4n0q5v"),Err(Error::InvalidByte(2, b'b')));
+        assert_eq!(decode("li1dgmt3"),Err(Error::InvalidLength(8)));
+        assert_eq!(decode("A1G7SGD8"),Err(Error::InvalidChecksum));
+        assert_eq!(decode("a12UEL5L"),Err(Error::MixedCase(3)));
+        assert_eq!(decode("a12uel5m"),Err(Error::InvalidChecksum));
+        assert!(encode("", &[], Variant::Bech32).is_err());
+        assert_eq!(encode("a", &[32], Variant::Bech32),Err(Error::InvalidByte(0, 32)));
+    }
+
+    #[test]
+    fn test_segwit_program() {
+        // BIP 173 P2WPKH example: witness version 0 followed by the program
+        let (hrp, data, variant) = decode("BC1QW508D6QEJXTDG4Y5R3ZARVARY0C5XW7KV8F3T4").unwrap();
+        assert_eq!((hrp.as_str(), data[0], variant),("bc", 0, Variant::Bech32));
+        let program = from_base32(&data[1..]).unwrap();
+        assert_eq!(program,hex::decode("751e76e8199196d454941c45d1b3a323f1433bd6").unwrap());
+
+        let mut data = vec![0];
+        data.extend(to_base32(&program));
+        assert_eq!(encode("bc", &data, Variant::Bech32).unwrap(),"bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4");
+
+        assert_eq!(from_base32(&[0xff]),Err(Error::InvalidByte(0, 0xff)));
+        assert_eq!(from_base32(&[0, 0, 0]),Err(Error::InvalidLength(3)));
+        assert_eq!(from_base32(&[0, 1]),Err(Error::InvalidLastSymbol(1, 1)));
+    }
+}
@@ -0,0 +1,117 @@
+// Base16 (RFC 4648 section 8)
+use super::Error;
+use crate::utility::ct;
+
+const LOWER: &[u8;16] = b"0123456789abcdef";
+const UPPER: &[u8;16] = b"0123456789ABCDEF";
+
+fn encode_with(data: &[u8], table: &[u8;16]) -> String {
+    let mut s = String::with_capacity(data.len() * 2);
+    for x in data.iter() {
+        s.push(table[(x >> 4) as usize] as char);
+        s.push(table[(x & 0xf) as usize] as char);
+    }
+    s
+}
+
+pub fn encode(data: &[u8]) -> String {
+    encode_with(data, LOWER)
+}
+
+pub fn encode_upper(data: &[u8]) -> String {
+    encode_with(data, UPPER)
+}
+
+/// Decodes an even number of hex digits of either case; nothing else is accepted.
+pub fn decode(data: &str) -> Result<Vec<u8>,Error> {
+    let bytes = data.as_bytes();
+    if bytes.len() % 2 != 0 {
+        return Err(Error::InvalidLength(bytes.len()));
+    }
+
+    let digit = |i: usize| {
+        let c = bytes[i];
+        match c {
+            b'0'..=b'9' => Ok(c - b'0'),
+            b'a'..=b'f' => Ok(c - b'a' + 10),
+            b'A'..=b'F' => Ok(c - b'A' + 10),
+            _ => Err(Error::InvalidByte(i, c)),
+        }
+    };
+
+    (0..bytes.len()).step_by(2).map(|i| Ok(digit(i)? << 4 | digit(i + 1)?)).collect()
+}
+
+/// Same as `decode`, but the time taken depends only on the length of `data`, not on
+/// the digits; use it for secrets. Invalid input is decoded again with `decode` to
+/// locate the error.
+pub fn decode_ct(data: &str) -> Result<Vec<u8>,Error> {
+    let bytes = data.as_bytes();
+    if bytes.len() % 2 != 0 {
+        return Err(Error::InvalidLength(bytes.len()));
+    }
+
+    // value + 1 of the digit, 0 if it is not one
+    let digit = |c: u8| {
+        let c = c as i32;
+        (ct::range_mask(c, 0x30, 0x39) & (c - 0x30 + 1))
+            | (ct::range_mask(c, 0x61, 0x66) & (c - 0x61 + 11))
+            | (ct::range_mask(c, 0x41, 0x46) & (c - 0x41 + 11))
+    };
+
+    let mut bad = 0;
+    let mut v = Vec::with_capacity(bytes.len() / 2);
+    for pair in bytes.chunks_exact(2) {
+        let (hi, lo) = (digit(pair[0]), digit(pair[1]));
+        bad |= (hi - 1) | (lo - 1);
+        v.push(((hi - 1) << 4 | ((lo - 1) & 0xf)) as u8);
+    }
+
+    if bad < 0 {
+        return decode(data);
+    }
+    Ok(v)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hex() {
+        // RFC 4648 section 10
+        assert_eq!(encode_upper(b"foobar"),"666F6F626172");
+        assert_eq!(encode(&[0x00, 0x0f, 0xa5, 0xff]),"000fa5ff");
+        assert_eq!(encode(b""),"");
+
+        assert_eq!(decode("666F6F626172").unwrap(),b"foobar");
+        assert_eq!(decode("000fa5ff").unwrap(),[0x00, 0x0f, 0xa5, 0xff]);
+        assert_eq!(decode("").unwrap(),b"");
+        assert_eq!(decode("abc"),Err(Error::InvalidLength(3)));
+        assert_eq!(decode("0g"),Err(Error::InvalidByte(1, b'g')));
+        assert_eq!(decode(" 0"),Err(Error::InvalidByte(0, b' ')));
+        assert_eq!(decode("0x00"),Err(Error::InvalidByte(1, b'x')));
+    }
+
+    #[test]
+    fn test_decode_ct() {
+        for s in ["", "00", "0123456789abcdefABCDEF", "ff", "abc", "0g", "g0", "0x00", "@`/:GgFf"].iter() {
+            assert_eq!(decode_ct(s),decode(s));
+        }
+        for x in 0..=255u8 {
+            assert_eq!(decode_ct(&encode(&[x])).unwrap(),[x]);
+            assert_eq!(decode_ct(&encode_upper(&[x])).unwrap(),[x]);
+        }
+    }
+
+    #[test]
+    #[ignore = "wall-clock timing, run with --ignored on a quiet machine"]
+    fn test_decode_ct_timing() {
+        let zeros = encode(&[0;1024]);
+        let random = encode(&crate::rng::Rng::from_seed([3;32]).generate_bytes(1024));
+        let t = ct::timing::welch_t(zeros.as_bytes(), random.as_bytes(), 20000, |s| {
+            std::hint::black_box(decode_ct(std::str::from_utf8(s).unwrap()).unwrap());
+        });
+        assert!(t.abs() < 10.0, "t = {}", t);
+    }
+}
@@ -0,0 +1,41 @@
+// Text encodings for binary data
+pub mod hex;
+pub mod base32;
+pub mod base58;
+pub mod bech32;
+
+use std::fmt;
+
+/// Error shared by the codecs; offsets are byte offsets into the input text.
+#[derive(Debug,Clone,Copy,PartialEq,Eq)]
+pub enum Error {
+    /// Byte outside the alphabet
+    InvalidByte(usize, u8),
+    /// Input length no encoding can produce
+    InvalidLength(usize),
+    /// Padding missing, unexpected, or of the wrong length
+    InvalidPadding(usize),
+    /// Last symbol has non-zero bits past the end of the data
+    InvalidLastSymbol(usize, u8),
+    /// Both upper and lower case letters where only one is allowed
+    MixedCase(usize),
+    InvalidChecksum,
+    /// Malformed or missing Bech32 human-readable part
+    InvalidPrefix,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::InvalidByte(i, c) => write!(f, "invalid byte 0x{:02x} at offset {}", c, i),
+            Error::InvalidLength(n) => write!(f, "invalid length {}", n),
+            Error::InvalidPadding(i) => write!(f, "invalid padding at offset {}", i),
+            Error::InvalidLastSymbol(i, c) => write!(f, "non-canonical last symbol {:?} at offset {}", *c as char, i),
+            Error::MixedCase(i) => write!(f, "mixed case at offset {}", i),
+            Error::InvalidChecksum => write!(f, "invalid checksum"),
+            Error::InvalidPrefix => write!(f, "invalid human-readable part"),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
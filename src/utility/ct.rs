@@ -0,0 +1,86 @@
+// Branchless helpers for decoding and comparing secret material
+//
+// Masks are all ones (-1) for true and 0 for false, computed without branching on the
+// inputs.
+
+/// -1 if `lo <= c <= hi`, else 0, for bytes `c`, `lo` and `hi`.
+pub(crate) fn range_mask(c: i32, lo: i32, hi: i32) -> i32 {
+    ((lo - 1 - c) & (c - hi - 1)) >> 8
+}
+
+/// -1 if `a == b`, else 0, for bytes `a` and `b`.
+pub(crate) fn eq_mask(a: i32, b: i32) -> i32 {
+    ((a ^ b) - 1) >> 8
+}
+
+/// Whether `a` and `b` are equal, in time that depends only on their lengths.
+pub(crate) fn bytes_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b.iter()).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+#[cfg(test)]
+pub(crate) mod timing {
+    use crate::rng::Rng;
+    use std::time::Instant;
+
+    /// Welch's t statistic between decode times of two input classes, dudect style.
+    ///
+    /// Measurements of the classes are interleaved in random order and the slowest 10%
+    /// are cropped to drop interrupts; |t| above ~5 suggests input-dependent timing.
+    pub(crate) fn welch_t<F: FnMut(&[u8])>(class0: &[u8], class1: &[u8], samples: usize, mut f: F) -> f64 {
+        let mut rng = Rng::from_seed([0x7e;32]);
+        let mut times: [Vec<f64>;2] = [Vec::with_capacity(samples), Vec::with_capacity(samples)];
+
+        for _ in 0..2 * samples {
+            let class = (rng.generate_range(0, 2)) as usize;
+            let input = if class == 0 {class0} else {class1};
+            let start = Instant::now();
+            f(input);
+            times[class].push(start.elapsed().as_nanos() as f64);
+        }
+
+        let mut all: Vec<f64> = times.iter().flatten().cloned().collect();
+        all.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        let cutoff = all[all.len() * 9 / 10];
+
+        let stats: Vec<(f64, f64, f64)> = times.iter().map(|t| {
+            let t: Vec<f64> = t.iter().cloned().filter(|x| *x <= cutoff).collect();
+            let n = t.len() as f64;
+            let mean = t.iter().sum::<f64>() / n;
+            let var = t.iter().map(|x| (x - mean) * (x - mean)).sum::<f64>() / (n - 1.0);
+            (n, mean, var)
+        }).collect();
+
+        let (n0, m0, v0) = stats[0];
+        let (n1, m1, v1) = stats[1];
+        (m0 - m1) / (v0 / n0 + v1 / n1).sqrt()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_masks() {
+        for c in 0..256 {
+            assert_eq!(range_mask(c, 0x41, 0x5a),if (0x41..=0x5a).contains(&c) {-1} else {0});
+            assert_eq!(eq_mask(c, 0x2b),if c == 0x2b {-1} else {0});
+        }
+        assert!(bytes_eq(b"secret", b"secret"));
+        assert!(!bytes_eq(b"secret", b"secreT"));
+        assert!(!bytes_eq(b"secret", b"secret!"));
+    }
+
+    #[test]
+    #[ignore = "wall-clock timing, run with --ignored on a quiet machine"]
+    fn test_timing_harness() {
+        // an early-exit comparison must be caught
+        let zeros = [b'0';4096];
+        let random = crate::rng::Rng::from_seed([1;32]).generate_bytes(4096);
+        let t = timing::welch_t(&zeros, &random, 5000, |s| {
+            std::hint::black_box(s.iter().take_while(|c| **c == b'0').count());
+        });
+        assert!(t.abs() > 10.0, "t = {}", t);
+    }
+}
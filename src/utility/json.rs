@@ -0,0 +1,409 @@
+// JSON (RFC 8259) values with a strict parser and compact serialization
+//
+// Objects keep their member order and may not repeat a name (RFC 7493 I-JSON), so that
+// a document cannot mean different things to different parsers.
+use std::collections::HashSet;
+use std::fmt;
+
+/// Deepest nesting of arrays and objects accepted
+pub const MAX_DEPTH: usize = 64;
+
+#[derive(Debug,Clone,PartialEq)]
+pub enum Value {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<Value>),
+    Object(Vec<(String, Value)>),
+}
+
+/// Parse error with the byte offset where it was detected.
+#[derive(Debug,Clone,PartialEq,Eq)]
+pub enum Error {
+    UnexpectedEnd,
+    UnexpectedChar(usize),
+    InvalidNumber(usize),
+    /// Bad escape, lone surrogate or unescaped control character
+    InvalidString(usize),
+    DuplicateName(String),
+    TooDeep,
+    TrailingData(usize),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::UnexpectedEnd => write!(f, "unexpected end of JSON text"),
+            Error::UnexpectedChar(i) => write!(f, "unexpected character at offset {}", i),
+            Error::InvalidNumber(i) => write!(f, "invalid number at offset {}", i),
+            Error::InvalidString(i) => write!(f, "invalid string at offset {}", i),
+            Error::DuplicateName(name) => write!(f, "duplicate member {:?}", name),
+            Error::TooDeep => write!(f, "nesting too deep"),
+            Error::TrailingData(i) => write!(f, "trailing data at offset {}", i),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+struct Parser<'a> {
+    text: &'a [u8],
+    pos: usize,
+    depth: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn skip_whitespace(&mut self) {
+        while let Some(b' ') | Some(b'\t') | Some(b'\n') | Some(b'\r') = self.text.get(self.pos) {
+            self.pos += 1;
+        }
+    }
+
+    fn peek(&mut self) -> Result<u8,Error> {
+        self.skip_whitespace();
+        self.text.get(self.pos).copied().ok_or(Error::UnexpectedEnd)
+    }
+
+    fn expect(&mut self, c: u8) -> Result<(),Error> {
+        if self.peek()? != c {
+            return Err(Error::UnexpectedChar(self.pos));
+        }
+        self.pos += 1;
+        Ok(())
+    }
+
+    fn literal(&mut self, word: &str, value: Value) -> Result<Value,Error> {
+        if !self.text[self.pos..].starts_with(word.as_bytes()) {
+            return Err(Error::UnexpectedChar(self.pos));
+        }
+        self.pos += word.len();
+        Ok(value)
+    }
+
+    fn value(&mut self) -> Result<Value,Error> {
+        match self.peek()? {
+            b'{' => self.nested(Parser::object),
+            b'[' => self.nested(Parser::array),
+            b'"' => Ok(Value::String(self.string()?)),
+            b't' => self.literal("true", Value::Bool(true)),
+            b'f' => self.literal("false", Value::Bool(false)),
+            b'n' => self.literal("null", Value::Null),
+            b'-' | b'0'..=b'9' => self.number(),
+            _ => Err(Error::UnexpectedChar(self.pos)),
+        }
+    }
+
+    fn nested(&mut self, f: fn(&mut Parser<'a>) -> Result<Value,Error>) -> Result<Value,Error> {
+        if self.depth == MAX_DEPTH {
+            return Err(Error::TooDeep);
+        }
+        self.depth += 1;
+        let value = f(self)?;
+        self.depth -= 1;
+        Ok(value)
+    }
+
+    fn object(&mut self) -> Result<Value,Error> {
+        self.expect(b'{')?;
+        let mut members: Vec<(String, Value)> = Vec::new();
+        let mut names = HashSet::new();
+        if self.peek()? == b'}' {
+            self.pos += 1;
+            return Ok(Value::Object(members));
+        }
+        loop {
+            if self.peek()? != b'"' {
+                return Err(Error::UnexpectedChar(self.pos));
+            }
+            let name = self.string()?;
+            if !names.insert(name.clone()) {
+                return Err(Error::DuplicateName(name));
+            }
+            self.expect(b':')?;
+            members.push((name, self.value()?));
+            match self.peek()? {
+                b',' => self.pos += 1,
+                b'}' => {
+                    self.pos += 1;
+                    return Ok(Value::Object(members));
+                },
+                _ => return Err(Error::UnexpectedChar(self.pos)),
+            }
+        }
+    }
+
+    fn array(&mut self) -> Result<Value,Error> {
+        self.expect(b'[')?;
+        let mut items = Vec::new();
+        if self.peek()? == b']' {
+            self.pos += 1;
+            return Ok(Value::Array(items));
+        }
+        loop {
+            items.push(self.value()?);
+            match self.peek()? {
+                b',' => self.pos += 1,
+                b']' => {
+                    self.pos += 1;
+                    return Ok(Value::Array(items));
+                },
+                _ => return Err(Error::UnexpectedChar(self.pos)),
+            }
+        }
+    }
+
+    fn number(&mut self) -> Result<Value,Error> {
+        let start = self.pos;
+        let digits = |p: &mut Parser| {
+            let from = p.pos;
+            while p.text.get(p.pos).is_some_and(|c| c.is_ascii_digit()) {
+                p.pos += 1;
+            }
+            p.pos > from
+        };
+        if self.text[self.pos] == b'-' {
+            self.pos += 1;
+        }
+        // no leading zeros
+        if self.text.get(self.pos) == Some(&b'0') {
+            self.pos += 1;
+        } else if !digits(self) {
+            return Err(Error::InvalidNumber(start));
+        }
+        if self.text.get(self.pos) == Some(&b'.') {
+            self.pos += 1;
+            if !digits(self) {
+                return Err(Error::InvalidNumber(start));
+            }
+        }
+        if let Some(b'e') | Some(b'E') = self.text.get(self.pos) {
+            self.pos += 1;
+            if let Some(b'+') | Some(b'-') = self.text.get(self.pos) {
+                self.pos += 1;
+            }
+            if !digits(self) {
+                return Err(Error::InvalidNumber(start));
+            }
+        }
+        let s = std::str::from_utf8(&self.text[start..self.pos]).unwrap();
+        match s.parse::<f64>() {
+            Ok(x) if x.is_finite() => Ok(Value::Number(x)),
+            _ => Err(Error::InvalidNumber(start)),
+        }
+    }
+
+    fn hex4(&mut self) -> Result<u32,Error> {
+        let h = self.text.get(self.pos..self.pos + 4).ok_or(Error::UnexpectedEnd)?;
+        let s = std::str::from_utf8(h).map_err(|_| Error::InvalidString(self.pos))?;
+        if !s.bytes().all(|c| c.is_ascii_hexdigit()) {
+            return Err(Error::InvalidString(self.pos));
+        }
+        self.pos += 4;
+        Ok(u32::from_str_radix(s, 16).unwrap())
+    }
+
+    fn string(&mut self) -> Result<String,Error> {
+        self.expect(b'"')?;
+        let mut out = Vec::new();
+        loop {
+            let c = *self.text.get(self.pos).ok_or(Error::UnexpectedEnd)?;
+            let at = self.pos;
+            self.pos += 1;
+            match c {
+                b'"' => break,
+                b'\\' => {
+                    let e = *self.text.get(self.pos).ok_or(Error::UnexpectedEnd)?;
+                    self.pos += 1;
+                    let ch = match e {
+                        b'"' => '"',
+                        b'\\' => '\\',
+                        b'/' => '/',
+                        b'b' => '\u{8}',
+                        b'f' => '\u{c}',
+                        b'n' => '\n',
+                        b'r' => '\r',
+                        b't' => '\t',
+                        b'u' => {
+                            let mut code = self.hex4()?;
+                            if (0xd800..0xdc00).contains(&code) {
+                                if !self.text[self.pos..].starts_with(b"\\u") {
+                                    return Err(Error::InvalidString(at));
+                                }
+                                self.pos += 2;
+                                let low = self.hex4()?;
+                                if !(0xdc00..0xe000).contains(&low) {
+                                    return Err(Error::InvalidString(at));
+                                }
+                                code = 0x10000 + ((code - 0xd800) << 10) + (low - 0xdc00);
+                            }
+                            std::char::from_u32(code).ok_or(Error::InvalidString(at))?
+                        },
+                        _ => return Err(Error::InvalidString(at)),
+                    };
+                    let mut buf = [0u8;4];
+                    out.extend_from_slice(ch.encode_utf8(&mut buf).as_bytes());
+                },
+                0x00..=0x1f => return Err(Error::InvalidString(at)),
+                _ => out.push(c),
+            }
+        }
+        // the input is a &str, so unescaped bytes are already valid UTF-8
+        Ok(String::from_utf8(out).unwrap())
+    }
+}
+
+impl Value {
+    /// Parses a complete JSON text.
+    pub fn parse(text: &str) -> Result<Value,Error> {
+        let mut p = Parser {text: text.as_bytes(), pos: 0, depth: 0};
+        let value = p.value()?;
+        p.skip_whitespace();
+        if p.pos != text.len() {
+            return Err(Error::TrailingData(p.pos));
+        }
+        Ok(value)
+    }
+
+    /// The member `name` of an object.
+    pub fn get(&self, name: &str) -> Option<&Value> {
+        match self {
+            Value::Object(members) => members.iter().find(|(n, _)| n == name).map(|(_, v)| v),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Value::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            Value::Number(x) => Some(*x),
+            _ => None,
+        }
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            Value::Bool(b) => Some(*b),
+            _ => None,
+        }
+    }
+
+    pub fn as_array(&self) -> Option<&[Value]> {
+        match self {
+            Value::Array(items) => Some(items),
+            _ => None,
+        }
+    }
+
+    pub fn as_object(&self) -> Option<&[(String, Value)]> {
+        match self {
+            Value::Object(members) => Some(members),
+            _ => None,
+        }
+    }
+}
+
+impl From<&str> for Value {
+    fn from(s: &str) -> Value {
+        Value::String(s.to_string())
+    }
+}
+
+fn write_string(f: &mut fmt::Formatter, s: &str) -> fmt::Result {
+    write!(f, "\"")?;
+    for c in s.chars() {
+        match c {
+            '"' => write!(f, "\\\"")?,
+            '\\' => write!(f, "\\\\")?,
+            '\n' => write!(f, "\\n")?,
+            '\r' => write!(f, "\\r")?,
+            '\t' => write!(f, "\\t")?,
+            '\u{0}'..='\u{1f}' => write!(f, "\\u{:04x}", c as u32)?,
+            _ => write!(f, "{}", c)?,
+        }
+    }
+    write!(f, "\"")
+}
+
+impl fmt::Display for Value {
+    /// Compact JSON text, without whitespace between tokens
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Value::Null => write!(f, "null"),
+            Value::Bool(b) => write!(f, "{}", b),
+            Value::Number(x) => write!(f, "{}", x),
+            Value::String(s) => write_string(f, s),
+            Value::Array(items) => {
+                write!(f, "[")?;
+                for (i, v) in items.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ",")?;
+                    }
+                    write!(f, "{}", v)?;
+                }
+                write!(f, "]")
+            },
+            Value::Object(members) => {
+                write!(f, "{{")?;
+                for (i, (n, v)) in members.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ",")?;
+                    }
+                    write_string(f, n)?;
+                    write!(f, ":{}", v)?;
+                }
+                write!(f, "}}")
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let v = Value::parse(" {\"a\": [1, -2.5e3, true, null], \"b\" : {\"c\":\"\\u00e9\\ud83d\\ude00\\n\\/\"}, \"d\":0} ").unwrap();
+        assert_eq!(v.get("a").unwrap().as_array().unwrap(),
+            [Value::Number(1.0), Value::Number(-2500.0), Value::Bool(true), Value::Null]);
+        assert_eq!(v.get("b").and_then(|b| b.get("c")).and_then(Value::as_str),Some("é😀\n/"));
+        assert_eq!(v.get("d").and_then(Value::as_f64),Some(0.0));
+        assert_eq!(v.get("e"),None);
+        assert_eq!(v.to_string(),"{\"a\":[1,-2500,true,null],\"b\":{\"c\":\"é😀\\n/\"},\"d\":0}");
+        assert_eq!(Value::parse(&v.to_string()).unwrap(),v);
+        assert_eq!(Value::from("\"\\\u{1}").to_string(),"\"\\\"\\\\\\u0001\"");
+        assert_eq!(Value::parse("[]").unwrap().to_string(),"[]");
+        assert_eq!(Value::parse("{}").unwrap().to_string(),"{}");
+    }
+
+    #[test]
+    fn test_parse_invalid() {
+        assert_eq!(Value::parse(""),Err(Error::UnexpectedEnd));
+        assert_eq!(Value::parse("[1,]"),Err(Error::UnexpectedChar(3)));
+        assert_eq!(Value::parse("{\"a\":1,}"),Err(Error::UnexpectedChar(7)));
+        assert_eq!(Value::parse("{'a':1}"),Err(Error::UnexpectedChar(1)));
+        assert_eq!(Value::parse("01"),Err(Error::TrailingData(1)));
+        assert_eq!(Value::parse("1."),Err(Error::InvalidNumber(0)));
+        assert_eq!(Value::parse("-"),Err(Error::InvalidNumber(0)));
+        assert_eq!(Value::parse("1e999"),Err(Error::InvalidNumber(0)));
+        assert_eq!(Value::parse("\"\\ud83d\""),Err(Error::InvalidString(1)));
+        assert_eq!(Value::parse("\"\\x\""),Err(Error::InvalidString(1)));
+        assert_eq!(Value::parse("\"a\tb\""),Err(Error::InvalidString(2)));
+        assert_eq!(Value::parse("\"abc"),Err(Error::UnexpectedEnd));
+        assert_eq!(Value::parse("tru"),Err(Error::UnexpectedChar(0)));
+        assert_eq!(Value::parse("{\"a\":1,\"a\":2}"),Err(Error::DuplicateName("a".to_string())));
+        // a large object repeating its first name at the end
+        let members: Vec<String> = (0..100_000).map(|i| format!("\"m{}\":0", i)).collect();
+        let text = format!("{{{},\"m0\":1}}", members.join(","));
+        assert_eq!(Value::parse(&text),Err(Error::DuplicateName("m0".to_string())));
+        assert_eq!(Value::parse(&"[".repeat(MAX_DEPTH + 1)),Err(Error::TooDeep));
+        assert!(Value::parse(&format!("{}{}", "[".repeat(MAX_DEPTH), "]".repeat(MAX_DEPTH))).is_ok());
+    }
+}
@@ -1 +1,6 @@
+pub mod asn1;
 pub mod base64;
+pub mod codec;
+pub mod json;
+pub mod pem;
+pub(crate) mod ct;
@@ -0,0 +1,289 @@
+// Issuing X.509 v3 certificates
+use super::{BasicConstraints, Certificate, CertificateRequest, Error, Extension, Extensions, GeneralName, KeyUsage,
+    Name, SignatureAlgorithm, SubjectPublicKeyInfo};
+use crate::rng::Rng;
+use crate::rsa;
+use crate::utility::asn1::{self, Oid, Time};
+use num::BigUint;
+use std::fmt;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Validity of a certificate when none is given: 90 days from now
+const DEFAULT_VALIDITY_SECS: i64 = 90 * 86400;
+
+/// Invalid input to `CertificateBuilder`.
+#[derive(Debug,Clone,PartialEq,Eq)]
+pub enum BuildError {
+    /// A validity period whose notBefore is after its notAfter
+    InvalidValidity,
+    BadRequestSignature,
+    /// A request key or signature algorithm other than RSA
+    UnsupportedAlgorithm(Oid),
+}
+
+impl fmt::Display for BuildError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            BuildError::InvalidValidity => write!(f, "validity period ends before it starts"),
+            BuildError::BadRequestSignature => write!(f, "bad signature on certificate request"),
+            BuildError::UnsupportedAlgorithm(oid) => write!(f, "unsupported algorithm {}", oid),
+        }
+    }
+}
+
+impl std::error::Error for BuildError {}
+
+/// Builds and signs a certificate for a subject name and key.
+///
+/// Unless set, the serial number is 128 random bits and the validity starts now and lasts
+/// 90 days. A subject key identifier is always added, and an authority key identifier
+/// when the issuer has one.
+#[derive(Debug,Clone)]
+pub struct CertificateBuilder {
+    subject: Name,
+    public_key: SubjectPublicKeyInfo,
+    serial: Option<BigUint>,
+    validity: Option<(Time, Time)>,
+    basic_constraints: Option<BasicConstraints>,
+    key_usage: Option<KeyUsage>,
+    subject_alt_names: Vec<GeneralName>,
+    extensions: Vec<Extension>,
+    signature_algorithm: SignatureAlgorithm,
+}
+
+impl CertificateBuilder {
+    pub fn new(subject: Name, public_key: &rsa::PublicKey) -> CertificateBuilder {
+        CertificateBuilder {
+            subject, public_key: SubjectPublicKeyInfo::from_rsa(public_key), serial: None, validity: None,
+            basic_constraints: None, key_usage: None, subject_alt_names: Vec::new(), extensions: Vec::new(),
+            signature_algorithm: SignatureAlgorithm::RsaPkcs1Sha256,
+        }
+    }
+
+    /// Starts from a request's subject, key and subjectAltName; its other requested
+    /// extensions are not copied. Fails unless the request's signature verifies.
+    pub fn from_request(req: &CertificateRequest) -> Result<CertificateBuilder,BuildError> {
+        match req.verify_signature() {
+            Ok(true) => (),
+            Ok(false) => return Err(BuildError::BadRequestSignature),
+            Err(Error::UnsupportedAlgorithm(oid)) => return Err(BuildError::UnsupportedAlgorithm(oid)),
+            Err(_) => return Err(BuildError::BadRequestSignature),
+        }
+        let spki = req.public_key();
+        let key = spki.rsa_public_key().map_err(|_| BuildError::UnsupportedAlgorithm(spki.algorithm.clone()))?;
+        Ok(CertificateBuilder::new(req.subject().clone(), &key).with_subject_alt_names(req.subject_alt_names()))
+    }
+
+    pub fn with_serial(mut self, serial: BigUint) -> Self {
+        self.serial = Some(serial);
+        self
+    }
+
+    /// Fails with `InvalidValidity` if `not_before` is after `not_after`.
+    pub fn with_validity(mut self, not_before: Time, not_after: Time) -> Result<Self,BuildError> {
+        if not_before > not_after {
+            return Err(BuildError::InvalidValidity);
+        }
+        self.validity = Some((not_before, not_after));
+        Ok(self)
+    }
+
+    pub fn with_basic_constraints(mut self, bc: BasicConstraints) -> Self {
+        self.basic_constraints = Some(bc);
+        self
+    }
+
+    pub fn with_key_usage(mut self, usage: KeyUsage) -> Self {
+        self.key_usage = Some(usage);
+        self
+    }
+
+    pub fn with_subject_alt_names(mut self, names: &[GeneralName]) -> Self {
+        self.subject_alt_names.extend_from_slice(names);
+        self
+    }
+
+    /// Adds any other extension; it must not be one the builder writes itself.
+    pub fn with_extension(mut self, ext: Extension) -> Self {
+        self.extensions.push(ext);
+        self
+    }
+
+    pub fn with_signature_algorithm(mut self, alg: SignatureAlgorithm) -> Self {
+        self.signature_algorithm = alg;
+        self
+    }
+
+    /// Signs the certificate with the subject's own key.
+    pub fn self_signed(&self, key: &rsa::PrivateKey) -> Certificate {
+        let public_key = SubjectPublicKeyInfo::from_rsa(&rsa::PublicKey::from_private_key(key));
+        assert_eq!(public_key, self.public_key, "key does not match the subject key");
+        self.sign(&self.subject, None, key)
+    }
+
+    /// Signs the certificate as `issuer`, whose private key is `key`.
+    pub fn signed_by(&self, issuer: &Certificate, key: &rsa::PrivateKey) -> Certificate {
+        let public_key = SubjectPublicKeyInfo::from_rsa(&rsa::PublicKey::from_private_key(key));
+        assert_eq!(&public_key, issuer.public_key(), "key does not match the issuer certificate");
+        self.sign(issuer.subject(), issuer.subject_key_id(), key)
+    }
+
+    fn sign(&self, issuer: &Name, authority_key_id: Option<&[u8]>, key: &rsa::PrivateKey) -> Certificate {
+        let serial = self.serial.clone().unwrap_or_else(|| {
+            let mut bytes = Rng::new().generate_bytes(16);
+            bytes[0] &= 0x7f;
+            bytes[0] |= 0x40;
+            BigUint::from_bytes_be(&bytes)
+        });
+        let (not_before, not_after) = self.validity.unwrap_or_else(|| {
+            let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs() as i64;
+            (Time::from_unix(now), Time::from_unix(now + DEFAULT_VALIDITY_SECS))
+        });
+
+        let mut exts = Vec::new();
+        exts.extend(self.basic_constraints.map(|bc| bc.to_extension()));
+        exts.extend(self.key_usage.map(|ku| ku.to_extension()));
+        if !self.subject_alt_names.is_empty() {
+            exts.push(Extension::subject_alt_name(&self.subject_alt_names, self.subject.is_empty()));
+        }
+        exts.push(Extension::subject_key_id(&self.public_key.key_id()));
+        exts.extend(authority_key_id.map(Extension::authority_key_id));
+        for ext in self.extensions.iter() {
+            assert!(exts.iter().all(|e| e.oid != ext.oid), "duplicate extension {}", ext.oid);
+            exts.push(ext.clone());
+        }
+
+        let tbs = asn1::encode(|w| w.write_sequence(|w| {
+            w.write_context(0, |w| w.write_u64(2));
+            w.write_integer(&serial);
+            self.signature_algorithm.write(w);
+            w.write_raw(issuer.to_der());
+            w.write_sequence(|w| {
+                w.write_time(&not_before);
+                w.write_time(&not_after);
+            });
+            w.write_raw(self.subject.to_der());
+            self.public_key.write(w);
+            w.write_context(3, |w| Extensions::write(w, &exts));
+        }));
+        let signature = self.signature_algorithm.sign(key, &tbs);
+        let der = asn1::encode(|w| w.write_sequence(|w| {
+            w.write_raw(&tbs);
+            self.signature_algorithm.write(w);
+            w.write_bit_string_bytes(&signature);
+        }));
+        Certificate::from_der(&der).unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utility::pem::Pem;
+    use crate::x509::{oid, verify_chain, Error, RequestBuilder};
+    use std::net::IpAddr;
+
+    const ROOT: &str = include_str!("testdata/root.pem");
+    const ROOT_KEY: &str = include_str!("testdata/root.key");
+    const INTER_KEY: &str = include_str!("testdata/inter.key");
+    const LEAF_KEY: &str = include_str!("testdata/leaf.key");
+
+    fn key(pem: &str) -> rsa::PrivateKey {
+        rsa::PrivateKey::from_pem(pem).unwrap()
+    }
+
+    fn at(y: u16) -> Time {
+        Time::new(y, 1, 1, 0, 0, 0).unwrap()
+    }
+
+    #[test]
+    fn test_self_signed() {
+        let sk = key(ROOT_KEY);
+        let pk = rsa::PublicKey::from_private_key(&sk);
+        let subject = Name::new().with_attribute(oid::COUNTRY, "JP").with_attribute(oid::COMMON_NAME, "Test CA");
+        let ca = CertificateBuilder::new(subject.clone(), &pk)
+            .with_serial(BigUint::from(7u8))
+            .with_validity(at(2024), at(2054)).unwrap()
+            .with_basic_constraints(BasicConstraints {ca: true, path_len: Some(1)})
+            .with_key_usage(KeyUsage::KEY_CERT_SIGN | KeyUsage::CRL_SIGN)
+            .self_signed(&sk);
+
+        assert_eq!(ca.version(),3);
+        assert_eq!(ca.serial(),&BigUint::from(7u8));
+        assert_eq!((ca.subject(), ca.issuer()),(&subject, &subject));
+        assert_eq!((ca.not_before(), ca.not_after()),(at(2024), at(2054)));
+        assert_eq!(ca.basic_constraints(),Some(BasicConstraints {ca: true, path_len: Some(1)}));
+        assert_eq!(ca.subject_key_id().map(|id| id.len()),Some(20));
+        assert_eq!(ca.authority_key_id(),None);
+        assert_eq!(ca.verify_signature(ca.public_key()),Ok(true));
+        assert_eq!(Certificate::from_pem(&ca.to_pem()).unwrap(),ca);
+        // the notAfter after 2049 is a GeneralizedTime
+        assert!(ca.to_der().windows(15).any(|w| w == b"20540101000000Z"));
+
+        let defaults = CertificateBuilder::new(subject, &pk).self_signed(&sk);
+        assert!(defaults.serial().bits() > 64 && defaults.serial().bits() <= 127);
+        assert_eq!(defaults.not_after().unix() - defaults.not_before().unix(),DEFAULT_VALIDITY_SECS);
+        assert_eq!(defaults.basic_constraints(),None);
+    }
+
+    #[test]
+    fn test_ca_signed() {
+        // issue a leaf from a CSR under the OpenSSL root, then an intermediate of our own
+        let root = Certificate::from_pem(ROOT).unwrap();
+        let inter_key = key(INTER_KEY);
+        let inter = CertificateBuilder::new(Name::new().with_attribute(oid::COMMON_NAME, "Built CA"),
+            &rsa::PublicKey::from_private_key(&inter_key))
+            .with_validity(at(2024), at(2030)).unwrap()
+            .with_basic_constraints(BasicConstraints {ca: true, path_len: Some(0)})
+            .with_key_usage(KeyUsage::KEY_CERT_SIGN)
+            .with_signature_algorithm(SignatureAlgorithm::RsaPssSha256 {salt_len: 32})
+            .signed_by(&root, &key(ROOT_KEY));
+        assert_eq!(inter.issuer(),root.subject());
+        assert_eq!(inter.authority_key_id(),root.subject_key_id());
+
+        let names = [GeneralName::Dns("svc.test".to_string()), GeneralName::Ip(IpAddr::from([127, 0, 0, 1]))];
+        let csr = RequestBuilder::new(Name::new().with_attribute(oid::COMMON_NAME, "svc.test"))
+            .with_subject_alt_names(&names)
+            .sign(&key(LEAF_KEY));
+        let leaf = CertificateBuilder::from_request(&csr).unwrap()
+            .with_validity(at(2025), at(2026)).unwrap()
+            .with_basic_constraints(BasicConstraints {ca: false, path_len: None})
+            .with_key_usage(KeyUsage::DIGITAL_SIGNATURE)
+            .with_extension(Extension::new(oid::EXT_KEY_USAGE, false, vec![0x30, 0x0a, 0x06, 0x08, 0x2b, 0x06, 0x01, 0x05, 0x05, 0x07, 0x03, 0x01]))
+            .signed_by(&inter, &inter_key);
+        assert_eq!(leaf.subject_alt_names(),names);
+        assert_eq!(leaf.public_key(),csr.public_key());
+        assert!(leaf.extensions().get(oid::EXT_KEY_USAGE).is_some());
+
+        let now = Time::new(2025, 6, 1, 0, 0, 0).unwrap();
+        let (chain, roots) = ([leaf, inter], [root]);
+        assert_eq!(verify_chain(&chain, &roots, now).unwrap().len(),3);
+        assert_eq!(verify_chain(&chain, &roots, at(2027)),Err(Error::Expired(0)));
+    }
+
+    #[test]
+    fn test_builder_errors() {
+        let csr = RequestBuilder::new(Name::new().with_attribute(oid::COMMON_NAME, "svc.test")).sign(&key(LEAF_KEY));
+        let mut der = csr.to_der().to_vec();
+        let n = der.len();
+        der[n - 1] ^= 1;
+        let forged = CertificateRequest::from_der(&der).unwrap();
+        assert_eq!(CertificateBuilder::from_request(&forged).err(),Some(BuildError::BadRequestSignature));
+
+        // made by `openssl req -new -newkey ec`
+        let ec = CertificateRequest::from_pem(include_str!("testdata/ec.csr")).unwrap();
+        assert!(matches!(CertificateBuilder::from_request(&ec), Err(BuildError::UnsupportedAlgorithm(_))));
+
+        let builder = CertificateBuilder::from_request(&csr).unwrap();
+        assert_eq!(builder.clone().with_validity(at(2026), at(2025)).err(),Some(BuildError::InvalidValidity));
+        assert!(builder.with_validity(at(2025), at(2025)).is_ok());
+    }
+
+    #[test]
+    #[should_panic(expected = "key does not match")]
+    fn test_wrong_key() {
+        let root = Certificate::from_pem(ROOT).unwrap();
+        let pk = rsa::PublicKey::from_private_key(&key(LEAF_KEY));
+        CertificateBuilder::new(Name::new().with_attribute(oid::COMMON_NAME, "x"), &pk).signed_by(&root, &key(LEAF_KEY));
+    }
+}
@@ -0,0 +1,211 @@
+// PKCS#10 certification requests (RFC 2986)
+use super::{oid, Error, Extension, Extensions, GeneralName, Name, SignatureAlgorithm, SubjectPublicKeyInfo};
+use crate::rsa;
+use crate::utility::asn1::{self, Oid, Tag};
+use crate::utility::pem::{self, Pem};
+
+/// A parsed request that keeps its DER encoding; requested extensions are decoded
+/// like a certificate's.
+#[derive(Debug,Clone)]
+pub struct CertificateRequest {
+    der: Vec<u8>,
+    info: Vec<u8>,
+    subject: Name,
+    public_key: SubjectPublicKeyInfo,
+    extensions: Extensions,
+    signature_algorithm: SignatureAlgorithm,
+    signature: Vec<u8>,
+}
+
+impl PartialEq for CertificateRequest {
+    fn eq(&self, other: &CertificateRequest) -> bool {
+        self.der == other.der
+    }
+}
+
+impl Eq for CertificateRequest {}
+
+impl CertificateRequest {
+    pub fn from_der(der: &[u8]) -> Result<CertificateRequest,asn1::Error> {
+        asn1::decode(der, |r| {
+            let mut req = r.read_sequence()?;
+            let info_tlv = req.clone().read_tlv()?;
+            let mut info = req.read_sequence()?;
+            if info.read_u64()? != 0 {
+                return Err(asn1::Error::InvalidValue("unknown request version"));
+            }
+            let subject = Name::read(&mut info)?;
+            let public_key = SubjectPublicKeyInfo::read(&mut info)?;
+
+            // attributes [0] IMPLICIT SET OF Attribute; only extensionRequest is used
+            let mut extensions = Extensions::default();
+            let mut attrs = info.read_constructed(Tag::context(0))?;
+            while !attrs.is_empty() {
+                let mut attr = attrs.read_sequence()?;
+                let oid = attr.read_oid()?;
+                let mut values = attr.read_set()?;
+                if oid.arcs() == oid::EXTENSION_REQUEST {
+                    extensions = Extensions::read(&mut values)?;
+                }
+                while !values.is_empty() {
+                    values.read_tlv()?;
+                }
+                attr.finish()?;
+            }
+            info.finish()?;
+
+            let signature_algorithm = SignatureAlgorithm::read(&mut req)?;
+            let signature = req.read_bit_string()?.bytes()?.to_vec();
+            req.finish()?;
+
+            Ok(CertificateRequest {
+                der: der.to_vec(), info: info_tlv.raw.to_vec(), subject, public_key, extensions,
+                signature_algorithm, signature,
+            })
+        })
+    }
+
+    pub fn to_der(&self) -> &[u8] {
+        &self.der
+    }
+
+    pub fn subject(&self) -> &Name {
+        &self.subject
+    }
+
+    pub fn public_key(&self) -> &SubjectPublicKeyInfo {
+        &self.public_key
+    }
+
+    pub fn extensions(&self) -> &Extensions {
+        &self.extensions
+    }
+
+    pub fn subject_alt_names(&self) -> &[GeneralName] {
+        &self.extensions.subject_alt_names
+    }
+
+    pub fn signature_algorithm(&self) -> &SignatureAlgorithm {
+        &self.signature_algorithm
+    }
+
+    /// Checks the proof of possession: the request must be signed by its own key.
+    pub fn verify_signature(&self) -> Result<bool,Error> {
+        let key = self.public_key.rsa_public_key().map_err(|_| Error::UnsupportedAlgorithm(self.public_key.algorithm.clone()))?;
+        match &self.signature_algorithm {
+            SignatureAlgorithm::RsaPkcs1Sha256 => Ok(key.verify_pkcs1v15_sha256(&self.info, &self.signature)),
+            SignatureAlgorithm::RsaPssSha256 {salt_len} => Ok(key.verify_pss_sha256(&self.info, &self.signature, *salt_len)),
+            SignatureAlgorithm::Other(oid) => Err(Error::UnsupportedAlgorithm(oid.clone())),
+        }
+    }
+}
+
+impl Pem for CertificateRequest {
+    fn to_pem(&self) -> String {
+        pem::encode(&pem::Block::new("CERTIFICATE REQUEST", self.der.clone()))
+    }
+
+    fn from_pem(text: &str) -> Result<Self,pem::Error> {
+        let block = pem::parse_label(text, "CERTIFICATE REQUEST")?;
+        Ok(CertificateRequest::from_der(&block.data)?)
+    }
+}
+
+/// Builds and signs a `CertificateRequest`.
+#[derive(Debug,Clone)]
+pub struct RequestBuilder {
+    subject: Name,
+    extensions: Vec<Extension>,
+    signature_algorithm: SignatureAlgorithm,
+}
+
+impl RequestBuilder {
+    pub fn new(subject: Name) -> RequestBuilder {
+        RequestBuilder {subject, extensions: Vec::new(), signature_algorithm: SignatureAlgorithm::RsaPkcs1Sha256}
+    }
+
+    pub fn with_subject_alt_names(self, names: &[GeneralName]) -> Self {
+        let critical = self.subject.is_empty();
+        self.with_extension(Extension::subject_alt_name(names, critical))
+    }
+
+    /// Requests an extension; panics if one with the same OID was already added.
+    pub fn with_extension(mut self, ext: Extension) -> Self {
+        assert!(self.extensions.iter().all(|e| e.oid != ext.oid), "duplicate extension {}", ext.oid);
+        self.extensions.push(ext);
+        self
+    }
+
+    pub fn with_signature_algorithm(mut self, alg: SignatureAlgorithm) -> Self {
+        self.signature_algorithm = alg;
+        self
+    }
+
+    pub fn sign(&self, key: &rsa::PrivateKey) -> CertificateRequest {
+        let public_key = SubjectPublicKeyInfo::from_rsa(&rsa::PublicKey::from_private_key(key));
+        let info = asn1::encode(|w| w.write_sequence(|w| {
+            w.write_u64(0);
+            w.write_raw(self.subject.to_der());
+            public_key.write(w);
+            w.write_constructed(Tag::context(0), |w| {
+                if !self.extensions.is_empty() {
+                    w.write_sequence(|w| {
+                        w.write_oid(&Oid::new(oid::EXTENSION_REQUEST));
+                        w.write_set(|w| Extensions::write(w, &self.extensions));
+                    });
+                }
+            });
+        }));
+        let signature = self.signature_algorithm.sign(key, &info);
+        let der = asn1::encode(|w| w.write_sequence(|w| {
+            w.write_raw(&info);
+            self.signature_algorithm.write(w);
+            w.write_bit_string_bytes(&signature);
+        }));
+        CertificateRequest::from_der(&der).unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LEAF_KEY: &str = include_str!("testdata/leaf.key");
+    const LEAF_CSR: &str = include_str!("testdata/leaf.csr");
+
+    #[test]
+    fn test_parse() {
+        // made by `openssl req -new`, without attributes
+        let csr = CertificateRequest::from_pem(LEAF_CSR).unwrap();
+        assert_eq!(csr.subject().to_string(),"CN=service.internal,OU=Services,O=Crusto Test");
+        assert_eq!(csr.verify_signature(),Ok(true));
+        assert!(csr.subject_alt_names().is_empty());
+        assert_eq!(csr.to_pem(),LEAF_CSR);
+
+        let key = rsa::PrivateKey::from_pem(LEAF_KEY).unwrap();
+        assert_eq!(csr.public_key(),&SubjectPublicKeyInfo::from_rsa(&rsa::PublicKey::from_private_key(&key)));
+    }
+
+    #[test]
+    fn test_build() {
+        let key = rsa::PrivateKey::from_pem(LEAF_KEY).unwrap();
+        let subject = Name::new().with_attribute(oid::ORGANIZATION, "Crusto Test").with_attribute(oid::COMMON_NAME, "api.internal");
+        let names = [GeneralName::Dns("api.internal".to_string()), GeneralName::Ip("10.0.0.2".parse().unwrap())];
+
+        for alg in [SignatureAlgorithm::RsaPkcs1Sha256, SignatureAlgorithm::RsaPssSha256 {salt_len: 32}].iter() {
+            let csr = RequestBuilder::new(subject.clone())
+                .with_subject_alt_names(&names)
+                .with_signature_algorithm(alg.clone())
+                .sign(&key);
+            assert_eq!(csr.verify_signature(),Ok(true));
+            assert_eq!(csr.signature_algorithm(),alg);
+            assert_eq!(csr.subject(),&subject);
+            assert_eq!(csr.subject_alt_names(),names);
+            assert!(!csr.extensions().get(oid::SUBJECT_ALT_NAME).unwrap().critical);
+            assert_eq!(CertificateRequest::from_pem(&csr.to_pem()).unwrap(),csr);
+        }
+
+        let csr = RequestBuilder::new(Name::new()).with_subject_alt_names(&names).sign(&key);
+        assert!(csr.extensions().get(oid::SUBJECT_ALT_NAME).unwrap().critical);
+    }
+}
@@ -0,0 +1,347 @@
+// Certificate extensions (RFC 5280 section 4.2)
+use super::oid;
+use crate::utility::asn1::{self, Oid, Reader, Tag};
+use std::convert::TryFrom;
+use std::net::IpAddr;
+
+/// A raw extension; `value` is the DER inside the `extnValue` OCTET STRING.
+#[derive(Debug,Clone,PartialEq,Eq)]
+pub struct Extension {
+    pub oid: Oid,
+    pub critical: bool,
+    pub value: Vec<u8>,
+}
+
+impl Extension {
+    pub fn new(arcs: &[u64], critical: bool, value: Vec<u8>) -> Extension {
+        Extension {oid: Oid::new(arcs), critical, value}
+    }
+
+    /// subjectAltName; it must be critical when the subject name is empty.
+    pub fn subject_alt_name(names: &[GeneralName], critical: bool) -> Extension {
+        assert!(!names.is_empty(), "empty subjectAltName");
+        let value = asn1::encode(|w| w.write_sequence(|w| {
+            for name in names.iter() {
+                name.write(w);
+            }
+        }));
+        Extension::new(oid::SUBJECT_ALT_NAME, critical, value)
+    }
+
+    pub fn subject_key_id(id: &[u8]) -> Extension {
+        Extension::new(oid::SUBJECT_KEY_ID, false, asn1::encode(|w| w.write_octet_string(id)))
+    }
+
+    pub fn authority_key_id(id: &[u8]) -> Extension {
+        let value = asn1::encode(|w| w.write_sequence(|w| w.write_tlv(Tag::context_primitive(0), id)));
+        Extension::new(oid::AUTHORITY_KEY_ID, false, value)
+    }
+}
+
+#[derive(Debug,Clone,Copy,PartialEq,Eq)]
+pub struct BasicConstraints {
+    pub ca: bool,
+    /// Maximum number of intermediate CA certificates that may follow this one
+    pub path_len: Option<u32>,
+}
+
+impl BasicConstraints {
+    /// Critical basicConstraints, as RFC 5280 requires for CA certificates.
+    pub fn to_extension(&self) -> Extension {
+        let value = asn1::encode(|w| w.write_sequence(|w| {
+            if self.ca {
+                w.write_bool(true);
+            }
+            if let Some(n) = self.path_len {
+                w.write_u64(n as u64);
+            }
+        }));
+        Extension::new(oid::BASIC_CONSTRAINTS, true, value)
+    }
+}
+
+/// keyUsage bits; bit `i` of the named bit list is `1 << i`.
+#[derive(Debug,Clone,Copy,PartialEq,Eq)]
+pub struct KeyUsage(pub u16);
+
+impl KeyUsage {
+    pub const DIGITAL_SIGNATURE: KeyUsage = KeyUsage(1 << 0);
+    pub const NON_REPUDIATION: KeyUsage = KeyUsage(1 << 1);
+    pub const KEY_ENCIPHERMENT: KeyUsage = KeyUsage(1 << 2);
+    pub const DATA_ENCIPHERMENT: KeyUsage = KeyUsage(1 << 3);
+    pub const KEY_AGREEMENT: KeyUsage = KeyUsage(1 << 4);
+    pub const KEY_CERT_SIGN: KeyUsage = KeyUsage(1 << 5);
+    pub const CRL_SIGN: KeyUsage = KeyUsage(1 << 6);
+    pub const ENCIPHER_ONLY: KeyUsage = KeyUsage(1 << 7);
+    pub const DECIPHER_ONLY: KeyUsage = KeyUsage(1 << 8);
+
+    pub fn contains(&self, other: KeyUsage) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    /// Critical keyUsage, with trailing zero bits dropped as DER requires for named bits.
+    pub fn to_extension(&self) -> Extension {
+        let len = (16 - (self.0 & 0x1ff).leading_zeros()) as usize;
+        let mut data = vec![0u8;len.div_ceil(8)];
+        for i in (0..len).filter(|i| self.0 >> i & 1 == 1) {
+            data[i / 8] |= 0x80 >> (i % 8);
+        }
+        let unused_bits = (data.len() * 8 - len) as u8;
+        let value = asn1::encode(|w| w.write_bit_string(asn1::BitString {data: &data, unused_bits}));
+        Extension::new(oid::KEY_USAGE, true, value)
+    }
+}
+
+impl std::ops::BitOr for KeyUsage {
+    type Output = KeyUsage;
+
+    fn bitor(self, rhs: KeyUsage) -> KeyUsage {
+        KeyUsage(self.0 | rhs.0)
+    }
+}
+
+/// subjectAltName entry; forms other than these are kept as their tag and content.
+#[derive(Debug,Clone,PartialEq,Eq)]
+pub enum GeneralName {
+    Email(String),
+    Dns(String),
+    Uri(String),
+    Ip(IpAddr),
+    Other(Tag, Vec<u8>),
+}
+
+impl GeneralName {
+    pub(crate) fn write(&self, w: &mut asn1::Writer) {
+        match self {
+            GeneralName::Email(s) => w.write_tlv(Tag::context_primitive(1), ia5_bytes(s)),
+            GeneralName::Dns(s) => w.write_tlv(Tag::context_primitive(2), ia5_bytes(s)),
+            GeneralName::Uri(s) => w.write_tlv(Tag::context_primitive(6), ia5_bytes(s)),
+            GeneralName::Ip(IpAddr::V4(ip)) => w.write_tlv(Tag::context_primitive(7), &ip.octets()),
+            GeneralName::Ip(IpAddr::V6(ip)) => w.write_tlv(Tag::context_primitive(7), &ip.octets()),
+            GeneralName::Other(tag, content) => w.write_tlv(*tag, content),
+        }
+    }
+}
+
+fn ia5_bytes(s: &str) -> &[u8] {
+    assert!(s.is_ascii(), "invalid IA5String");
+    s.as_bytes()
+}
+
+/// The extensions of a certificate with the supported ones decoded.
+#[derive(Debug,Clone,Default,PartialEq,Eq)]
+pub struct Extensions {
+    pub all: Vec<Extension>,
+    pub basic_constraints: Option<BasicConstraints>,
+    pub key_usage: Option<KeyUsage>,
+    pub subject_alt_names: Vec<GeneralName>,
+    pub authority_key_id: Option<Vec<u8>>,
+    pub subject_key_id: Option<Vec<u8>>,
+}
+
+const SUPPORTED: [&[u64];5] = [
+    oid::BASIC_CONSTRAINTS, oid::KEY_USAGE, oid::SUBJECT_ALT_NAME, oid::AUTHORITY_KEY_ID, oid::SUBJECT_KEY_ID,
+];
+
+impl Extensions {
+    pub fn get(&self, arcs: &[u64]) -> Option<&Extension> {
+        self.all.iter().find(|e| e.oid.arcs() == arcs)
+    }
+
+    /// Critical extensions this module does not interpret; a verifier must reject them.
+    pub fn unhandled_critical(&self) -> Option<&Extension> {
+        self.all.iter().find(|e| e.critical && !SUPPORTED.contains(&e.oid.arcs()))
+    }
+
+    // Extensions ::= SEQUENCE SIZE (1..MAX) OF Extension
+    pub(crate) fn write(w: &mut asn1::Writer, exts: &[Extension]) {
+        w.write_sequence(|w| {
+            for ext in exts.iter() {
+                w.write_sequence(|w| {
+                    w.write_oid(&ext.oid);
+                    if ext.critical {
+                        w.write_bool(true);
+                    }
+                    w.write_octet_string(&ext.value);
+                });
+            }
+        });
+    }
+
+    pub(crate) fn read(r: &mut Reader) -> Result<Extensions,asn1::Error> {
+        let mut exts = Extensions::default();
+        let mut seq = r.read_sequence()?;
+        if seq.is_empty() {
+            return Err(asn1::Error::InvalidValue("empty extensions"));
+        }
+        while !seq.is_empty() {
+            let mut ext = seq.read_sequence()?;
+            let oid = ext.read_oid()?;
+            let critical = match ext.peek_tag() {
+                Some(Tag::BOOLEAN) => match ext.read_bool()? {
+                    true => true,
+                    false => return Err(asn1::Error::NonCanonical("explicit DEFAULT FALSE")),
+                },
+                _ => false,
+            };
+            let value = ext.read_octet_string()?.to_vec();
+            ext.finish()?;
+            if exts.get(oid.arcs()).is_some() {
+                return Err(asn1::Error::InvalidValue("duplicate extension"));
+            }
+            exts.decode(&oid, &value)?;
+            exts.all.push(Extension {oid, critical, value});
+        }
+        Ok(exts)
+    }
+
+    fn decode(&mut self, oid: &Oid, value: &[u8]) -> Result<(),asn1::Error> {
+        match oid.arcs() {
+            oid::BASIC_CONSTRAINTS => self.basic_constraints = Some(asn1::decode(value, |r| {
+                let mut seq = r.read_sequence()?;
+                let ca = match seq.peek_tag() {
+                    Some(Tag::BOOLEAN) => match seq.read_bool()? {
+                        true => true,
+                        false => return Err(asn1::Error::NonCanonical("explicit DEFAULT FALSE")),
+                    },
+                    _ => false,
+                };
+                let path_len = match seq.is_empty() {
+                    true => None,
+                    false => Some(u32::try_from(seq.read_u64()?).map_err(|_| asn1::Error::InvalidValue("pathLenConstraint too large"))?),
+                };
+                seq.finish()?;
+                Ok(BasicConstraints {ca, path_len})
+            })?),
+            oid::KEY_USAGE => self.key_usage = Some(asn1::decode(value, |r| {
+                let bits = r.read_bit_string()?;
+                if bits.len() > 9 {
+                    return Err(asn1::Error::InvalidValue("unknown keyUsage bits"));
+                }
+                Ok(KeyUsage((0..9).filter(|i| bits.bit(*i)).fold(0, |acc, i| acc | 1 << i)))
+            })?),
+            oid::SUBJECT_ALT_NAME => self.subject_alt_names = asn1::decode(value, |r| {
+                let mut seq = r.read_sequence()?;
+                let mut names = Vec::new();
+                while !seq.is_empty() {
+                    names.push(read_general_name(&mut seq)?);
+                }
+                if names.is_empty() {
+                    return Err(asn1::Error::InvalidValue("empty subjectAltName"));
+                }
+                Ok(names)
+            })?,
+            oid::AUTHORITY_KEY_ID => self.authority_key_id = asn1::decode(value, |r| {
+                let mut seq = r.read_sequence()?;
+                let id = seq.read_optional(Tag::context_primitive(0))?.map(|id| id.to_vec());
+                // authorityCertIssuer and authorityCertSerialNumber are not used
+                while !seq.is_empty() {
+                    seq.read_tlv()?;
+                }
+                Ok(id)
+            })?,
+            oid::SUBJECT_KEY_ID => self.subject_key_id = Some(asn1::decode(value, |r| r.read_octet_string())?.to_vec()),
+            _ => (),
+        }
+        Ok(())
+    }
+}
+
+fn ia5(content: &[u8]) -> Result<String,asn1::Error> {
+    match std::str::from_utf8(content) {
+        Ok(s) if s.is_ascii() => Ok(s.to_string()),
+        _ => Err(asn1::Error::InvalidValue("invalid IA5String")),
+    }
+}
+
+pub(crate) fn read_general_name(r: &mut Reader) -> Result<GeneralName,asn1::Error> {
+    let tlv = r.read_tlv()?;
+    Ok(match tlv.tag.0 {
+        0x81 => GeneralName::Email(ia5(tlv.content)?),
+        0x82 => GeneralName::Dns(ia5(tlv.content)?),
+        0x86 => GeneralName::Uri(ia5(tlv.content)?),
+        0x87 => match tlv.content.len() {
+            4 => GeneralName::Ip(IpAddr::from(<[u8;4]>::try_from(tlv.content).unwrap())),
+            16 => GeneralName::Ip(IpAddr::from(<[u8;16]>::try_from(tlv.content).unwrap())),
+            _ => return Err(asn1::Error::InvalidValue("invalid iPAddress")),
+        },
+        _ => GeneralName::Other(tlv.tag, tlv.content.to_vec()),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn extensions(exts: &[(&[u64], bool, Vec<u8>)]) -> Result<Extensions,asn1::Error> {
+        let exts: Vec<Extension> = exts.iter().map(|(arcs, critical, value)| Extension::new(arcs, *critical, value.clone())).collect();
+        let der = asn1::encode(|w| Extensions::write(w, &exts));
+        asn1::decode(&der, Extensions::read)
+    }
+
+    #[test]
+    fn test_extensions() {
+        let exts = extensions(&[
+            (oid::BASIC_CONSTRAINTS, true, vec![0x30, 0x06, 0x01, 0x01, 0xff, 0x02, 0x01, 0x00]),
+            (oid::KEY_USAGE, true, vec![0x03, 0x02, 0x01, 0x06]),
+            (oid::SUBJECT_ALT_NAME, false, vec![0x30, 0x0b, 0x82, 0x03, b'a', b'.', b'b', 0x87, 0x04, 10, 0, 0, 1]),
+            (oid::SUBJECT_KEY_ID, false, vec![0x04, 0x02, 0xab, 0xcd]),
+            (oid::AUTHORITY_KEY_ID, false, vec![0x30, 0x04, 0x80, 0x02, 0x12, 0x34]),
+            (&[1, 2, 3], false, vec![0x05, 0x00]),
+        ]).unwrap();
+        assert_eq!(exts.basic_constraints,Some(BasicConstraints {ca: true, path_len: Some(0)}));
+        assert_eq!(exts.key_usage,Some(KeyUsage::KEY_CERT_SIGN | KeyUsage::CRL_SIGN));
+        assert!(exts.key_usage.unwrap().contains(KeyUsage::KEY_CERT_SIGN));
+        assert!(!exts.key_usage.unwrap().contains(KeyUsage::DIGITAL_SIGNATURE));
+        assert_eq!(exts.subject_alt_names,[GeneralName::Dns("a.b".to_string()), GeneralName::Ip("10.0.0.1".parse().unwrap())]);
+        assert_eq!(exts.subject_key_id,Some(vec![0xab, 0xcd]));
+        assert_eq!(exts.authority_key_id,Some(vec![0x12, 0x34]));
+        assert_eq!(exts.all.len(),6);
+        assert!(exts.get(&[1, 2, 3]).is_some());
+        assert_eq!(exts.unhandled_critical(),None);
+
+        let exts = extensions(&[(&[1, 2, 3], true, vec![0x05, 0x00])]).unwrap();
+        assert_eq!(exts.unhandled_critical().unwrap().oid,Oid::new(&[1, 2, 3]));
+
+        assert!(extensions(&[]).is_err());
+        assert!(extensions(&[(oid::KEY_USAGE, false, vec![0x03, 0x02, 0x01, 0x06]), (oid::KEY_USAGE, false, vec![0x03, 0x02, 0x01, 0x06])]).is_err());
+        assert!(extensions(&[(oid::BASIC_CONSTRAINTS, false, vec![0x30, 0x03, 0x01, 0x01, 0x00])]).is_err());
+        assert!(extensions(&[(oid::SUBJECT_ALT_NAME, false, vec![0x30, 0x03, 0x87, 0x01, 0x00])]).is_err());
+        assert!(extensions(&[(oid::SUBJECT_KEY_ID, false, vec![0x04, 0x01])]).is_err());
+        assert_eq!(extensions(&[(oid::BASIC_CONSTRAINTS, false, vec![0x30, 0x00])]).unwrap().basic_constraints,
+            Some(BasicConstraints {ca: false, path_len: None}));
+    }
+
+    #[test]
+    fn test_encode() {
+        let names = [
+            GeneralName::Dns("a.b".to_string()),
+            GeneralName::Ip("10.0.0.1".parse().unwrap()),
+            GeneralName::Ip("::1".parse().unwrap()),
+            GeneralName::Email("x@a.b".to_string()),
+            GeneralName::Uri("https://a.b/".to_string()),
+        ];
+        let built = [
+            BasicConstraints {ca: true, path_len: Some(1)}.to_extension(),
+            BasicConstraints {ca: false, path_len: None}.to_extension(),
+            (KeyUsage::DIGITAL_SIGNATURE | KeyUsage::KEY_ENCIPHERMENT).to_extension(),
+            Extension::subject_alt_name(&names, false),
+            Extension::subject_key_id(&[1, 2, 3]),
+            Extension::authority_key_id(&[4, 5]),
+        ];
+        assert_eq!(built[1].value,[0x30, 0x00]);
+        assert_eq!(built[2].value,[0x03, 0x02, 0x05, 0xa0]);
+        assert_eq!(KeyUsage::DECIPHER_ONLY.to_extension().value,[0x03, 0x03, 0x07, 0x00, 0x80]);
+        assert_eq!(KeyUsage::CRL_SIGN.to_extension().value,[0x03, 0x02, 0x01, 0x02]);
+
+        for ext in built.iter() {
+            let exts = asn1::decode(&asn1::encode(|w| Extensions::write(w, std::slice::from_ref(ext))), Extensions::read).unwrap();
+            assert_eq!(exts.all,std::slice::from_ref(ext));
+        }
+        let all = asn1::decode(&asn1::encode(|w| Extensions::write(w, &built[2..])), Extensions::read).unwrap();
+        assert_eq!(all.key_usage,Some(KeyUsage::DIGITAL_SIGNATURE | KeyUsage::KEY_ENCIPHERMENT));
+        assert_eq!(all.subject_alt_names,names);
+        assert_eq!(all.subject_key_id,Some(vec![1, 2, 3]));
+        assert_eq!(all.authority_key_id,Some(vec![4, 5]));
+    }
+}
@@ -0,0 +1,459 @@
+// X.509 v3 certificates (RFC 5280)
+//
+// Parsing only checks the encoding; trust decisions are made by `verify_chain`.
+// `CertificateBuilder` and `RequestBuilder` issue certificates and PKCS#10 requests.
+mod builder;
+mod csr;
+pub mod extension;
+pub mod name;
+pub mod oid;
+mod verify;
+
+pub use self::builder::{BuildError, CertificateBuilder};
+pub use self::csr::{CertificateRequest, RequestBuilder};
+pub use self::extension::{BasicConstraints, Extension, Extensions, GeneralName, KeyUsage};
+pub use self::name::Name;
+pub use self::verify::{verify_chain, Error};
+
+use crate::rng::Rng;
+use crate::{rsa, sha256};
+use crate::utility::asn1::{self, Oid, Reader, Tag, Time};
+use crate::utility::pem::{self, Pem};
+use num::BigUint;
+
+/// Signature algorithm of a certificate; only RSA with SHA-256 can be verified.
+#[derive(Debug,Clone,PartialEq,Eq)]
+pub enum SignatureAlgorithm {
+    /// sha256WithRSAEncryption
+    RsaPkcs1Sha256,
+    /// RSASSA-PSS with SHA-256 and MGF1-SHA-256
+    RsaPssSha256 { salt_len: usize },
+    Other(Oid),
+}
+
+/// SubjectPublicKeyInfo; `parameters` is the raw DER of the algorithm parameters.
+#[derive(Debug,Clone,PartialEq,Eq)]
+pub struct SubjectPublicKeyInfo {
+    pub algorithm: Oid,
+    pub parameters: Option<Vec<u8>>,
+    pub key: Vec<u8>,
+}
+
+impl SubjectPublicKeyInfo {
+    pub fn from_rsa(key: &rsa::PublicKey) -> SubjectPublicKeyInfo {
+        SubjectPublicKeyInfo {algorithm: Oid::new(oid::RSA_ENCRYPTION), parameters: Some(vec![0x05, 0x00]), key: key.to_der()}
+    }
+
+    /// SHA-256 of the key truncated to 160 bits (RFC 7093 method 1), for key identifiers
+    pub fn key_id(&self) -> Vec<u8> {
+        sha256::sha256(&self.key)[..20].to_vec()
+    }
+
+    pub(crate) fn write(&self, w: &mut asn1::Writer) {
+        w.write_sequence(|w| {
+            w.write_sequence(|w| {
+                w.write_oid(&self.algorithm);
+                if let Some(params) = &self.parameters {
+                    w.write_raw(params);
+                }
+            });
+            w.write_bit_string_bytes(&self.key);
+        });
+    }
+
+    /// The RSA key, if the algorithm is rsaEncryption.
+    pub fn rsa_public_key(&self) -> Result<rsa::PublicKey,asn1::Error> {
+        if self.algorithm.arcs() != oid::RSA_ENCRYPTION {
+            return Err(asn1::Error::InvalidValue("not an RSA key"));
+        }
+        rsa::PublicKey::from_der(&self.key)
+    }
+
+    fn read(r: &mut Reader) -> Result<SubjectPublicKeyInfo,asn1::Error> {
+        let mut seq = r.read_sequence()?;
+        let mut alg = seq.read_sequence()?;
+        let algorithm = alg.read_oid()?;
+        let parameters = if alg.is_empty() {None} else {Some(alg.read_tlv()?.raw.to_vec())};
+        alg.finish()?;
+        let key = seq.read_bit_string()?.bytes()?.to_vec();
+        seq.finish()?;
+        Ok(SubjectPublicKeyInfo {algorithm, parameters, key})
+    }
+}
+
+// AlgorithmIdentifier of SHA-256, whose parameters are NULL or absent
+fn read_sha256(r: &mut Reader) -> Result<bool,asn1::Error> {
+    let mut seq = r.read_sequence()?;
+    let is_sha256 = seq.read_oid()?.arcs() == oid::SHA256;
+    if !seq.is_empty() {
+        seq.read_tlv()?;
+    }
+    seq.finish()?;
+    Ok(is_sha256)
+}
+
+fn write_sha256(w: &mut asn1::Writer) {
+    w.write_sequence(|w| {
+        w.write_oid(&Oid::new(oid::SHA256));
+        w.write_null();
+    });
+}
+
+impl SignatureAlgorithm {
+    /// Panics for `Other`.
+    pub(crate) fn write(&self, w: &mut asn1::Writer) {
+        w.write_sequence(|w| match self {
+            SignatureAlgorithm::RsaPkcs1Sha256 => {
+                w.write_oid(&Oid::new(oid::SHA256_WITH_RSA));
+                w.write_null();
+            },
+            SignatureAlgorithm::RsaPssSha256 {salt_len} => {
+                w.write_oid(&Oid::new(oid::RSASSA_PSS));
+                w.write_sequence(|w| {
+                    w.write_context(0, write_sha256);
+                    w.write_context(1, |w| w.write_sequence(|w| {
+                        w.write_oid(&Oid::new(oid::MGF1));
+                        write_sha256(w);
+                    }));
+                    if *salt_len != 20 {
+                        w.write_context(2, |w| w.write_u64(*salt_len as u64));
+                    }
+                });
+            },
+            SignatureAlgorithm::Other(oid) => panic!("cannot sign with {}", oid),
+        });
+    }
+
+    pub(crate) fn sign(&self, key: &rsa::PrivateKey, msg: &[u8]) -> Vec<u8> {
+        match self {
+            SignatureAlgorithm::RsaPkcs1Sha256 => key.sign_pkcs1v15_sha256(msg),
+            SignatureAlgorithm::RsaPssSha256 {salt_len} => key.sign_pss_sha256(msg, *salt_len, &mut Rng::new()),
+            SignatureAlgorithm::Other(oid) => panic!("cannot sign with {}", oid),
+        }
+    }
+
+    fn read(r: &mut Reader) -> Result<SignatureAlgorithm,asn1::Error> {
+        let mut seq = r.read_sequence()?;
+        let oid = seq.read_oid()?;
+        let alg = match oid.arcs() {
+            oid::SHA256_WITH_RSA => {
+                if !seq.is_empty() {
+                    seq.read_null()?;
+                }
+                SignatureAlgorithm::RsaPkcs1Sha256
+            },
+            oid::RSASSA_PSS => {
+                // RSASSA-PSS-params; every field defaults to SHA-1 era values
+                let mut params = seq.read_sequence()?;
+                let hash = match params.read_optional_context(0)? {
+                    Some(mut h) => read_sha256(&mut h)?,
+                    None => false,
+                };
+                let mgf = match params.read_optional_context(1)? {
+                    Some(mut m) => {
+                        let mut m = m.read_sequence()?;
+                        m.read_oid()?.arcs() == oid::MGF1 && read_sha256(&mut m)?
+                    },
+                    None => false,
+                };
+                let salt_len = match params.read_optional_context(2)? {
+                    Some(mut s) => s.read_u64()?,
+                    None => 20,
+                };
+                let trailer = match params.read_optional_context(3)? {
+                    Some(mut t) => t.read_u64()?,
+                    None => 1,
+                };
+                params.finish()?;
+                if hash && mgf && trailer == 1 && salt_len <= 1024 {
+                    SignatureAlgorithm::RsaPssSha256 {salt_len: salt_len as usize}
+                } else {
+                    SignatureAlgorithm::Other(oid)
+                }
+            },
+            _ => {
+                if !seq.is_empty() {
+                    seq.read_tlv()?;
+                }
+                SignatureAlgorithm::Other(oid)
+            },
+        };
+        seq.finish()?;
+        Ok(alg)
+    }
+}
+
+/// A parsed certificate that keeps its DER encoding.
+#[derive(Debug,Clone)]
+pub struct Certificate {
+    der: Vec<u8>,
+    tbs: Vec<u8>,
+    version: u8,
+    serial: BigUint,
+    issuer: Name,
+    subject: Name,
+    not_before: Time,
+    not_after: Time,
+    public_key: SubjectPublicKeyInfo,
+    extensions: Extensions,
+    signature_algorithm: SignatureAlgorithm,
+    signature: Vec<u8>,
+}
+
+impl PartialEq for Certificate {
+    fn eq(&self, other: &Certificate) -> bool {
+        self.der == other.der
+    }
+}
+
+impl Eq for Certificate {}
+
+impl Certificate {
+    pub fn from_der(der: &[u8]) -> Result<Certificate,asn1::Error> {
+        asn1::decode(der, |r| {
+            let mut cert = r.read_sequence()?;
+            let tbs_tlv = cert.clone().read_tlv()?;
+            let mut tbs = cert.read_sequence()?;
+
+            let version = match tbs.read_optional_context(0)? {
+                Some(mut v) => match v.read_u64()? {
+                    0 => return Err(asn1::Error::NonCanonical("explicit DEFAULT v1")),
+                    n @ 1..=2 => {
+                        v.finish()?;
+                        n as u8 + 1
+                    },
+                    _ => return Err(asn1::Error::InvalidValue("unknown certificate version")),
+                },
+                None => 1,
+            };
+            let serial = tbs.read_integer()?;
+            let inner_alg = tbs.clone().read_tlv()?.raw;
+            SignatureAlgorithm::read(&mut tbs)?;
+            let issuer = Name::read(&mut tbs)?;
+            let mut validity = tbs.read_sequence()?;
+            let (not_before, not_after) = (validity.read_time()?, validity.read_time()?);
+            validity.finish()?;
+            let subject = Name::read(&mut tbs)?;
+            let public_key = SubjectPublicKeyInfo::read(&mut tbs)?;
+
+            for n in 1..=2 {
+                if tbs.read_optional(Tag(0x80 | n))?.is_some() && version < 2 {
+                    return Err(asn1::Error::InvalidValue("unique identifier in a v1 certificate"));
+                }
+            }
+            let extensions = match tbs.read_optional_context(3)? {
+                Some(_) if version < 3 => return Err(asn1::Error::InvalidValue("extensions in a v1 or v2 certificate")),
+                Some(mut e) => {
+                    let exts = Extensions::read(&mut e)?;
+                    e.finish()?;
+                    exts
+                },
+                None => Extensions::default(),
+            };
+            tbs.finish()?;
+
+            let outer_alg = cert.clone().read_tlv()?.raw;
+            if outer_alg != inner_alg {
+                return Err(asn1::Error::InvalidValue("signature algorithm mismatch"));
+            }
+            let signature_algorithm = SignatureAlgorithm::read(&mut cert)?;
+            let signature = cert.read_bit_string()?.bytes()?.to_vec();
+            cert.finish()?;
+
+            Ok(Certificate {
+                der: der.to_vec(), tbs: tbs_tlv.raw.to_vec(), version, serial, issuer, subject,
+                not_before, not_after, public_key, extensions, signature_algorithm, signature,
+            })
+        })
+    }
+
+    /// Every `CERTIFICATE` block of a PEM bundle, in order.
+    pub fn from_pem_chain(text: &str) -> Result<Vec<Certificate>,pem::Error> {
+        let blocks = pem::parse(text, pem::Mode::Lax)?;
+        let certs = blocks.iter().filter(|b| b.label == "CERTIFICATE");
+        Ok(certs.map(|b| Certificate::from_der(&b.data)).collect::<Result<_,_>>()?)
+    }
+
+    pub fn to_der(&self) -> &[u8] {
+        &self.der
+    }
+
+    /// The signed `TBSCertificate` encoding
+    pub fn tbs_der(&self) -> &[u8] {
+        &self.tbs
+    }
+
+    pub fn version(&self) -> u8 {
+        self.version
+    }
+
+    pub fn serial(&self) -> &BigUint {
+        &self.serial
+    }
+
+    pub fn issuer(&self) -> &Name {
+        &self.issuer
+    }
+
+    pub fn subject(&self) -> &Name {
+        &self.subject
+    }
+
+    pub fn not_before(&self) -> Time {
+        self.not_before
+    }
+
+    pub fn not_after(&self) -> Time {
+        self.not_after
+    }
+
+    pub fn is_valid_at(&self, t: Time) -> bool {
+        self.not_before <= t && t <= self.not_after
+    }
+
+    pub fn public_key(&self) -> &SubjectPublicKeyInfo {
+        &self.public_key
+    }
+
+    pub fn extensions(&self) -> &Extensions {
+        &self.extensions
+    }
+
+    pub fn basic_constraints(&self) -> Option<BasicConstraints> {
+        self.extensions.basic_constraints
+    }
+
+    pub fn key_usage(&self) -> Option<KeyUsage> {
+        self.extensions.key_usage
+    }
+
+    pub fn subject_alt_names(&self) -> &[GeneralName] {
+        &self.extensions.subject_alt_names
+    }
+
+    pub fn authority_key_id(&self) -> Option<&[u8]> {
+        self.extensions.authority_key_id.as_deref()
+    }
+
+    pub fn subject_key_id(&self) -> Option<&[u8]> {
+        self.extensions.subject_key_id.as_deref()
+    }
+
+    pub fn signature_algorithm(&self) -> &SignatureAlgorithm {
+        &self.signature_algorithm
+    }
+
+    pub fn signature(&self) -> &[u8] {
+        &self.signature
+    }
+
+    pub fn is_self_issued(&self) -> bool {
+        self.issuer == self.subject
+    }
+
+    /// Checks the signature against the key of `issuer`; `Err` if either algorithm is
+    /// unsupported.
+    pub fn verify_signature(&self, issuer: &SubjectPublicKeyInfo) -> Result<bool,Error> {
+        let key = issuer.rsa_public_key().map_err(|_| Error::UnsupportedAlgorithm(issuer.algorithm.clone()))?;
+        match &self.signature_algorithm {
+            SignatureAlgorithm::RsaPkcs1Sha256 => Ok(key.verify_pkcs1v15_sha256(&self.tbs, &self.signature)),
+            SignatureAlgorithm::RsaPssSha256 {salt_len} => Ok(key.verify_pss_sha256(&self.tbs, &self.signature, *salt_len)),
+            SignatureAlgorithm::Other(oid) => Err(Error::UnsupportedAlgorithm(oid.clone())),
+        }
+    }
+}
+
+impl Pem for Certificate {
+    fn to_pem(&self) -> String {
+        pem::encode(&pem::Block::new("CERTIFICATE", self.der.clone()))
+    }
+
+    fn from_pem(text: &str) -> Result<Self,pem::Error> {
+        let block = pem::parse_label(text, "CERTIFICATE")?;
+        Ok(Certificate::from_der(&block.data)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::IpAddr;
+
+    pub(crate) const ROOT: &str = include_str!("testdata/root.pem");
+    pub(crate) const INTER: &str = include_str!("testdata/inter.pem");
+    pub(crate) const LEAF: &str = include_str!("testdata/leaf.pem");
+
+    #[test]
+    fn test_parse() {
+        // fixtures made by OpenSSL; the intermediate is signed with RSASSA-PSS
+        let root = Certificate::from_pem(ROOT).unwrap();
+        let inter = Certificate::from_pem(INTER).unwrap();
+        let leaf = Certificate::from_pem(LEAF).unwrap();
+
+        assert_eq!(root.version(),3);
+        assert_eq!(root.serial(),&BigUint::from(1u8));
+        assert_eq!(root.subject().to_string(),"CN=Crusto Root CA,O=Crusto Test,C=JP");
+        assert!(root.is_self_issued());
+        assert_eq!(root.signature_algorithm(),&SignatureAlgorithm::RsaPkcs1Sha256);
+        assert_eq!(root.basic_constraints(),Some(BasicConstraints {ca: true, path_len: None}));
+        assert_eq!(root.key_usage(),Some(KeyUsage::KEY_CERT_SIGN | KeyUsage::CRL_SIGN));
+        assert_eq!(root.public_key().rsa_public_key().unwrap().size(),256);
+        assert_eq!(root.to_pem(),ROOT);
+
+        assert_eq!(inter.issuer(),root.subject());
+        assert_eq!(inter.signature_algorithm(),&SignatureAlgorithm::RsaPssSha256 {salt_len: 32});
+        assert_eq!(inter.basic_constraints(),Some(BasicConstraints {ca: true, path_len: Some(0)}));
+        assert_eq!(inter.authority_key_id(),root.subject_key_id());
+        assert_eq!(inter.not_before().to_string(),"2024-01-01T00:00:00Z");
+        assert_eq!(inter.not_after().to_string(),"2030-01-01T00:00:00Z");
+
+        assert_eq!(leaf.serial(),&BigUint::parse_bytes(b"0123456789abcdef01", 16).unwrap());
+        assert_eq!(leaf.subject().common_name(),Some("service.internal"));
+        assert_eq!(leaf.issuer().common_name(),Some("Crusto Intermediate CA"));
+        assert_eq!(leaf.basic_constraints(),Some(BasicConstraints {ca: false, path_len: None}));
+        assert_eq!(leaf.key_usage(),Some(KeyUsage::DIGITAL_SIGNATURE | KeyUsage::KEY_ENCIPHERMENT));
+        assert_eq!(leaf.subject_alt_names(),[
+            GeneralName::Dns("service.internal".to_string()),
+            GeneralName::Dns("*.service.internal".to_string()),
+            GeneralName::Ip(IpAddr::from([10, 0, 0, 1])),
+            GeneralName::Email("ops@example.com".to_string()),
+            GeneralName::Uri("https://service.internal/".to_string()),
+        ]);
+        assert!(leaf.extensions().get(oid::EXT_KEY_USAGE).is_some());
+        assert!(leaf.is_valid_at(Time::new(2025, 6, 1, 0, 0, 0).unwrap()));
+        assert!(!leaf.is_valid_at(Time::new(2026, 1, 1, 0, 0, 1).unwrap()));
+
+        assert_eq!(leaf.verify_signature(inter.public_key()),Ok(true));
+        assert_eq!(inter.verify_signature(root.public_key()),Ok(true));
+        assert_eq!(root.verify_signature(root.public_key()),Ok(true));
+        assert_eq!(leaf.verify_signature(root.public_key()),Ok(false));
+
+        let chain = Certificate::from_pem_chain(&format!("{}{}{}", LEAF, "intermediate:\n", INTER)).unwrap();
+        assert_eq!(chain,[leaf, inter]);
+    }
+
+    #[test]
+    fn test_parse_invalid() {
+        let der = pem::parse_label(LEAF, "CERTIFICATE").unwrap().data;
+        assert!(Certificate::from_der(&der).is_ok());
+
+        let mut trailing = der.clone();
+        trailing.push(0);
+        assert_eq!(Certificate::from_der(&trailing),Err(asn1::Error::TrailingData));
+        assert!(Certificate::from_der(&der[..der.len() - 1]).is_err());
+
+        // sha256WithRSAEncryption in the TBS changed to sha384WithRSAEncryption
+        let mut mismatch = der.clone();
+        let alg = [0x06, 0x09, 0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x01, 0x0b];
+        let i = mismatch.windows(alg.len()).position(|w| w == alg).unwrap();
+        mismatch[i + 10] = 0x0c;
+        assert_eq!(Certificate::from_der(&mismatch),Err(asn1::Error::InvalidValue("signature algorithm mismatch")));
+
+        let mut tampered = der.clone();
+        let i = tampered.windows(16).position(|w| w == b"service.internal").unwrap();
+        tampered[i] = b'S';
+        let tampered = Certificate::from_der(&tampered).unwrap();
+        let inter = Certificate::from_pem(INTER).unwrap();
+        assert_eq!(tampered.verify_signature(inter.public_key()),Ok(false));
+    }
+}
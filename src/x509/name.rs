@@ -0,0 +1,192 @@
+// Distinguished names
+use super::oid;
+use crate::utility::asn1::{self, Oid, Reader};
+use std::fmt;
+
+/// An X.501 `Name`: relative distinguished names from the most significant (e.g. country)
+/// down, each a set of attribute type and string value pairs.
+///
+/// Names are compared by their DER encoding.
+#[derive(Debug,Clone)]
+pub struct Name {
+    rdns: Vec<Vec<(Oid, String)>>,
+    der: Vec<u8>,
+}
+
+impl PartialEq for Name {
+    fn eq(&self, other: &Name) -> bool {
+        self.der == other.der
+    }
+}
+
+impl Eq for Name {}
+
+impl Default for Name {
+    fn default() -> Name {
+        Name::new()
+    }
+}
+
+fn short_name(oid: &Oid) -> Option<&'static str> {
+    let names: [(&[u64], &str);9] = [
+        (oid::COMMON_NAME, "CN"), (oid::SERIAL_NUMBER, "serialNumber"), (oid::COUNTRY, "C"),
+        (oid::LOCALITY, "L"), (oid::STATE, "ST"), (oid::ORGANIZATION, "O"),
+        (oid::ORGANIZATIONAL_UNIT, "OU"), (oid::EMAIL_ADDRESS, "emailAddress"), (oid::DOMAIN_COMPONENT, "DC"),
+    ];
+    names.iter().find(|(arcs, _)| oid.arcs() == *arcs).map(|(_, name)| *name)
+}
+
+impl Name {
+    /// The empty name; add attributes with `with_attribute`.
+    pub fn new() -> Name {
+        Name {rdns: Vec::new(), der: vec![0x30, 0x00]}
+    }
+
+    /// Appends a single-valued RDN, more specific than the existing ones.
+    ///
+    /// Country, serial number, email address and domain component values are written as
+    /// PrintableString or IA5String as RFC 5280 expects; other values as UTF8String.
+    pub fn with_attribute(mut self, arcs: &[u64], value: &str) -> Self {
+        let oid = Oid::new(arcs);
+        let content = asn1::Reader::new(&self.der).read_tlv().unwrap().content.to_vec();
+        self.der = asn1::encode(|w| w.write_sequence(|w| {
+            w.write_raw(&content);
+            w.write_set(|w| w.write_sequence(|w| {
+                w.write_oid(&oid);
+                match arcs {
+                    oid::COUNTRY | oid::SERIAL_NUMBER => w.write_printable_string(value),
+                    oid::EMAIL_ADDRESS | oid::DOMAIN_COMPONENT => w.write_ia5_string(value),
+                    _ => w.write_utf8_string(value),
+                }
+            }));
+        }));
+        self.rdns.push(vec![(oid, value.to_string())]);
+        self
+    }
+
+    pub(crate) fn read(r: &mut Reader) -> Result<Name,asn1::Error> {
+        let der = r.clone().read_tlv()?.raw.to_vec();
+        let mut seq = r.read_sequence()?;
+        let mut rdns = Vec::new();
+        while !seq.is_empty() {
+            let mut set = seq.read_set()?;
+            let mut rdn = Vec::new();
+            while !set.is_empty() {
+                let mut atv = set.read_sequence()?;
+                rdn.push((atv.read_oid()?, atv.read_string()?.to_string()));
+                atv.finish()?;
+            }
+            if rdn.is_empty() {
+                return Err(asn1::Error::InvalidValue("empty RelativeDistinguishedName"));
+            }
+            rdns.push(rdn);
+        }
+        Ok(Name {rdns, der})
+    }
+
+    pub fn to_der(&self) -> &[u8] {
+        &self.der
+    }
+
+    pub fn rdns(&self) -> &[Vec<(Oid, String)>] {
+        &self.rdns
+    }
+
+    /// The first value of attribute `arcs`, e.g. `name.get(oid::ORGANIZATION)`.
+    pub fn get(&self, arcs: &[u64]) -> Option<&str> {
+        self.rdns.iter().flatten().find(|(t, _)| t.arcs() == arcs).map(|(_, v)| v.as_str())
+    }
+
+    /// The most specific common name.
+    pub fn common_name(&self) -> Option<&str> {
+        self.rdns.iter().rev().flatten().find(|(t, _)| t.arcs() == oid::COMMON_NAME).map(|(_, v)| v.as_str())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rdns.is_empty()
+    }
+}
+
+impl fmt::Display for Name {
+    /// RFC 4514 string, most specific RDN first, e.g. `CN=example.com,O=Example,C=JP`
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (i, rdn) in self.rdns.iter().rev().enumerate() {
+            if i > 0 {
+                write!(f, ",")?;
+            }
+            for (j, (t, v)) in rdn.iter().enumerate() {
+                if j > 0 {
+                    write!(f, "+")?;
+                }
+                match short_name(t) {
+                    Some(name) => write!(f, "{}=", name)?,
+                    None => write!(f, "{}=", t)?,
+                }
+                for (k, c) in v.chars().enumerate() {
+                    let edge = (k == 0 && (c == '#' || c == ' ')) || (k + 1 == v.chars().count() && c == ' ');
+                    if edge || ",+\"\\<>;".contains(c) {
+                        write!(f, "\\")?;
+                    }
+                    write!(f, "{}", c)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utility::asn1::Tag;
+
+    fn name(rdns: &[&[(&[u64], &str)]]) -> Name {
+        let der = asn1::encode(|w| w.write_sequence(|w| {
+            for rdn in rdns.iter() {
+                w.write_set(|w| {
+                    for (t, v) in rdn.iter() {
+                        w.write_sequence(|w| {
+                            w.write_oid(&Oid::new(t));
+                            w.write_utf8_string(v);
+                        });
+                    }
+                });
+            }
+        }));
+        asn1::decode(&der, Name::read).unwrap()
+    }
+
+    #[test]
+    fn test_name() {
+        let n = name(&[&[(oid::COUNTRY, "JP")], &[(oid::ORGANIZATION, "Crusto, Inc.")], &[(oid::COMMON_NAME, "a")],
+            &[(oid::COMMON_NAME, " b+c "), (&[2, 5, 4, 99], "#x")]]);
+        // attributes of a multi-valued RDN come back in DER SET order
+        assert_eq!(n.to_string(),"2.5.4.99=\\#x+CN=\\ b\\+c\\ ,CN=a,O=Crusto\\, Inc.,C=JP");
+        assert_eq!(n.get(oid::ORGANIZATION),Some("Crusto, Inc."));
+        assert_eq!(n.get(oid::COMMON_NAME),Some("a"));
+        assert_eq!(n.common_name(),Some(" b+c "));
+        assert_eq!(n.get(oid::LOCALITY),None);
+        assert_eq!(n,name(&[&[(oid::COUNTRY, "JP")], &[(oid::ORGANIZATION, "Crusto, Inc.")], &[(oid::COMMON_NAME, "a")],
+            &[(oid::COMMON_NAME, " b+c "), (&[2, 5, 4, 99], "#x")]]));
+        assert_ne!(n,name(&[&[(oid::COUNTRY, "JP")]]));
+
+        let built = Name::new().with_attribute(oid::COUNTRY, "JP").with_attribute(oid::ORGANIZATION, "Crusto, Inc.")
+            .with_attribute(oid::COMMON_NAME, "a");
+        assert_eq!(built.to_string(),"CN=a,O=Crusto\\, Inc.,C=JP");
+        assert_eq!(asn1::decode(built.to_der(), Name::read).unwrap(),built);
+        assert_eq!(&built.to_der()[..13],[0x30, 0x30, 0x31, 0x0b, 0x30, 0x09, 0x06, 0x03, 0x55, 0x04, 0x06, 0x13, 0x02]);
+        assert_eq!(Name::default(),Name::new());
+
+        let empty = name(&[]);
+        assert!(empty.is_empty() && empty.to_string().is_empty());
+        assert_eq!(empty.to_der(),[0x30, 0x00]);
+
+        let bad = [0x30, 0x02, 0x31, 0x00];
+        assert!(asn1::decode(&bad, Name::read).is_err());
+        let t61 = asn1::encode(|w| w.write_sequence(|w| w.write_set(|w| w.write_sequence(|w| {
+            w.write_oid(&Oid::new(oid::COMMON_NAME));
+            w.write_tlv(Tag(0x14), b"x");
+        }))));
+        assert!(asn1::decode(&t61, Name::read).is_err());
+    }
+}
@@ -0,0 +1,30 @@
+// Object identifiers used by certificates, as arcs to compare with `Oid::arcs`
+
+// algorithms
+pub const RSA_ENCRYPTION: &[u64] = &[1, 2, 840, 113549, 1, 1, 1];
+pub const MGF1: &[u64] = &[1, 2, 840, 113549, 1, 1, 8];
+pub const RSASSA_PSS: &[u64] = &[1, 2, 840, 113549, 1, 1, 10];
+pub const SHA256_WITH_RSA: &[u64] = &[1, 2, 840, 113549, 1, 1, 11];
+pub const SHA256: &[u64] = &[2, 16, 840, 1, 101, 3, 4, 2, 1];
+
+// name attributes
+pub const COMMON_NAME: &[u64] = &[2, 5, 4, 3];
+pub const SERIAL_NUMBER: &[u64] = &[2, 5, 4, 5];
+pub const COUNTRY: &[u64] = &[2, 5, 4, 6];
+pub const LOCALITY: &[u64] = &[2, 5, 4, 7];
+pub const STATE: &[u64] = &[2, 5, 4, 8];
+pub const ORGANIZATION: &[u64] = &[2, 5, 4, 10];
+pub const ORGANIZATIONAL_UNIT: &[u64] = &[2, 5, 4, 11];
+pub const EMAIL_ADDRESS: &[u64] = &[1, 2, 840, 113549, 1, 9, 1];
+pub const DOMAIN_COMPONENT: &[u64] = &[0, 9, 2342, 19200300, 100, 1, 25];
+
+// PKCS#9 attributes
+pub const EXTENSION_REQUEST: &[u64] = &[1, 2, 840, 113549, 1, 9, 14];
+
+// extensions
+pub const SUBJECT_KEY_ID: &[u64] = &[2, 5, 29, 14];
+pub const KEY_USAGE: &[u64] = &[2, 5, 29, 15];
+pub const SUBJECT_ALT_NAME: &[u64] = &[2, 5, 29, 17];
+pub const BASIC_CONSTRAINTS: &[u64] = &[2, 5, 29, 19];
+pub const AUTHORITY_KEY_ID: &[u64] = &[2, 5, 29, 35];
+pub const EXT_KEY_USAGE: &[u64] = &[2, 5, 29, 37];
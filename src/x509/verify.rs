@@ -0,0 +1,251 @@
+// Certification path building and validation
+use super::{Certificate, KeyUsage};
+use crate::utility::asn1::{Oid, Time};
+use std::fmt;
+
+/// Longest path tried, counting the leaf and the root
+const MAX_PATH_LEN: usize = 10;
+
+/// Most signatures verified while searching for a path
+const MAX_SIGNATURE_CHECKS: usize = 100;
+
+/// Verification error; indices are positions in the path, 0 being the leaf.
+#[derive(Debug,Clone,PartialEq,Eq)]
+pub enum Error {
+    NoCertificates,
+    /// No trusted path from the certificate at the index
+    UnknownIssuer(usize),
+    BadSignature(usize),
+    NotYetValid(usize),
+    Expired(usize),
+    /// An issuing certificate without cA or keyCertSign
+    NotCa(usize),
+    PathLenExceeded(usize),
+    UnhandledCriticalExtension(usize, Oid),
+    UnsupportedAlgorithm(Oid),
+    /// The path search gave up after `MAX_SIGNATURE_CHECKS` signatures
+    TooManySignatureChecks,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::NoCertificates => write!(f, "no certificates"),
+            Error::UnknownIssuer(i) => write!(f, "no trusted issuer for certificate {}", i),
+            Error::BadSignature(i) => write!(f, "bad signature on certificate {}", i),
+            Error::NotYetValid(i) => write!(f, "certificate {} is not yet valid", i),
+            Error::Expired(i) => write!(f, "certificate {} has expired", i),
+            Error::NotCa(i) => write!(f, "certificate {} may not issue certificates", i),
+            Error::PathLenExceeded(i) => write!(f, "path length constraint of certificate {} exceeded", i),
+            Error::UnhandledCriticalExtension(i, oid) => write!(f, "unhandled critical extension {} in certificate {}", oid, i),
+            Error::UnsupportedAlgorithm(oid) => write!(f, "unsupported algorithm {}", oid),
+            Error::TooManySignatureChecks => write!(f, "too many signature checks while building a path"),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+// checks on every certificate of the path
+fn check_certificate(cert: &Certificate, i: usize, now: Time) -> Result<(),Error> {
+    if now < cert.not_before() {
+        return Err(Error::NotYetValid(i));
+    }
+    if now > cert.not_after() {
+        return Err(Error::Expired(i));
+    }
+    if let Some(ext) = cert.extensions().unhandled_critical() {
+        return Err(Error::UnhandledCriticalExtension(i, ext.oid.clone()));
+    }
+    Ok(())
+}
+
+// checks on `issuer` at index `j` signing the path below it
+fn check_issuer(path: &[&Certificate], issuer: &Certificate, j: usize, now: Time, checks: &mut usize) -> Result<(),Error> {
+    check_certificate(issuer, j, now)?;
+    // trusted v1 roots predate basicConstraints
+    match issuer.basic_constraints() {
+        Some(bc) if bc.ca => {
+            // self-issued intermediates do not count towards the limit
+            let below = path[1..j].iter().filter(|c| !c.is_self_issued()).count();
+            if bc.path_len.is_some_and(|n| below > n as usize) {
+                return Err(Error::PathLenExceeded(j));
+            }
+        },
+        None if issuer.version() < 3 => (),
+        _ => return Err(Error::NotCa(j)),
+    }
+    if issuer.key_usage().is_some_and(|ku| !ku.contains(KeyUsage::KEY_CERT_SIGN)) {
+        return Err(Error::NotCa(j));
+    }
+
+    if *checks == MAX_SIGNATURE_CHECKS {
+        return Err(Error::TooManySignatureChecks);
+    }
+    *checks += 1;
+    let cert = path[j - 1];
+    match cert.verify_signature(issuer.public_key())? {
+        true => Ok(()),
+        false => Err(Error::BadSignature(j - 1)),
+    }
+}
+
+fn may_issue(issuer: &Certificate, cert: &Certificate) -> bool {
+    let ids_match = match (cert.authority_key_id(), issuer.subject_key_id()) {
+        (Some(a), Some(s)) => a == s,
+        _ => true,
+    };
+    issuer.subject() == cert.issuer() && ids_match
+}
+
+fn build<'a>(path: &mut Vec<&'a Certificate>, intermediates: &'a [Certificate], roots: &'a [Certificate], now: Time,
+        checks: &mut usize) -> Result<(),Error> {
+    let j = path.len();
+    let cert = path[j - 1];
+    let mut error = None;
+
+    for root in roots.iter().filter(|r| may_issue(r, cert)) {
+        path.push(root);
+        match check_issuer(path, root, j, now, checks) {
+            Ok(()) => return Ok(()),
+            Err(Error::TooManySignatureChecks) => return Err(Error::TooManySignatureChecks),
+            Err(e) => error = error.or(Some(e)),
+        }
+        path.pop();
+    }
+
+    if j + 1 < MAX_PATH_LEN {
+        let candidates: Vec<&Certificate> = intermediates.iter().filter(|c| may_issue(c, cert) && !path.contains(c)).collect();
+        for issuer in candidates {
+            path.push(issuer);
+            match check_issuer(path, issuer, j, now, checks).and_then(|_| build(path, intermediates, roots, now, checks)) {
+                Ok(()) => return Ok(()),
+                Err(Error::TooManySignatureChecks) => return Err(Error::TooManySignatureChecks),
+                Err(e) => error = error.or(Some(e)),
+            }
+            path.pop();
+        }
+    }
+
+    Err(error.unwrap_or(Error::UnknownIssuer(j - 1)))
+}
+
+/// Finds and validates a path from `chain[0]` to one of `roots` at time `now`, using the
+/// rest of `chain` as untrusted intermediates in any order.
+///
+/// Every certificate, roots included, must be within its validity period. Issuers
+/// must be CAs allowed to sign certificates, within their path length constraints, and
+/// their signatures must verify. Returns the path from the leaf to the root.
+///
+/// The search stops with `TooManySignatureChecks` after 100 signature verifications, so
+/// a chain padded with look-alike issuers cannot make it run for long.
+pub fn verify_chain<'a>(chain: &'a [Certificate], roots: &'a [Certificate], now: Time) -> Result<Vec<&'a Certificate>,Error> {
+    let leaf = chain.first().ok_or(Error::NoCertificates)?;
+    check_certificate(leaf, 0, now)?;
+    if roots.contains(leaf) {
+        return Ok(vec![leaf]);
+    }
+
+    let mut path = vec![leaf];
+    build(&mut path, &chain[1..], roots, now, &mut 0)?;
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::tests::{INTER, LEAF, ROOT};
+    use crate::utility::pem::Pem;
+
+    const SUB: &str = include_str!("testdata/sub.pem");
+    const LEAF2: &str = include_str!("testdata/leaf2.pem");
+
+    fn cert(pem: &str) -> Certificate {
+        Certificate::from_pem(pem).unwrap()
+    }
+
+    #[test]
+    fn test_verify_chain() {
+        let now = Time::new(2025, 6, 1, 0, 0, 0).unwrap();
+        let roots = [cert(ROOT)];
+        let chain = [cert(LEAF), cert(INTER)];
+
+        let path = verify_chain(&chain, &roots, now).unwrap();
+        assert_eq!(path,[&chain[0], &chain[1], &roots[0]]);
+
+        // intermediates in any order, with unrelated certificates mixed in
+        let shuffled = [cert(LEAF), cert(SUB), cert(ROOT), cert(INTER)];
+        assert_eq!(verify_chain(&shuffled, &roots, now).unwrap().len(),3);
+
+        assert_eq!(verify_chain(&roots, &roots, now).unwrap(),[&roots[0]]);
+        assert_eq!(verify_chain(&chain[..1], &roots, now),Err(Error::UnknownIssuer(0)));
+        assert_eq!(verify_chain(&chain, &[], now),Err(Error::UnknownIssuer(1)));
+        assert_eq!(verify_chain(&chain, &[cert(INTER)], now).unwrap().len(),2);
+        assert_eq!(verify_chain(&[], &roots, now),Err(Error::NoCertificates));
+    }
+
+    #[test]
+    fn test_validity() {
+        let roots = [cert(ROOT)];
+        let chain = [cert(LEAF), cert(INTER)];
+        let at = |y, m, d| Time::new(y, m, d, 0, 0, 0).unwrap();
+
+        assert_eq!(verify_chain(&chain, &roots, at(2024, 6, 1)),Err(Error::NotYetValid(0)));
+        assert_eq!(verify_chain(&chain, &roots, at(2026, 1, 2)),Err(Error::Expired(0)));
+        assert!(verify_chain(&chain, &roots, at(2025, 12, 31)).is_ok());
+        // the intermediate on its own after it expired, and the root after it expired
+        assert_eq!(verify_chain(&chain[1..], &roots, at(2031, 1, 1)),Err(Error::Expired(0)));
+        assert_eq!(verify_chain(&roots, &roots, at(2035, 1, 1)),Err(Error::Expired(0)));
+    }
+
+    #[test]
+    fn test_path_len() {
+        // the intermediate has pathlen:0 but issued another CA
+        let now = Time::new(2025, 6, 1, 0, 0, 0).unwrap();
+        let roots = [cert(ROOT)];
+        let chain = [cert(LEAF2), cert(SUB), cert(INTER)];
+        assert_eq!(verify_chain(&chain, &roots, now),Err(Error::PathLenExceeded(2)));
+        assert!(verify_chain(&chain[1..], &roots, now).is_ok());
+        // trusting the sub-CA directly gives a valid path
+        assert_eq!(verify_chain(&chain, &[cert(SUB)], now).unwrap().len(),2);
+    }
+
+    #[test]
+    fn test_not_ca_and_bad_signature() {
+        let now = Time::new(2025, 6, 1, 0, 0, 0).unwrap();
+        let roots = [cert(ROOT)];
+
+        // issued by the CA:FALSE leaf
+        let chain = [cert(include_str!("testdata/notca.pem")), cert(LEAF), cert(INTER)];
+        assert_eq!(verify_chain(&chain, &roots, now),Err(Error::NotCa(1)));
+
+        let mut der = cert(LEAF).to_der().to_vec();
+        let n = der.len();
+        der[n - 1] ^= 1;
+        let forged = Certificate::from_der(&der).unwrap();
+        assert_eq!(verify_chain(&[forged, cert(INTER)], &roots, now),Err(Error::BadSignature(0)));
+    }
+
+    #[test]
+    fn test_signature_check_limit() {
+        // copies of the intermediate with broken signatures, each costing two checks
+        let now = Time::new(2025, 6, 1, 0, 0, 0).unwrap();
+        let roots = [cert(ROOT)];
+        let forged = |k| {
+            let mut der = cert(INTER).to_der().to_vec();
+            let n = der.len();
+            der[n - 1 - k] ^= 1;
+            Certificate::from_der(&der).unwrap()
+        };
+
+        let mut chain = vec![cert(LEAF)];
+        chain.extend((0..40).map(forged));
+        chain.push(cert(INTER));
+        assert_eq!(verify_chain(&chain, &roots, now).unwrap().len(),3);
+
+        let mut chain = vec![cert(LEAF)];
+        chain.extend((0..60).map(forged));
+        chain.push(cert(INTER));
+        assert_eq!(verify_chain(&chain, &roots, now),Err(Error::TooManySignatureChecks));
+    }
+}